@@ -0,0 +1,75 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use passrus_core::{cryptman, Container, Entry, Vault};
+
+const PASS: &str = "password";
+
+/// builds a container holding n entries spread over ten children.
+fn container_with_entries(n: usize) -> Container {
+    let mut root = Container::new("root");
+    for c in 0..10 {
+        let mut child = Container::new(&format!("child{}", c));
+        for i in (c..n).step_by(10) {
+            child.add_entry(Entry::new(
+                &format!("user{}", i),
+                format!("password number {}", i).into_bytes(),
+                &format!("user{}@email.com", i),
+                &format!("site{}.com", i),
+            ));
+        }
+        root.add_child(child).unwrap();
+    }
+    root
+}
+
+fn bench_key_derivation(c: &mut Criterion) {
+    c.bench_function("pass_2_key", |b| {
+        b.iter(|| cryptman::pass_2_key(PASS, [7u8; 32]).unwrap())
+    });
+}
+
+fn bench_container_encryption(c: &mut Criterion) {
+    let (key, salt) = cryptman::pass_2_key(PASS, [7u8; 32]).unwrap();
+    let nonce = [3u8; 24];
+
+    let mut group = c.benchmark_group("encrypt_container");
+    for size in [10, 100, 1_000, 10_000] {
+        let mut container = container_with_entries(size);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| {
+                let json = container.to_json_string();
+                cryptman::encrypt_file_mem_with_salt(json.into_bytes(), "", &key, &nonce, &salt)
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_add_entry_and_save(c: &mut Criterion) {
+    let path = std::env::temp_dir().join("passrus-bench-vault");
+    let mut vault = Vault::new(path.to_str().unwrap(), "root");
+    vault.root = container_with_entries(1_000);
+
+    let mut i = 0;
+    c.bench_function("add_entry_and_save_1000", |b| {
+        b.iter(|| {
+            i += 1;
+            vault.root.add_entry(Entry::new(
+                "bench",
+                b"bench password".to_vec(),
+                "bench@email.com",
+                &format!("bench{}.com", i),
+            ));
+            vault.save(PASS).unwrap();
+        })
+    });
+    let _ = std::fs::remove_file(path);
+}
+
+criterion_group!(
+    benches,
+    bench_key_derivation,
+    bench_container_encryption,
+    bench_add_entry_and_save
+);
+criterion_main!(benches);
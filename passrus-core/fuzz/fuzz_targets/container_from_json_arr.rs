@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use passrus_core::Container;
+
+// decrypted vault content is parsed with Container::from_json_arr, and anything that
+// round trips through it has to serialise again.
+fuzz_target!(|data: &[u8]| {
+    let mut container = Container::new("");
+    if container.from_json_arr(data).is_ok() {
+        let _ = container.to_json_string();
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use passrus_core::cryptman;
+
+// arbitrary bytes as an encrypted file: the salt/nonce split at the tail must never panic,
+// whatever the length. every input runs argon2, so expect a low exec/s.
+fuzz_target!(|data: &[u8]| {
+    let _ = cryptman::decrypt_file_mem_gen_key(data.to_vec(), "", "password");
+});
@@ -0,0 +1,182 @@
+use crate::error::{PassrusError, Result};
+use crate::format;
+use argon2::Argon2;
+use hkdf::Hkdf;
+use sha2::Sha256;
+use chacha20poly1305::{
+    aead::{AeadInPlace, NewAead},
+    XChaCha20Poly1305,
+};
+use tracing::{debug, info, instrument};
+use rand::{rngs::OsRng, RngCore};
+use sha3::{Digest, Sha3_256};
+use std::str;
+
+/// takes a str, hashes it using sha3_256, returns a string of the hash.
+pub fn hash_str(input: &str)-> String{
+    let mut hasher = Sha3_256::new();
+    hasher.update(input);
+    let result = hasher.finalize();
+    hex::encode(result).to_owned()
+}
+
+///expects clear text passphrase as str, and the salt for the key as [u8;32]. provide an empty array for salt to generate a new one.
+#[instrument(skip_all)]
+pub fn pass_2_key(input: &str, mut salt: [u8; 32]) -> Result<([u8; 32], [u8; 32])> {
+    info!(target:"pass_2_key", "attempting to generate key...");
+    if salt.is_empty() {
+        debug!(target:"pass_2_key", "empty salt provided, generating salt.");
+        OsRng.fill_bytes(&mut salt);
+    }
+
+    let mut res = [0u8; 32];
+    Argon2::default().hash_password_into(input.as_bytes(), salt.as_slice(), &mut res)?;
+    info!(target:"pass_2_key", "successfully generated key from password & salt.");
+    Ok((res, salt))
+}
+
+/// encrypts data by loading it into memory wholly first. takes data as a Vec<u8> ,dest,key,nonce,and salt. encrypted using XChaCha20Poly1305.
+/// the data is encrypted in place, so the buffer passed in becomes the returned file without further copies.
+#[instrument(skip_all, fields(dist = dist, len = file_data.len()))]
+pub fn encrypt_file_mem_with_salt(
+    file_data: Vec<u8>,
+    dist: &str,
+    key: &[u8; 32],
+    nonce: &[u8; 24],
+    salt: &[u8; 32],
+) -> Result<Vec<u8>> {
+    info!(target:"encrypt_file_mem_with_salt", "attempting to encrypt data...");
+
+    let cipher = XChaCha20Poly1305::new(key.into());
+    debug!(target: "encrypt_file_mem_with_salt", "cipher generated from key successfully.");
+
+    debug!(target: "encrypt_file_mem_with_salt", "target data read successfully.");
+
+    let mut encrypted_file = file_data;
+    cipher
+        .encrypt_in_place(nonce.into(), b"", &mut encrypted_file)
+        .map_err(|err| PassrusError::Crypto(format!("Encrypting small file: {}", err)))?;
+    debug!(target: "encrypt_file_mem_with_salt", "target data encrypted successfully.");
+
+    format::append_trailer(&mut encrypted_file, nonce, salt);
+    debug!(target:"encrypt_file_mem_with_salt", "nonce and salt appended to encrypted content successfully");
+
+    if !dist.is_empty() {
+        info!(target:"encrypt_file_mem_with_salt", "filepath provided: attempting to write encrypted content to file:{}...",dist);
+        write_dist(dist, &encrypted_file)?;
+        info!(target:"encrypt_file_mem_with_salt", "encrypted content written to file written successfully");
+    }
+    Ok(encrypted_file)
+}
+
+/// writes data to the file at dist. it goes to a new temporary file next to dist first (random name, created
+/// exclusively with mode 0600, then given dist's permissions if it exists), which is synced and then renamed over dist,
+/// so a crash or a full disk leaves either the old file or the new one, never half of the new one. the temporary file
+/// may hold decrypted data, so it is shredded if anything fails, and the replaced file is overwritten (see
+/// shred::replace).
+#[cfg(feature = "fs")]
+fn write_dist(dist: &str, data: &[u8]) -> Result<()> {
+    use std::{fs::{self, OpenOptions}, io::Write};
+    #[cfg(unix)]
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut suffix = [0u8; 8];
+    OsRng.fill_bytes(&mut suffix);
+    let tmp = format!("{}.{}.tmp", dist, hex::encode(suffix));
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(&tmp)?;
+    let written = (|| {
+        if let Ok(metadata) = fs::metadata(dist) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.write_all(data)?;
+        file.sync_all()
+    })()
+    .map_err(PassrusError::from)
+    .and_then(|()| crate::shred::replace(&tmp, dist));
+    if let Err(err) = written {
+        drop(file);
+        let _ = crate::shred::remove_file(&tmp);
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// without the fs feature (e.g. on wasm32) there is no filesystem to write to, so asking for a destination file is an error.
+#[cfg(not(feature = "fs"))]
+fn write_dist(dist: &str, _data: &[u8]) -> Result<()> {
+    Err(PassrusError::Io(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("cannot write {}: passrus-core was built without the fs feature", dist),
+    )))
+}
+
+/// decrypt_file_mem_gen_key expects a path to the encrypted file,
+/// the destination for the decrypted content,
+/// and the password to decrypt it with.
+///
+/// the file is read in, then the salt and nonce are parsed
+/// from the end of the file content.
+///
+/// these are used to then decrypt the remaining file content.
+///
+/// the file is loaded into memory, not streamed, and decrypted in place.
+#[instrument(skip_all, fields(dist = dist, len = file_data.len()))]
+pub fn decrypt_file_mem_gen_key(
+    file_data: Vec<u8>,
+    dist: &str,
+    pass: &str,
+) -> Result<Vec<u8>> {
+    debug!(target:"decrypt_file_mem_gen_key","retrieving salt and nonce from tail of file.");
+    let salt = format::parse(&file_data)?.salt;
+    debug!(target:"decrypt_file_mem_gen_key","salt and nonce retrieved.");
+
+    //generate a key based on the pass and salt pulled from file
+    let key = pass_2_key(pass, salt)?.0;
+    debug!(target:"decrypt_file_mem_gen_key","generated key.");
+
+    //decrypt the content with the nonce pulled from file, and the generated key
+    let decrypted_file = decrypt_mem_with_key(file_data, &key)?;
+    info!(target:"decrypt_file_mem_gen_key","decrypted content successfully.");
+
+    //if a path is provided, write the decryted content to the given file.
+    if !dist.is_empty() {
+        info!(target:"decrypt_file_mem_gen_key","file path detected, writing decrypted content to destination file...");
+        write_dist(dist, &decrypted_file)?;
+    }
+
+    Ok(decrypted_file)
+}
+
+/// decrypt a file in the format v0 layout with a key given directly instead of one derived from a password, the salt
+/// in its trailer is ignored. the data is decrypted in place.
+pub fn decrypt_mem_with_key(file_data: Vec<u8>, key: &[u8; 32]) -> Result<Vec<u8>> {
+    let file = format::parse(&file_data)?;
+    let (nonce, ciphertext_len) = (file.nonce, file.ciphertext.len());
+    let cipher = XChaCha20Poly1305::new(key.into());
+
+    let mut decrypted_file = file_data;
+    decrypted_file.truncate(ciphertext_len);
+    cipher
+        .decrypt_in_place(&nonce.into(), b"", &mut decrypted_file)
+        .map_err(|_| PassrusError::WrongPassword)?;
+    Ok(decrypted_file)
+}
+
+/// prefix of the HKDF info for entry keys, so they can't collide with keys derived for anything else later.
+const ENTRY_KEY_INFO: &[u8] = b"passrus entry key ";
+
+/// the key an entry's password is encrypted with: HKDF-SHA256 of the key from the vault password, with the entry's id
+/// as info. a leaked entry key gives away neither the vault key nor any other entry's.
+pub fn entry_key(vault_key: &[u8; 32], entry_id: &str) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    // 32 bytes is well under HKDF-SHA256's 8160 byte limit, expand can't fail.
+    let _ = Hkdf::<Sha256>::new(None, vault_key).expand(
+        &[ENTRY_KEY_INFO, entry_id.as_bytes()].concat(),
+        &mut key,
+    );
+    key
+}
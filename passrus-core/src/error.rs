@@ -0,0 +1,109 @@
+use thiserror::Error;
+
+/// errors returned by passrus-core. callers can match on the variant to tell
+/// a wrong password apart from a damaged file or a missing entry.
+#[derive(Debug, Error)]
+pub enum PassrusError {
+    /// the AEAD tag did not verify. with XChaCha20Poly1305 a wrong password and
+    /// tampered ciphertext are indistinguishable, so both end up here.
+    #[error("wrong password, or the encrypted data has been modified")]
+    WrongPassword,
+
+    /// the data could not be parsed into salt, nonce and ciphertext.
+    #[error("corrupt file: {reason}")]
+    CorruptFile { reason: String },
+
+    /// no entry exists under the given key.
+    #[error("entry not found: {0}")]
+    EntryNotFound(String),
+
+    /// no container exists at the given path.
+    #[error("container not found: {0}")]
+    ContainerNotFound(String),
+
+    /// a password broke an enforced policy.
+    #[error("password violates the container policy: {}", crate::policy::describe(.0))]
+    PolicyViolation(Vec<crate::policy::Violation>),
+
+    /// a container path or name failed validation.
+    #[error("invalid path: {0}")]
+    InvalidPath(String),
+
+    /// the entry exists but is of a different kind than the operation needs.
+    #[error("{entry} is a {actual} entry, expected {expected}")]
+    WrongEntryKind {
+        entry: String,
+        expected: &'static str,
+        actual: &'static str,
+    },
+
+    /// the entry's fields don't make sense for its kind, e.g. a card number failing the Luhn check.
+    #[error("invalid entry: {0}")]
+    InvalidEntry(String),
+
+    /// every recovery code of the entry has been handed out.
+    #[error("no unused recovery codes left for {0}")]
+    RecoveryCodesExhausted(String),
+
+    /// an otpauth URI or migration payload could not be parsed.
+    #[error("invalid otp seed: {0}")]
+    InvalidOtp(String),
+
+    /// a wordlist for passphrases is too short or repeats words.
+    #[error("invalid wordlist: {0}")]
+    InvalidWordlist(String),
+
+    /// the entry or container was saved by someone else since the revision the caller read.
+    #[error("{target} changed meanwhile: expected revision {expected}, it is at {actual}")]
+    Conflict {
+        target: String,
+        expected: u64,
+        actual: u64,
+    },
+
+    /// a new master password scored below policy::MIN_MASTER_BITS, see policy::estimate_bits.
+    #[error("master password too weak: at most {bits} bits, at least {min} are needed")]
+    WeakPassword { bits: u32, min: u32 },
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("serialisation error: {0}")]
+    Serde(#[from] serde_json::Error),
+
+    /// key derivation or encryption failed for a reason other than a bad tag.
+    #[error("crypto error: {0}")]
+    Crypto(String),
+}
+
+impl PassrusError {
+    /// stable, machine readable identifier for the error, used as the error code in protocol responses.
+    pub fn code(&self) -> &'static str {
+        match self {
+            PassrusError::WrongPassword => "wrong_password",
+            PassrusError::CorruptFile { .. } => "corrupt_file",
+            PassrusError::EntryNotFound(_) => "entry_not_found",
+            PassrusError::ContainerNotFound(_) => "container_not_found",
+            PassrusError::InvalidPath(_) => "invalid_path",
+            PassrusError::PolicyViolation(_) => "policy_violation",
+            PassrusError::InvalidOtp(_) => "invalid_otp",
+            PassrusError::WrongEntryKind { .. } => "wrong_entry_kind",
+            PassrusError::InvalidEntry(_) => "invalid_entry",
+            PassrusError::RecoveryCodesExhausted(_) => "recovery_codes_exhausted",
+            PassrusError::InvalidWordlist(_) => "invalid_wordlist",
+            PassrusError::Conflict { .. } => "conflict",
+            PassrusError::WeakPassword { .. } => "weak_password",
+            PassrusError::Io(_) => "io",
+            PassrusError::Serde(_) => "serde",
+            PassrusError::Crypto(_) => "crypto",
+        }
+    }
+}
+
+impl From<argon2::Error> for PassrusError {
+    fn from(err: argon2::Error) -> Self {
+        PassrusError::Crypto(err.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, PassrusError>;
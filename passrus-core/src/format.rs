@@ -0,0 +1,84 @@
+//! byte level layout of an encrypted passrus file.
+//!
+//! the current layout (format v0) has no header. everything needed to decrypt is appended to the ciphertext:
+//!
+//! | offset   | length | content                                                            |
+//! |----------|--------|--------------------------------------------------------------------|
+//! | 0        | n      | XChaCha20Poly1305 ciphertext, ending in the 16 byte Poly1305 tag     |
+//! | n        | 24     | XChaCha20 nonce                                                    |
+//! | n + 24   | 32     | Argon2 salt                                                        |
+//!
+//! the key is 32 bytes of Argon2id (version 0x13, m=19456 KiB, t=2, p=1) over the utf-8 passphrase and the salt.
+//! there is no associated data. for a vault file the plaintext is the JSON serialisation of the root Container.
+//!
+//! golden files for this layout live in passrus-core/tests/golden, other implementations should be checked against them.
+use crate::error::{PassrusError, Result};
+use crate::{cryptman, Container};
+
+pub const TAG_LEN: usize = 16;
+pub const NONCE_LEN: usize = 24;
+pub const SALT_LEN: usize = 32;
+/// the smallest valid file: an empty plaintext still carries a tag, nonce and salt.
+pub const MIN_LEN: usize = TAG_LEN + NONCE_LEN + SALT_LEN;
+
+/// the parts of an encrypted file, the ciphertext is borrowed from the buffer it was parsed from.
+pub struct EncryptedFile<'a> {
+    pub ciphertext: &'a [u8],
+    pub nonce: [u8; NONCE_LEN],
+    pub salt: [u8; SALT_LEN],
+}
+
+impl EncryptedFile<'_> {
+    /// serialise back into the on disk layout: ciphertext, nonce, salt.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.ciphertext.len() + NONCE_LEN + SALT_LEN);
+        out.extend_from_slice(self.ciphertext);
+        append_trailer(&mut out, &self.nonce, &self.salt);
+        out
+    }
+}
+
+/// turn a ciphertext buffer into a complete file by appending the nonce and salt, without copying the ciphertext.
+pub fn append_trailer(ciphertext: &mut Vec<u8>, nonce: &[u8; NONCE_LEN], salt: &[u8; SALT_LEN]) {
+    ciphertext.reserve_exact(NONCE_LEN + SALT_LEN);
+    ciphertext.extend_from_slice(nonce);
+    ciphertext.extend_from_slice(salt);
+}
+
+/// split an encrypted file into ciphertext, nonce and salt. fails with CorruptFile if data is shorter than MIN_LEN.
+pub fn parse(data: &[u8]) -> Result<EncryptedFile<'_>> {
+    if data.len() < MIN_LEN {
+        return Err(PassrusError::CorruptFile {
+            reason: format!(
+                "{} bytes is too short to hold a tag, nonce and salt ({} bytes)",
+                data.len(),
+                MIN_LEN
+            ),
+        });
+    }
+
+    let (rest, salt_bytes) = data.split_at(data.len() - SALT_LEN);
+    let (ciphertext, nonce_bytes) = rest.split_at(rest.len() - NONCE_LEN);
+
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce.copy_from_slice(nonce_bytes);
+    let mut salt = [0u8; SALT_LEN];
+    salt.copy_from_slice(salt_bytes);
+
+    Ok(EncryptedFile {
+        ciphertext,
+        nonce,
+        salt,
+    })
+}
+
+/// check data against the spec. without a password only the layout can be checked,
+/// with one the content is also decrypted and must parse as a Container.
+pub fn validate(data: &[u8], pass: Option<&str>) -> Result<()> {
+    parse(data)?;
+    if let Some(pass) = pass {
+        let decrypted = cryptman::decrypt_file_mem_gen_key(data.to_vec(), "", pass)?;
+        Container::new("").from_json_arr(decrypted.as_slice())?;
+    }
+    Ok(())
+}
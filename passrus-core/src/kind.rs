@@ -0,0 +1,731 @@
+//! typed entry kinds. a plain login (username, password, email, url) is the default; other kinds carry their own data
+//! in Entry::kind, serialised with a "type" tag so new kinds can be added without touching existing vaults.
+//!
+//! each kind declares its fields in one table (Fields::FIELDS): every row is a FieldSpec (which fields are required,
+//! which are protected and masked when shown, which may repeat) together with how the field's values are read and
+//! where they are written. building a kind from "field: value" pairs (EntryKind::from_fields), validating it, showing
+//! it (EntryKind::display_fields) and sealing its protected fields (see Entry::seal_fields) all go through that table,
+//! so a field is protected everywhere by its flag alone.
+//!
+//! adding a kind touches: the variant and its struct, the struct's Fields impl, its tag in name and NAMES, and its
+//! arms in empty, data and data_mut.
+use crate::error::{PassrusError, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, mem};
+
+/// warn once this many or fewer recovery codes are left.
+pub const RECOVERY_CODES_LOW: usize = 3;
+
+/// one kind specific field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldSpec {
+    pub name: &'static str,
+    /// must be given and not empty.
+    pub required: bool,
+    /// masked when shown unless revealed.
+    pub protected: bool,
+    /// may be given more than once.
+    pub repeated: bool,
+}
+
+const fn field(name: &'static str, required: bool, protected: bool, repeated: bool) -> FieldSpec {
+    FieldSpec {
+        name,
+        required,
+        protected,
+        repeated,
+    }
+}
+
+/// where the values of one field are kept in a kind.
+enum Slot<'a> {
+    One(&'a mut String),
+    /// one value per line given, for repeated fields.
+    Many(&'a mut Vec<String>),
+    /// a comma separated list given as one value.
+    List(&'a mut Vec<String>),
+    Codes(&'a mut Vec<RecoveryCode>),
+}
+
+impl<'a> Slot<'a> {
+    /// store a value as given on input, already trimmed.
+    fn push(self, value: &str) {
+        match self {
+            Slot::One(one) => *one = value.to_owned(),
+            Slot::Many(many) => many.push(value.to_owned()),
+            Slot::List(list) => {
+                *list = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(str::to_owned)
+                    .collect()
+            }
+            Slot::Codes(_) if value.is_empty() => {}
+            Slot::Codes(codes) => codes.push(RecoveryCode {
+                code: value.to_owned(),
+                used_at: None,
+            }),
+        }
+    }
+
+    /// every stored value, for sealing.
+    fn values_mut(self) -> Vec<&'a mut String> {
+        match self {
+            Slot::One(one) => vec![one],
+            Slot::Many(many) | Slot::List(many) => many.iter_mut().collect(),
+            Slot::Codes(codes) => codes.iter_mut().map(|c| &mut c.code).collect(),
+        }
+    }
+}
+
+/// a row of a kind's field table: the spec, the field's values and where to write them.
+struct Field<T: 'static> {
+    spec: FieldSpec,
+    values: fn(&T) -> Vec<&str>,
+    slot: fn(&mut T) -> Slot<'_>,
+}
+
+const fn row<T>(
+    spec: FieldSpec,
+    values: fn(&T) -> Vec<&str>,
+    slot: fn(&mut T) -> Slot<'_>,
+) -> Field<T> {
+    Field { spec, values, slot }
+}
+
+/// the data of a kind other than a login, described by its field table. the other methods are hooks for what a
+/// kind does beyond the table.
+trait Fields: Sized + 'static {
+    const FIELDS: &'static [Field<Self>];
+
+    /// value of field spec as shown when not revealed, None if it is shown as is.
+    fn masked(&self, spec: &FieldSpec, value: &str) -> Option<String> {
+        spec.protected.then(|| mask(value))
+    }
+
+    /// (label, value) pairs for showing, see shown_fields.
+    fn display_fields(&self, reveal: bool) -> Vec<(&'static str, String)> {
+        shown_fields(self, reveal)
+    }
+
+    /// tidy values read from input, before they are validated.
+    fn tidy(&mut self) {}
+
+    /// the kind's own rules, beyond the required fields.
+    fn check(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// every non-empty value of kind in table order, labelled with its field and masked unless reveal is set.
+fn shown_fields<T: Fields>(kind: &T, reveal: bool) -> Vec<(&'static str, String)> {
+    let mut fields = Vec::new();
+    for field in T::FIELDS {
+        for value in (field.values)(kind).into_iter().filter(|v| !v.is_empty()) {
+            let masked = if reveal {
+                None
+            } else {
+                kind.masked(&field.spec, value)
+            };
+            fields.push((field.spec.name, masked.unwrap_or_else(|| value.to_owned())));
+        }
+    }
+    fields
+}
+
+/// the table of a kind behind a reference, see EntryKind::data.
+trait Data {
+    fn schema(&self) -> Vec<FieldSpec>;
+    fn values(&self, name: &str) -> Vec<&str>;
+    fn slot(&mut self, name: &str) -> Option<Slot<'_>>;
+    fn display_fields(&self, reveal: bool) -> Vec<(&'static str, String)>;
+    fn tidy(&mut self);
+    fn check(&self) -> Result<()>;
+}
+
+impl<T: Fields> Data for T {
+    fn schema(&self) -> Vec<FieldSpec> {
+        T::FIELDS.iter().map(|field| field.spec).collect()
+    }
+
+    fn values(&self, name: &str) -> Vec<&str> {
+        T::FIELDS
+            .iter()
+            .find(|field| field.spec.name == name)
+            .map_or_else(Vec::new, |field| (field.values)(self))
+    }
+
+    fn slot(&mut self, name: &str) -> Option<Slot<'_>> {
+        let field = T::FIELDS.iter().find(|field| field.spec.name == name)?;
+        Some((field.slot)(self))
+    }
+
+    fn display_fields(&self, reveal: bool) -> Vec<(&'static str, String)> {
+        Fields::display_fields(self, reveal)
+    }
+
+    fn tidy(&mut self) {
+        Fields::tidy(self)
+    }
+
+    fn check(&self) -> Result<()> {
+        Fields::check(self)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EntryKind {
+    #[default]
+    Login,
+    /// one time recovery codes for an account, consumed one by one.
+    RecoveryCodes(RecoveryCodes),
+    /// a payment card.
+    Card(Card),
+    /// personal details for filling in registration forms.
+    Identity(Identity),
+    /// free text: recovery phrases, runbooks, anything that isn't a login.
+    Note(Note),
+    /// another place the entry with the target id shows up, see Container::resolve.
+    Alias(Alias),
+}
+
+impl EntryKind {
+    /// every kind name, as written in the "type" tag.
+    pub const NAMES: [&'static str; 6] = [
+        "login",
+        "recovery_codes",
+        "card",
+        "identity",
+        "note",
+        "alias",
+    ];
+
+    /// the kind called name with nothing filled in, None for an unknown kind.
+    fn empty(name: &str) -> Option<Self> {
+        match name {
+            "login" => Some(EntryKind::Login),
+            "recovery_codes" => Some(EntryKind::RecoveryCodes(RecoveryCodes::default())),
+            "card" => Some(EntryKind::Card(Card::default())),
+            "identity" => Some(EntryKind::Identity(Identity::default())),
+            "note" => Some(EntryKind::Note(Note::default())),
+            "alias" => Some(EntryKind::Alias(Alias::default())),
+            _ => None,
+        }
+    }
+
+    /// the kind's field table, None for a login, whose fields live on Entry itself.
+    fn data(&self) -> Option<&dyn Data> {
+        match self {
+            EntryKind::Login => None,
+            EntryKind::RecoveryCodes(codes) => Some(codes),
+            EntryKind::Card(card) => Some(card),
+            EntryKind::Identity(identity) => Some(identity),
+            EntryKind::Note(note) => Some(note),
+            EntryKind::Alias(alias) => Some(alias),
+        }
+    }
+
+    fn data_mut(&mut self) -> Option<&mut dyn Data> {
+        match self {
+            EntryKind::Login => None,
+            EntryKind::RecoveryCodes(codes) => Some(codes),
+            EntryKind::Card(card) => Some(card),
+            EntryKind::Identity(identity) => Some(identity),
+            EntryKind::Note(note) => Some(note),
+            EntryKind::Alias(alias) => Some(alias),
+        }
+    }
+
+    /// the fields of the kind called name, None for an unknown kind.
+    pub fn schema_of(name: &str) -> Option<Vec<FieldSpec>> {
+        Self::empty(name).map(|kind| kind.schema())
+    }
+
+    pub fn schema(&self) -> Vec<FieldSpec> {
+        self.data().map_or_else(Vec::new, |data| data.schema())
+    }
+
+    /// build the kind called name from "field: value" pairs, with values trimmed. unknown fields, repeats of single
+    /// valued fields and missing required ones are rejected against the schema before the kind's own checks run.
+    pub fn from_fields(name: &str, fields: &[(&str, &str)]) -> Result<Self> {
+        let mut kind = Self::empty(name).ok_or_else(|| {
+            invalid(&format!(
+                "unknown entry kind {:?}, expected one of {}",
+                name,
+                Self::NAMES.join(", ")
+            ))
+        })?;
+        let schema = kind.schema();
+        for (i, (field, _)) in fields.iter().enumerate() {
+            let spec = schema
+                .iter()
+                .find(|spec| spec.name == field.trim())
+                .ok_or_else(|| {
+                    invalid(&format!(
+                        "{} entries have no field {:?}",
+                        name,
+                        field.trim()
+                    ))
+                })?;
+            if !spec.repeated
+                && fields[..i]
+                    .iter()
+                    .any(|(other, _)| other.trim() == spec.name)
+            {
+                return Err(invalid(&format!("{} is given more than once", spec.name)));
+            }
+        }
+
+        if let Some(data) = kind.data_mut() {
+            for (field, value) in fields {
+                if let Some(slot) = data.slot(field.trim()) {
+                    slot.push(value.trim());
+                }
+            }
+            data.tidy();
+        }
+        kind.validate()?;
+        Ok(kind)
+    }
+
+    /// check the required fields of the schema are set, then the kind's own rules (e.g. a card's Luhn check).
+    pub fn validate(&self) -> Result<()> {
+        for spec in self.schema().iter().filter(|spec| spec.required) {
+            if self
+                .field_values(spec.name)
+                .iter()
+                .all(|value| value.is_empty())
+            {
+                return Err(invalid(&format!(
+                    "{} entries need a {}",
+                    self.name(),
+                    spec.name
+                )));
+            }
+        }
+        self.data().map_or(Ok(()), |data| data.check())
+    }
+
+    pub fn is_login(&self) -> bool {
+        *self == EntryKind::Login
+    }
+
+    /// name of the kind as written in the "type" tag.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EntryKind::Login => "login",
+            EntryKind::RecoveryCodes(_) => "recovery_codes",
+            EntryKind::Card(_) => "card",
+            EntryKind::Identity(_) => "identity",
+            EntryKind::Note(_) => "note",
+            EntryKind::Alias(_) => "alias",
+        }
+    }
+
+    /// values of a kind specific field, for search and validation. empty if the kind has no such field.
+    pub fn field_values(&self, name: &str) -> Vec<&str> {
+        self.data().map_or_else(Vec::new, |data| data.values(name))
+    }
+
+    /// take the values of the schema's protected fields out of the kind, leaving them empty. Entry::seal_fields
+    /// encrypts them, put_protected puts them back.
+    pub(crate) fn take_protected(&mut self) -> BTreeMap<String, Vec<String>> {
+        let mut taken = BTreeMap::new();
+        if let Some(data) = self.data_mut() {
+            for spec in data.schema().into_iter().filter(|spec| spec.protected) {
+                let values = data
+                    .slot(spec.name)
+                    .map_or_else(Vec::new, Slot::values_mut)
+                    .into_iter()
+                    .map(mem::take)
+                    .collect();
+                taken.insert(spec.name.to_owned(), values);
+            }
+        }
+        taken
+    }
+
+    /// put values taken by take_protected back. fails if they don't fit the kind, e.g. after it was changed.
+    pub(crate) fn put_protected(&mut self, fields: BTreeMap<String, Vec<String>>) -> Result<()> {
+        let name = self.name();
+        for (field, values) in fields {
+            let targets = match self.data_mut() {
+                Some(data)
+                    if data
+                        .schema()
+                        .iter()
+                        .any(|spec| spec.name == field && spec.protected) =>
+                {
+                    data.slot(&field).map_or_else(Vec::new, Slot::values_mut)
+                }
+                _ => Vec::new(),
+            };
+            if targets.len() != values.len() {
+                return Err(PassrusError::CorruptFile {
+                    reason: format!("sealed field {} doesn't fit the {} entry", field, name),
+                });
+            }
+            for (target, value) in targets.into_iter().zip(values) {
+                *target = value;
+            }
+        }
+        Ok(())
+    }
+
+    /// the kind specific fields as (label, value) pairs for showing to the user.
+    /// secrets are masked unless reveal is set.
+    pub fn display_fields(&self, reveal: bool) -> Vec<(&'static str, String)> {
+        self.data()
+            .map_or_else(Vec::new, |data| data.display_fields(reveal))
+    }
+}
+
+/// a payment card. number, cvv and pin are protected: sealed under the entry's key in the vault file (see
+/// Entry::seal_fields) and masked when shown.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Card {
+    pub cardholder: String,
+    /// digits only.
+    pub number: String,
+    /// "MM/YY".
+    pub expiry: String,
+    pub cvv: String,
+    pub pin: String,
+}
+
+impl Fields for Card {
+    const FIELDS: &'static [Field<Self>] = &[
+        row(
+            field("cardholder", false, false, false),
+            |card| vec![card.cardholder.as_str()],
+            |card| Slot::One(&mut card.cardholder),
+        ),
+        row(
+            field("number", true, true, false),
+            |card| vec![card.number.as_str()],
+            |card| Slot::One(&mut card.number),
+        ),
+        row(
+            field("expiry", true, false, false),
+            |card| vec![card.expiry.as_str()],
+            |card| Slot::One(&mut card.expiry),
+        ),
+        row(
+            field("cvv", false, true, false),
+            |card| vec![card.cvv.as_str()],
+            |card| Slot::One(&mut card.cvv),
+        ),
+        row(
+            field("pin", false, true, false),
+            |card| vec![card.pin.as_str()],
+            |card| Slot::One(&mut card.pin),
+        ),
+    ];
+
+    /// the number keeps its last 4 characters.
+    fn masked(&self, spec: &FieldSpec, value: &str) -> Option<String> {
+        match spec.name {
+            "number" => {
+                let skip = value.chars().count().saturating_sub(4);
+                Some(format!(
+                    "•••• {}",
+                    value.chars().skip(skip).collect::<String>()
+                ))
+            }
+            _ => spec.protected.then(|| mask(value)),
+        }
+    }
+
+    /// spaces and dashes in the number are dropped.
+    fn tidy(&mut self) {
+        self.number.retain(|c| !matches!(c, ' ' | '-'));
+    }
+
+    fn check(&self) -> Result<()> {
+        self.validate()
+    }
+}
+
+impl Card {
+    /// build a card from "field: value" pairs (cardholder, number, expiry, cvv, pin), checking the number and expiry.
+    /// spaces and dashes in the number are dropped.
+    pub fn from_fields<'a, I: IntoIterator<Item = (&'a str, &'a str)>>(fields: I) -> Result<Self> {
+        let fields: Vec<(&str, &str)> = fields.into_iter().collect();
+        match EntryKind::from_fields("card", &fields)? {
+            EntryKind::Card(card) => Ok(card),
+            other => Err(invalid(&format!(
+                "expected a card, built a {}",
+                other.name()
+            ))),
+        }
+    }
+
+    /// the number must be 12-19 digits passing the Luhn check, the expiry MM/YY, cvv and pin digits if set.
+    pub fn validate(&self) -> Result<()> {
+        let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+        if !(12..=19).contains(&self.number.len()) || !digits(&self.number) || !luhn(&self.number) {
+            return Err(invalid("card number is not a valid 12-19 digit number"));
+        }
+        let expiry_ok = match self.expiry.split_once('/') {
+            Some((month, year)) => {
+                month.len() == 2
+                    && year.len() == 2
+                    && digits(year)
+                    && matches!(month.parse::<u8>(), Ok(1..=12))
+            }
+            None => false,
+        };
+        if !expiry_ok {
+            return Err(invalid("card expiry must be MM/YY"));
+        }
+        if !digits(&self.cvv) || !digits(&self.pin) {
+            return Err(invalid("cvv and pin must be digits"));
+        }
+        Ok(())
+    }
+}
+
+/// a titled block of text. notes need no username, password or url, the entry key is usually the title.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Note {
+    pub title: String,
+    pub body: String,
+}
+
+impl Fields for Note {
+    const FIELDS: &'static [Field<Self>] = &[
+        row(
+            field("title", true, false, false),
+            |note| vec![note.title.as_str()],
+            |note| Slot::One(&mut note.title),
+        ),
+        row(
+            field("body", false, true, false),
+            |note| vec![note.body.as_str()],
+            |note| Slot::One(&mut note.body),
+        ),
+    ];
+
+    /// the body only says how long it is.
+    fn masked(&self, spec: &FieldSpec, value: &str) -> Option<String> {
+        spec.protected
+            .then(|| format!("{} lines, hidden", value.lines().count()))
+    }
+}
+
+/// a second path to an existing entry. the alias holds nothing itself, reads and updates through it reach the entry
+/// with the target id.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Alias {
+    pub target: String,
+}
+
+impl Fields for Alias {
+    const FIELDS: &'static [Field<Self>] = &[row(
+        field("target", true, false, false),
+        |alias| vec![alias.target.as_str()],
+        |alias| Slot::One(&mut alias.target),
+    )];
+
+    fn display_fields(&self, _reveal: bool) -> Vec<(&'static str, String)> {
+        vec![("alias of", self.target.clone())]
+    }
+}
+
+/// a person's details. fields listed in protected are masked when shown, like passwords.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Identity {
+    pub name: String,
+    /// postal addresses, one string each, lines separated by ", ".
+    pub addresses: Vec<String>,
+    pub phone: String,
+    pub national_id: String,
+    pub passport_number: String,
+    /// names of the fields to mask.
+    pub protected: Vec<String>,
+}
+
+impl Fields for Identity {
+    const FIELDS: &'static [Field<Self>] = &[
+        row(
+            field("name", true, false, false),
+            |identity| vec![identity.name.as_str()],
+            |identity| Slot::One(&mut identity.name),
+        ),
+        row(
+            field("address", false, false, true),
+            |identity| identity.addresses.iter().map(String::as_str).collect(),
+            |identity| Slot::Many(&mut identity.addresses),
+        ),
+        row(
+            field("phone", false, false, false),
+            |identity| vec![identity.phone.as_str()],
+            |identity| Slot::One(&mut identity.phone),
+        ),
+        row(
+            field("national_id", false, true, false),
+            |identity| vec![identity.national_id.as_str()],
+            |identity| Slot::One(&mut identity.national_id),
+        ),
+        row(
+            field("passport_number", false, true, false),
+            |identity| vec![identity.passport_number.as_str()],
+            |identity| Slot::One(&mut identity.passport_number),
+        ),
+        // comma separated names of the fields to mask instead of the protected ones above.
+        row(
+            field("protect", false, false, false),
+            |identity| identity.protected.iter().map(String::as_str).collect(),
+            |identity| Slot::List(&mut identity.protected),
+        ),
+    ];
+
+    /// the fields listed in protected are masked, whatever the table says.
+    fn masked(&self, spec: &FieldSpec, value: &str) -> Option<String> {
+        self.is_protected(spec.name).then(|| mask(value))
+    }
+
+    /// protect is how the others are shown, not a detail of its own.
+    fn display_fields(&self, reveal: bool) -> Vec<(&'static str, String)> {
+        let mut fields = shown_fields(self, reveal);
+        fields.retain(|(label, _)| Self::is_data_field(label));
+        fields
+    }
+
+    fn check(&self) -> Result<()> {
+        match self.protected.iter().find(|f| !Self::is_data_field(f)) {
+            Some(unknown) => Err(invalid(&format!(
+                "can't protect unknown identity field {:?}",
+                unknown
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Identity {
+    /// build an identity from "field: value" pairs. address may be repeated, protect takes a comma separated list of
+    /// field names and defaults to the schema's protected fields.
+    pub fn from_fields<'a, I: IntoIterator<Item = (&'a str, &'a str)>>(fields: I) -> Result<Self> {
+        let fields: Vec<(&str, &str)> = fields.into_iter().collect();
+        match EntryKind::from_fields("identity", &fields)? {
+            EntryKind::Identity(identity) => Ok(identity),
+            other => Err(invalid(&format!(
+                "expected an identity, built a {}",
+                other.name()
+            ))),
+        }
+    }
+
+    /// an identity field holding data, i.e. anything in the schema but protect.
+    fn is_data_field(name: &str) -> bool {
+        name != "protect" && Self::FIELDS.iter().any(|field| field.spec.name == name)
+    }
+
+    pub fn is_protected(&self, field: &str) -> bool {
+        self.protected.iter().any(|f| f == field)
+    }
+}
+
+impl Default for Identity {
+    fn default() -> Self {
+        Identity {
+            name: String::new(),
+            addresses: Vec::new(),
+            phone: String::new(),
+            national_id: String::new(),
+            passport_number: String::new(),
+            protected: Self::FIELDS
+                .iter()
+                .filter(|field| field.spec.protected)
+                .map(|field| field.spec.name.to_owned())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecoveryCodes {
+    pub codes: Vec<RecoveryCode>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecoveryCode {
+    pub code: String,
+    /// unix time the code was handed out, None while unused.
+    pub used_at: Option<u64>,
+}
+
+impl Fields for RecoveryCodes {
+    const FIELDS: &'static [Field<Self>] = &[row(
+        field("code", true, true, true),
+        |codes| codes.codes.iter().map(|c| c.code.as_str()).collect(),
+        |codes| Slot::Codes(&mut codes.codes),
+    )];
+
+    /// how many are left, and the unused codes only when revealed.
+    fn display_fields(&self, reveal: bool) -> Vec<(&'static str, String)> {
+        let mut fields = vec![(
+            "remaining",
+            format!("{} of {}", self.remaining(), self.codes.len()),
+        )];
+        if reveal {
+            fields.extend(
+                self.codes
+                    .iter()
+                    .filter(|c| c.used_at.is_none())
+                    .map(|c| ("code", c.code.clone())),
+            );
+        }
+        fields
+    }
+}
+
+impl RecoveryCodes {
+    /// codes as issued by the site, in order. blank codes are dropped.
+    pub fn new<I: IntoIterator<Item = String>>(codes: I) -> Self {
+        let mut new = RecoveryCodes::default();
+        for code in codes {
+            Slot::Codes(&mut new.codes).push(code.trim());
+        }
+        new
+    }
+
+    /// how many codes haven't been handed out yet.
+    pub fn remaining(&self) -> usize {
+        self.codes.iter().filter(|c| c.used_at.is_none()).count()
+    }
+
+    /// hand out the first unused code and mark it used at now. None once all are used.
+    pub fn use_next(&mut self, now: u64) -> Option<String> {
+        let next = self.codes.iter_mut().find(|c| c.used_at.is_none())?;
+        next.used_at = Some(now);
+        Some(next.code.clone())
+    }
+}
+
+/// a value masked character for character.
+fn mask(value: &str) -> String {
+    "•".repeat(value.chars().count())
+}
+
+fn luhn(number: &str) -> bool {
+    let sum: u32 = number
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, d)| match (i % 2, d * 2) {
+            (0, _) => d,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+fn invalid(reason: &str) -> PassrusError {
+    PassrusError::InvalidEntry(reason.to_owned())
+}
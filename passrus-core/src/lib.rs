@@ -0,0 +1,61 @@
+//! core storage format for passrus.
+//!
+//! - [`passman`] holds the data model: a tree of [`Container`]s holding [`Entry`]s.
+//! - [`kind`] has the typed entry kinds beyond plain logins, such as recovery codes, cards, identities and notes.
+//! - [`link`] has the typed links between entries, such as the account used to recover another.
+//! - [`matching`] decides which entries are offered on a page, from each entry's url matching rules.
+//! - [`passphrase`] generates diceware style passphrases from a checked wordlist.
+//! - [`path`] has the typed [`ContainerPath`] ("a/b/c") and [`EntryRef`] used to address the tree.
+//! - [`otp`] parses one time password seeds from otpauth:// URIs and authenticator app exports.
+//! - [`policy`] defines per container password policies checked when entries are added.
+//! - [`revision`] numbers every change to entries and containers, so concurrent edits fail instead of clobbering.
+//! - [`rotation`] tracks how often entry passwords should change and which are due.
+//! - [`schema`] versions the serialised container shape and migrates older vaults on load.
+//! - [`cryptman`] handles key derivation, per entry keys included, and XChaCha20Poly1305 encryption of byte buffers.
+//! - [`format`] specifies the byte layout of encrypted files and validates data against it.
+//! - [`error`] defines [`PassrusError`], returned by every fallible call in the crate.
+//! - [`vault`] ties the two together, opening and saving an encrypted container tree on disk.
+//! - [`paper`] splits an encrypted vault into text frames for printing as QR codes, and joins them back.
+//! - [`shard`] stores large vaults as a directory with a file per top-level container, so saves rewrite less.
+//! - [`share`] exports a single entry under a one-time passphrase for handing to someone else.
+//! - [`shred`] overwrites files that held secrets before removing them.
+//! - [`subtree`] exports a container subtree under its own password and imports it into another vault.
+//! - [`vaultfile`] encrypts arbitrary files with the same primitives, streamed in chunks.
+//!
+//! file access is behind the default `fs` feature. building with
+//! `--no-default-features` leaves only in-memory encryption and the data model,
+//! which compiles to wasm32 so an exported vault can be decrypted client side.
+//!
+//! embedders (GUIs, scripts, other tools) can depend on this crate directly
+//! instead of going through the passrus binary.
+pub mod cryptman;
+pub mod error;
+pub mod format;
+pub mod kind;
+pub mod link;
+pub mod matching;
+pub mod otp;
+pub mod paper;
+pub mod passman;
+pub mod passphrase;
+pub mod path;
+pub mod policy;
+pub mod revision;
+pub mod rotation;
+pub mod schema;
+pub mod share;
+#[cfg(feature = "fs")]
+pub mod shard;
+pub mod subtree;
+#[cfg(feature = "fs")]
+pub mod shred;
+#[cfg(feature = "fs")]
+pub mod vault;
+#[cfg(feature = "fs")]
+pub mod vaultfile;
+
+pub use error::PassrusError;
+pub use path::{ContainerPath, EntryRef};
+pub use passman::{flatten, get_entries_by_field, Container, Entry, EntryIter};
+#[cfg(feature = "fs")]
+pub use vault::{Layout, Vault};
@@ -0,0 +1,66 @@
+//! typed links between entries, modelling how credentials depend on each other: the mail account a site's password
+//! resets go to, where its second factor lives, the login it signs in through.
+//!
+//! a link is stored on the entry it starts from and points at the target's id, so it survives renames and moves.
+//! Container::links_to finds the links pointing at an entry.
+use crate::error::{PassrusError, Result};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// what the target of a link is to the entry holding it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Relation {
+    /// the account that recovers this one, usually the email password resets go to.
+    Recovery,
+    /// where the second factor lives: recovery codes, or a login whose otp seed is used.
+    TwoFactor,
+    /// the login this account signs in through (single sign on).
+    SignsInWith,
+    /// anything else worth navigating to.
+    Related,
+}
+
+impl Relation {
+    pub const NAMES: [&'static str; 4] = ["recovery", "two_factor", "signs_in_with", "related"];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Relation::Recovery => "recovery",
+            Relation::TwoFactor => "two_factor",
+            Relation::SignsInWith => "signs_in_with",
+            Relation::Related => "related",
+        }
+    }
+}
+
+impl fmt::Display for Relation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Relation {
+    type Err = PassrusError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "recovery" => Ok(Relation::Recovery),
+            "two_factor" => Ok(Relation::TwoFactor),
+            "signs_in_with" => Ok(Relation::SignsInWith),
+            "related" => Ok(Relation::Related),
+            _ => Err(PassrusError::InvalidEntry(format!(
+                "unknown link type {:?}, expected one of {}",
+                s,
+                Self::NAMES.join(", ")
+            ))),
+        }
+    }
+}
+
+/// a link from the entry holding it to the entry with the target id.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Link {
+    pub relation: Relation,
+    pub target: String,
+}
@@ -0,0 +1,136 @@
+//! deciding whether an entry's credentials belong on a page, for autofill.
+//!
+//! each entry carries its own matching rules (Entry::matching_rules). without any, the page has to be on the entry's
+//! base domain, which is what most sites want; stricter or looser entries say so explicitly.
+use crate::error::{PassrusError, Result};
+use crate::{policy, Entry};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// second level labels under which country code domains are registered, so "example.co.uk" is a base domain and
+/// "co.uk" isn't. a heuristic in place of the public suffix list, which is too big to carry here.
+const SECOND_LEVEL: &[&str] = &["ac", "co", "com", "edu", "gov", "net", "or", "org"];
+
+/// one way of comparing the url of the page asking for credentials with the entry's url.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "pattern", rename_all = "snake_case")]
+pub enum MatchRule {
+    /// the page's host is the entry's host.
+    ExactHost,
+    /// the page is on the entry's base domain or any subdomain of it, the default.
+    BaseDomain,
+    /// the page's host is the entry's host or below it: app.example.com matches eu.app.example.com but not example.com.
+    Subdomains,
+    /// the page's whole url matches the regex.
+    Regex(String),
+    /// the entry is never offered, whatever else is set.
+    Never,
+}
+
+impl MatchRule {
+    /// parse a rule as written on the command line: exact-host, base-domain, subdomains, never or regex:<pattern>.
+    pub fn parse(s: &str) -> Result<Self> {
+        let rule = match s {
+            "exact-host" => MatchRule::ExactHost,
+            "base-domain" => MatchRule::BaseDomain,
+            "subdomains" => MatchRule::Subdomains,
+            "never" => MatchRule::Never,
+            _ => match s.strip_prefix("regex:") {
+                Some(pattern) => MatchRule::Regex(pattern.to_owned()),
+                None => return Err(invalid(&format!("unknown matching rule {:?}", s))),
+            },
+        };
+        rule.validate()?;
+        Ok(rule)
+    }
+
+    /// regex rules have to compile.
+    pub fn validate(&self) -> Result<()> {
+        if let MatchRule::Regex(pattern) = self {
+            Regex::new(pattern)
+                .map_err(|err| invalid(&format!("bad matching regex {:?}: {}", pattern, err)))?;
+        }
+        Ok(())
+    }
+
+    fn matches(&self, entry_url: &str, page_url: &str) -> bool {
+        let entry_host = host(entry_url);
+        let page_host = host(page_url);
+        match self {
+            MatchRule::ExactHost => !page_host.is_empty() && page_host == entry_host,
+            MatchRule::BaseDomain => {
+                !page_host.is_empty() && base_domain(&page_host) == base_domain(&entry_host)
+            }
+            MatchRule::Subdomains => is_within(&page_host, &entry_host),
+            // invalid patterns are refused by validate, one that slipped through matches nothing.
+            MatchRule::Regex(pattern) => Regex::new(pattern).is_ok_and(|re| re.is_match(page_url)),
+            MatchRule::Never => false,
+        }
+    }
+}
+
+/// written the way MatchRule::parse reads it.
+impl fmt::Display for MatchRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchRule::ExactHost => f.write_str("exact-host"),
+            MatchRule::BaseDomain => f.write_str("base-domain"),
+            MatchRule::Subdomains => f.write_str("subdomains"),
+            MatchRule::Regex(pattern) => write!(f, "regex:{}", pattern),
+            MatchRule::Never => f.write_str("never"),
+        }
+    }
+}
+
+/// whether entry should be offered on the page at page_url: never if a Never rule is set, otherwise if any rule
+/// matches, with BaseDomain standing in for an empty rule list.
+pub fn matches(entry: &Entry, page_url: &str) -> bool {
+    let rules = &entry.matching_rules;
+    if rules.is_empty() {
+        return MatchRule::BaseDomain.matches(&entry.url, page_url);
+    }
+    !rules.contains(&MatchRule::Never)
+        && rules.iter().any(|rule| rule.matches(&entry.url, page_url))
+}
+
+/// lowercased host without a trailing dot.
+fn host(url: &str) -> String {
+    policy::url_host(url)
+        .trim_end_matches('.')
+        .to_ascii_lowercase()
+}
+
+/// host is domain or a subdomain of it.
+fn is_within(host: &str, domain: &str) -> bool {
+    !domain.is_empty()
+        && (host == domain
+            || host
+                .strip_suffix(domain)
+                .is_some_and(|rest| rest.ends_with('.')))
+}
+
+/// the registrable part of host: the last two labels, or three under a country code second level like co.uk.
+/// ip addresses and single label hosts are returned whole.
+fn base_domain(host: &str) -> &str {
+    if host.parse::<std::net::IpAddr>().is_ok() || host.starts_with('[') {
+        return host;
+    }
+    let labels: Vec<&str> = host.split('.').collect();
+    let keep = match labels.as_slice() {
+        [.., second, tld] if tld.len() == 2 && SECOND_LEVEL.contains(second) => 3,
+        _ => 2,
+    };
+    if labels.len() <= keep {
+        return host;
+    }
+    let start = labels[..labels.len() - keep]
+        .iter()
+        .map(|label| label.len() + 1)
+        .sum();
+    &host[start..]
+}
+
+fn invalid(reason: &str) -> PassrusError {
+    PassrusError::InvalidEntry(reason.to_owned())
+}
@@ -0,0 +1,551 @@
+//! one time password seeds, imported from `otpauth://` URIs and Google Authenticator `otpauth-migration://` exports,
+//! and the codes generated from them: HOTP (RFC 4226) and TOTP (RFC 6238).
+//!
+//! seeds are stored on the entry they belong to (Entry::otp) and so are encrypted with the rest of the vault file.
+//! see <https://github.com/google/google-authenticator/wiki/Key-Uri-Format> for the URI format.
+use crate::error::{PassrusError, Result};
+use crate::{policy, Entry};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{digest::KeyInit, Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+
+/// how many counter values resync looks ahead by default.
+pub const RESYNC_WINDOW: u64 = 100;
+
+/// how the moving factor is derived.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OtpKind {
+    /// time based, a new code every period seconds.
+    Totp { period: u64 },
+    /// counter based, the counter is the next value to use.
+    Hotp { counter: u64 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// a shared secret and the parameters needed to generate codes from it.
+/// a seed read from a vault file is validated like a parsed one, see OtpSeed::validate.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedSeed")]
+pub struct OtpSeed {
+    pub kind: OtpKind,
+    pub secret: Vec<u8>,
+    pub algorithm: Algorithm,
+    pub digits: u32,
+    /// who issued the seed, usually the site name. may be empty.
+    pub issuer: String,
+    /// the account name from the label, usually a username or email. may be empty.
+    pub account: String,
+}
+
+/// an OtpSeed as stored, before OtpSeed::validate has seen it.
+#[derive(Deserialize)]
+struct UncheckedSeed {
+    kind: OtpKind,
+    secret: Vec<u8>,
+    algorithm: Algorithm,
+    digits: u32,
+    issuer: String,
+    account: String,
+}
+
+impl TryFrom<UncheckedSeed> for OtpSeed {
+    type Error = PassrusError;
+
+    fn try_from(seed: UncheckedSeed) -> Result<Self> {
+        let seed = OtpSeed {
+            kind: seed.kind,
+            secret: seed.secret,
+            algorithm: seed.algorithm,
+            digits: seed.digits,
+            issuer: seed.issuer,
+            account: seed.account,
+        };
+        seed.validate()?;
+        Ok(seed)
+    }
+}
+
+/// parse one line: an `otpauth://` URI gives one seed, an `otpauth-migration://` URI any number.
+pub fn parse(uri: &str) -> Result<Vec<OtpSeed>> {
+    let uri = uri.trim();
+    if uri.starts_with("otpauth-migration://") {
+        parse_migration(uri)
+    } else {
+        Ok(vec![parse_uri(uri)?])
+    }
+}
+
+/// parse `otpauth://totp|hotp/[issuer:]account?secret=...&issuer=...&algorithm=...&digits=...&period=...&counter=...`.
+pub fn parse_uri(uri: &str) -> Result<OtpSeed> {
+    let rest = uri
+        .strip_prefix("otpauth://")
+        .ok_or_else(|| invalid("not an otpauth:// URI"))?;
+    let (kind, rest) = rest
+        .split_once('/')
+        .ok_or_else(|| invalid("missing otp type"))?;
+    let (label, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+    let label = percent_decode(label)?;
+    let (label_issuer, account) = match label.split_once(':') {
+        Some((issuer, account)) => (issuer.trim().to_owned(), account.trim().to_owned()),
+        None => (String::new(), label.trim().to_owned()),
+    };
+
+    let mut secret = None;
+    let mut issuer = None;
+    let mut algorithm = Algorithm::Sha1;
+    let mut digits = 6;
+    let mut period = 30;
+    let mut counter = None;
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(value)?;
+        match key.to_ascii_lowercase().as_str() {
+            "secret" => secret = Some(base32_decode(&value)?),
+            "issuer" => issuer = Some(value),
+            "algorithm" => {
+                algorithm = match value.to_ascii_uppercase().as_str() {
+                    "SHA1" => Algorithm::Sha1,
+                    "SHA256" => Algorithm::Sha256,
+                    "SHA512" => Algorithm::Sha512,
+                    other => return Err(invalid(&format!("unsupported algorithm {}", other))),
+                }
+            }
+            "digits" => {
+                digits = u32::try_from(parse_number(&value, "digits")?)
+                    .map_err(|_| invalid(&format!("{} digits is out of range 6-10", value)))?
+            }
+            "period" => period = parse_number(&value, "period")?,
+            "counter" => counter = Some(parse_number(&value, "counter")?),
+            // unknown parameters (image, color, ...) are ignored, as the format asks.
+            _ => {}
+        }
+    }
+
+    let kind = match kind.to_ascii_lowercase().as_str() {
+        "totp" => OtpKind::Totp { period },
+        "hotp" => OtpKind::Hotp {
+            counter: counter.ok_or_else(|| invalid("hotp URI without a counter"))?,
+        },
+        other => return Err(invalid(&format!("unknown otp type {}", other))),
+    };
+    let secret = secret
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| invalid("missing secret"))?;
+
+    let seed = OtpSeed {
+        kind,
+        secret,
+        algorithm,
+        digits,
+        issuer: issuer.unwrap_or(label_issuer),
+        account,
+    };
+    seed.validate()?;
+    Ok(seed)
+}
+
+/// parse `otpauth-migration://offline?data=...`, the base64 protobuf MigrationPayload behind Google Authenticator's
+/// export QR codes. each QR code of a multi part export is a separate URI.
+pub fn parse_migration(uri: &str) -> Result<Vec<OtpSeed>> {
+    let query = uri
+        .strip_prefix("otpauth-migration://offline?")
+        .ok_or_else(|| invalid("not an otpauth-migration://offline URI"))?;
+    let data = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("data="))
+        .ok_or_else(|| invalid("migration URI without data"))?;
+    let data = percent_decode(data)?;
+    let payload = STANDARD
+        .decode(data.replace(' ', "+"))
+        .map_err(|err| invalid(&format!("migration data is not valid base64: {}", err)))?;
+
+    let mut seeds = Vec::new();
+    for (field, value) in Protobuf(&payload) {
+        if let (1, Value::Bytes(params)) = (field, value?) {
+            seeds.push(parse_migration_params(params)?);
+        }
+    }
+    Ok(seeds)
+}
+
+/// one MigrationPayload.OtpParameters message.
+fn parse_migration_params(message: &[u8]) -> Result<OtpSeed> {
+    let mut secret = Vec::new();
+    let mut name = String::new();
+    let mut issuer = String::new();
+    let mut algorithm = Algorithm::Sha1;
+    let mut digits = 6;
+    let mut hotp = false;
+    let mut counter = 0;
+    for (field, value) in Protobuf(message) {
+        match (field, value?) {
+            (1, Value::Bytes(bytes)) => secret = bytes.to_vec(),
+            (2, Value::Bytes(bytes)) => name = String::from_utf8_lossy(bytes).into_owned(),
+            (3, Value::Bytes(bytes)) => issuer = String::from_utf8_lossy(bytes).into_owned(),
+            (4, Value::Varint(n)) => {
+                algorithm = match n {
+                    0 | 1 => Algorithm::Sha1,
+                    2 => Algorithm::Sha256,
+                    3 => Algorithm::Sha512,
+                    _ => return Err(invalid("unsupported algorithm in migration data")),
+                }
+            }
+            (5, Value::Varint(n)) => digits = if n == 2 { 8 } else { 6 },
+            (6, Value::Varint(n)) => hotp = n == 1,
+            (7, Value::Varint(n)) => counter = n,
+            _ => {}
+        }
+    }
+    if secret.is_empty() {
+        return Err(invalid("migration entry without a secret"));
+    }
+
+    // the name is the URI label, "issuer:account" or just the account.
+    let account = match name.split_once(':') {
+        Some((label_issuer, account)) => {
+            if issuer.is_empty() {
+                issuer = label_issuer.trim().to_owned();
+            }
+            account.trim().to_owned()
+        }
+        None => name,
+    };
+    Ok(OtpSeed {
+        kind: if hotp {
+            OtpKind::Hotp { counter }
+        } else {
+            OtpKind::Totp { period: 30 }
+        },
+        secret,
+        algorithm,
+        digits,
+        issuer,
+        account,
+    })
+}
+
+impl OtpSeed {
+    /// codes have 6 to 10 digits and a TOTP period is at least a second. parse and deserialising check this, so a seed
+    /// only fails here if its fields were set by hand.
+    pub fn validate(&self) -> Result<()> {
+        check_digits(self.digits)?;
+        match self.kind {
+            OtpKind::Totp { period: 0 } => Err(invalid("period must be positive")),
+            _ => Ok(()),
+        }
+    }
+
+    /// the current code. for TOTP now is the unix time in seconds. for HOTP the stored counter is used and then
+    /// incremented, so the caller has to save the entry afterwards or the same code comes up again.
+    pub fn generate(&mut self, now: u64) -> Result<String> {
+        self.validate()?;
+        let moving_factor = match &mut self.kind {
+            OtpKind::Totp { period } => now / *period,
+            OtpKind::Hotp { counter } => {
+                let value = *counter;
+                *counter += 1;
+                value
+            }
+        };
+        hotp(&self.secret, self.algorithm, self.digits, moving_factor)
+    }
+
+    /// bring an HOTP counter back in step after codes were generated on the token without being used here.
+    /// codes are consecutive codes read off the token; the first counter within window ahead of the stored one that
+    /// produces all of them is taken, and the stored counter moves past them. two codes make a false match unlikely.
+    pub fn resync(&mut self, codes: &[&str], window: u64) -> Result<()> {
+        let counter = match &mut self.kind {
+            OtpKind::Hotp { counter } => counter,
+            OtpKind::Totp { .. } => {
+                return Err(invalid("only hotp seeds have a counter to resync"))
+            }
+        };
+        if codes.is_empty() {
+            return Err(invalid("resync needs at least one code"));
+        }
+        check_digits(self.digits)?;
+        let found = (*counter..=counter.saturating_add(window)).find(|start| {
+            codes.iter().enumerate().all(|(i, code)| {
+                hotp(&self.secret, self.algorithm, self.digits, start + i as u64)
+                    .is_ok_and(|expected| expected == code.trim())
+            })
+        });
+        match found {
+            Some(start) => {
+                *counter = start + codes.len() as u64;
+                Ok(())
+            }
+            None => Err(invalid(&format!(
+                "no counter within {} of {} produces those codes",
+                window, counter
+            ))),
+        }
+    }
+
+    /// the seed as an `otpauth://` URI, the form parse_uri reads and pass-otp stores.
+    pub fn to_uri(&self) -> String {
+        let (kind, moving) = match self.kind {
+            OtpKind::Totp { period } => ("totp", format!("period={}", period)),
+            OtpKind::Hotp { counter } => ("hotp", format!("counter={}", counter)),
+        };
+        let algorithm = match self.algorithm {
+            Algorithm::Sha1 => "SHA1",
+            Algorithm::Sha256 => "SHA256",
+            Algorithm::Sha512 => "SHA512",
+        };
+        let label = if self.issuer.is_empty() {
+            percent_encode(&self.account)
+        } else {
+            format!(
+                "{}:{}",
+                percent_encode(&self.issuer),
+                percent_encode(&self.account)
+            )
+        };
+        let mut uri = format!(
+            "otpauth://{}/{}?secret={}&algorithm={}&digits={}&{}",
+            kind,
+            label,
+            base32_encode(&self.secret),
+            algorithm,
+            self.digits,
+            moving
+        );
+        if !self.issuer.is_empty() {
+            uri.push_str("&issuer=");
+            uri.push_str(&percent_encode(&self.issuer));
+        }
+        uri
+    }
+
+    /// true if the seed looks like it belongs to entry: the issuer is the entry's url, its host, or the host without
+    /// "www." and the top level domain ("GitHub" for github.com), and the account, if both sides have one,
+    /// is the entry's username or email.
+    pub fn matches(&self, entry: &Entry) -> bool {
+        if self.issuer.is_empty() {
+            return false;
+        }
+        let host = policy::url_host(&entry.url);
+        let bare = host.strip_prefix("www.").unwrap_or(host);
+        let name = bare.split('.').next().unwrap_or(bare);
+        let issuer_matches = [entry.url.as_str(), host, bare, name]
+            .iter()
+            .any(|candidate| self.issuer.eq_ignore_ascii_case(candidate));
+
+        let account_matches = self.account.is_empty()
+            || (entry.username.is_empty() && entry.email.is_empty())
+            || self.account.eq_ignore_ascii_case(&entry.username)
+            || self.account.eq_ignore_ascii_case(&entry.email);
+        issuer_matches && account_matches
+    }
+}
+
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    /// fixed32 and fixed64 fields, which MigrationPayload doesn't use.
+    Fixed,
+}
+
+/// iterator over the (field number, value) pairs of a protobuf message, just enough wire format for MigrationPayload.
+struct Protobuf<'a>(&'a [u8]);
+
+impl<'a> Protobuf<'a> {
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = self
+                .0
+                .split_first()
+                .ok_or_else(|| invalid("truncated migration data"))?;
+            self.0 = rest;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("varint too long in migration data"))
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if n > self.0.len() {
+            return Err(invalid("truncated migration data"));
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn field(&mut self) -> Result<(u64, Value<'a>)> {
+        let key = self.varint()?;
+        let value = match key & 7 {
+            0 => Value::Varint(self.varint()?),
+            1 => self.take(8).map(|_| Value::Fixed)?,
+            2 => {
+                let len = self.varint()? as usize;
+                Value::Bytes(self.take(len)?)
+            }
+            5 => self.take(4).map(|_| Value::Fixed)?,
+            wire => {
+                return Err(invalid(&format!(
+                    "unsupported wire type {} in migration data",
+                    wire
+                )))
+            }
+        };
+        Ok((key >> 3, value))
+    }
+}
+
+impl<'a> Iterator for Protobuf<'a> {
+    type Item = (u64, Result<Value<'a>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            return None;
+        }
+        Some(match self.field() {
+            Ok((field, value)) => (field, Ok(value)),
+            Err(err) => {
+                // stop after the first error.
+                self.0 = &[];
+                (0, Err(err))
+            }
+        })
+    }
+}
+
+/// RFC 4226 HOTP: HMAC of the big endian counter, dynamically truncated to digits (6 to 10) decimal digits.
+pub fn hotp(secret: &[u8], algorithm: Algorithm, digits: u32, counter: u64) -> Result<String> {
+    check_digits(digits)?;
+    let message = counter.to_be_bytes();
+    let mac = match algorithm {
+        Algorithm::Sha1 => hmac::<Hmac<Sha1>>(secret, &message),
+        Algorithm::Sha256 => hmac::<Hmac<Sha256>>(secret, &message),
+        Algorithm::Sha512 => hmac::<Hmac<Sha512>>(secret, &message),
+    };
+    let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        mac[offset],
+        mac[offset + 1],
+        mac[offset + 2],
+        mac[offset + 3],
+    ]) & 0x7fff_ffff;
+    let code = u64::from(binary) % 10u64.pow(digits);
+    Ok(format!("{:0width$}", code, width = digits as usize))
+}
+
+fn hmac<M: Mac + KeyInit>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = <M as Mac>::new_from_slice(key).expect("hmac takes keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn check_digits(digits: u32) -> Result<()> {
+    if (6..=10).contains(&digits) {
+        Ok(())
+    } else {
+        Err(invalid(&format!("{} digits is out of range 6-10", digits)))
+    }
+}
+
+fn parse_number(value: &str, name: &str) -> Result<u64> {
+    value
+        .parse()
+        .map_err(|_| invalid(&format!("{} is not a number: {:?}", name, value)))
+}
+
+/// RFC 4648 base32, case insensitive, padding and spaces ignored.
+fn base32_decode(s: &str) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 5 / 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in s.chars().filter(|c| *c != '=' && !c.is_whitespace()) {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u32 - 'A' as u32,
+            c @ '2'..='7' => c as u32 - '2' as u32 + 26,
+            _ => return Err(invalid(&format!("secret is not valid base32: {:?}", c))),
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Ok(out)
+}
+
+/// RFC 4648 base32 without padding, as authenticator apps expect it.
+fn base32_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for b in bytes {
+        buffer = (buffer << 8) | *b as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[(buffer >> bits) as usize & 31] as char);
+        }
+        buffer &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        out.push(ALPHABET[(buffer << (5 - bits)) as usize & 31] as char);
+    }
+    out
+}
+
+/// escape everything but unreserved URI characters.
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+fn percent_decode(s: &str) -> Result<String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|h| std::str::from_utf8(h).ok())
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or_else(|| invalid("bad percent escape"))?;
+                out.push(hex);
+                i += 3;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).map_err(|_| invalid("URI is not valid utf-8"))
+}
+
+fn invalid(reason: &str) -> PassrusError {
+    PassrusError::InvalidOtp(reason.to_owned())
+}
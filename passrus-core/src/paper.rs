@@ -0,0 +1,113 @@
+//! paper backups: an encrypted vault file split into short text frames, one per QR code.
+//!
+//! each frame is `PRSP1:<index>/<total>:<checksum>:<payload>`, index counting from 1, checksum the first 8 hex digits of
+//! the sha3-256 of the whole file and payload a url safe base64 slice of it. the checksum ties frames to one backup, so
+//! codes from different printouts can't be mixed. frames can be scanned back in any order.
+//! the data is the vault file as written, still encrypted under the master password.
+use crate::error::{PassrusError, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use sha3::{Digest, Sha3_256};
+
+/// prefix identifying a paper backup frame and its version.
+pub const FRAME_PREFIX: &str = "PRSP1:";
+/// raw bytes per frame. 768 bytes are 1024 base64 characters, a QR code of about version 25 at medium error correction.
+pub const FRAME_BYTES: usize = 768;
+
+/// split data into frames of at most FRAME_BYTES each.
+pub fn split(data: &[u8]) -> Vec<String> {
+    let checksum = checksum(data);
+    let chunks: Vec<&[u8]> = if data.is_empty() {
+        vec![data]
+    } else {
+        data.chunks(FRAME_BYTES).collect()
+    };
+    let total = chunks.len();
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            format!(
+                "{}{}/{}:{}:{}",
+                FRAME_PREFIX,
+                i + 1,
+                total,
+                checksum,
+                URL_SAFE_NO_PAD.encode(chunk)
+            )
+        })
+        .collect()
+}
+
+/// put data back together from frames in any order. blank lines are skipped, duplicates are fine,
+/// missing frames or frames from another backup are an error.
+pub fn join<'a, I: IntoIterator<Item = &'a str>>(frames: I) -> Result<Vec<u8>> {
+    let mut parts: Vec<Option<Vec<u8>>> = Vec::new();
+    let mut expected: Option<String> = None;
+
+    for frame in frames.into_iter().map(str::trim).filter(|f| !f.is_empty()) {
+        let (index, total, checksum, payload) = parse_frame(frame)?;
+        match &expected {
+            Some(sum) if *sum != checksum || parts.len() != total => {
+                return Err(corrupt("frames come from different backups"));
+            }
+            Some(_) => {}
+            None => {
+                expected = Some(checksum);
+                parts = vec![None; total];
+            }
+        }
+        parts[index - 1] = Some(payload);
+    }
+
+    let expected = expected.ok_or_else(|| corrupt("no frames given"))?;
+    let missing: Vec<String> = parts
+        .iter()
+        .enumerate()
+        .filter(|(_, part)| part.is_none())
+        .map(|(i, _)| (i + 1).to_string())
+        .collect();
+    if !missing.is_empty() {
+        return Err(corrupt(&format!(
+            "missing frames {} of {}",
+            missing.join(", "),
+            parts.len()
+        )));
+    }
+
+    let data: Vec<u8> = parts.into_iter().flatten().flatten().collect();
+    if checksum(&data) != expected {
+        return Err(corrupt("checksum of the reassembled data doesn't match"));
+    }
+    Ok(data)
+}
+
+/// (index, total, checksum, payload) of one frame.
+fn parse_frame(frame: &str) -> Result<(usize, usize, String, Vec<u8>)> {
+    let rest = frame
+        .strip_prefix(FRAME_PREFIX)
+        .ok_or_else(|| corrupt("not a passrus paper backup frame"))?;
+    let mut fields = rest.splitn(3, ':');
+    let (position, checksum, payload) = match (fields.next(), fields.next(), fields.next()) {
+        (Some(position), Some(checksum), Some(payload)) => (position, checksum, payload),
+        _ => return Err(corrupt("frame is missing fields")),
+    };
+    let (index, total) = position
+        .split_once('/')
+        .and_then(|(i, t)| Some((i.parse::<usize>().ok()?, t.parse::<usize>().ok()?)))
+        .filter(|&(i, t)| i >= 1 && i <= t)
+        .ok_or_else(|| corrupt(&format!("bad frame position {:?}", position)))?;
+    let payload = URL_SAFE_NO_PAD
+        .decode(payload)
+        .map_err(|err| corrupt(&format!("frame {} is not valid base64: {}", index, err)))?;
+    Ok((index, total, checksum.to_owned(), payload))
+}
+
+fn checksum(data: &[u8]) -> String {
+    hex::encode(&Sha3_256::digest(data)[..4])
+}
+
+fn corrupt(reason: &str) -> PassrusError {
+    PassrusError::CorruptFile {
+        reason: reason.to_owned(),
+    }
+}
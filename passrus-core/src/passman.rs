@@ -0,0 +1,743 @@
+use crate::cryptman;
+use crate::error::{PassrusError, Result};
+use crate::format;
+use crate::kind::EntryKind;
+use crate::link::{Link, Relation};
+use crate::matching::MatchRule;
+use crate::otp::OtpSeed;
+use crate::path::{self, ContainerPath, EntryRef};
+use crate::policy::{self, PasswordPolicy, Violation};
+use crate::rotation::Rotation;
+use crate::schema;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::{hash_map::Values, HashMap};
+
+#[derive(Clone,Serialize, Deserialize)]
+pub struct Container {
+    pub name: String,
+    pub children: HashMap<String, Container>,
+    pub entries: HashMap<String, Entry>,
+    pub parent: String,
+    /// shape version of the serialised container, see schema. missing in vaults written before versioning.
+    #[serde(default)]
+    pub schema_version: u32,
+    /// minimum password policy for entries in this container and below, unless a deeper container sets its own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<PasswordPolicy>,
+    /// unix time of the last Vault::refresh_crypto, only set on the root container.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refreshed_at: Option<u64>,
+    /// bumped on every save that changes the container or anything below it, see revision.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub revision: u64,
+}
+impl Container {
+    /// add an entry to the list of entries, expects an entry.
+    pub fn add_entry(&mut self, mut entry: Entry) {
+        entry.parent = self.name.as_str().to_owned();
+        self.entries.insert(entry.url.as_str().to_owned(), entry);
+    }
+
+    /// Add a child container, expects a container. fails with PassrusError::InvalidPath if its name is empty or contains '/'.
+    pub fn add_child(&mut self, mut container: Container) -> Result<()> {
+        path::validate_name(&container.name)?;
+        container.parent = self.name.as_str().to_owned();
+        self.children
+            .insert(container.name.as_str().to_owned(), container);
+        Ok(())
+    }
+
+    /// returns the container at path below this one, or PassrusError::ContainerNotFound.
+    pub fn get_container(&self, path: &ContainerPath) -> Result<&Container> {
+        let mut current = self;
+        for name in path.segments() {
+            current = current
+                .children
+                .get(name)
+                .ok_or_else(|| PassrusError::ContainerNotFound(path.to_string()))?;
+        }
+        Ok(current)
+    }
+
+    /// returns a mutable reference to the container at path below this one, or PassrusError::ContainerNotFound.
+    pub fn get_container_mut(&mut self, path: &ContainerPath) -> Result<&mut Container> {
+        let mut current = self;
+        for name in path.segments() {
+            current = current
+                .children
+                .get_mut(name)
+                .ok_or_else(|| PassrusError::ContainerNotFound(path.to_string()))?;
+        }
+        Ok(current)
+    }
+
+    /// returns the entry an EntryRef points at, anywhere below this container. an alias resolves to its target,
+    /// which has to be below this container too.
+    pub fn resolve(&self, entry_ref: &EntryRef) -> Result<&Entry> {
+        let entry = self
+            .get_container(&entry_ref.container)?
+            .entries
+            .get(&entry_ref.key)
+            .ok_or_else(|| PassrusError::EntryNotFound(entry_ref.to_string()))?;
+        self.follow(entry)
+    }
+
+    /// returns a mutable reference to the entry an EntryRef points at, anywhere below this container.
+    /// like resolve, an alias resolves to its target, so changes made through either path reach the same entry.
+    pub fn resolve_mut(&mut self, entry_ref: &EntryRef) -> Result<&mut Entry> {
+        let target = match &self.resolve_raw(entry_ref)?.kind {
+            EntryKind::Alias(alias) => Some(alias.target.clone()),
+            _ => None,
+        };
+        match target {
+            Some(id) => self
+                .find_by_id_mut(&id)
+                .ok_or_else(|| dangling(entry_ref, &id)),
+            None => self
+                .get_container_mut(&entry_ref.container)?
+                .entries
+                .get_mut(&entry_ref.key)
+                .ok_or_else(|| PassrusError::EntryNotFound(entry_ref.to_string())),
+        }
+    }
+
+    /// the entry stored at entry_ref itself, without following an alias.
+    pub fn resolve_raw(&self, entry_ref: &EntryRef) -> Result<&Entry> {
+        self.get_container(&entry_ref.container)?
+            .entries
+            .get(&entry_ref.key)
+            .ok_or_else(|| PassrusError::EntryNotFound(entry_ref.to_string()))
+    }
+
+    /// entry itself, or the target of entry if it is an alias.
+    pub fn follow<'a>(&'a self, entry: &'a Entry) -> Result<&'a Entry> {
+        match &entry.kind {
+            EntryKind::Alias(alias) => self
+                .find_by_id(&alias.target)
+                .ok_or_else(|| dangling(&entry.url, &alias.target)),
+            _ => Ok(entry),
+        }
+    }
+
+    /// the entry with the given id anywhere below this container.
+    pub fn find_by_id(&self, id: &str) -> Option<&Entry> {
+        self.iter_entries()
+            .map(|(_, entry)| entry)
+            .find(|entry| entry.id == id)
+    }
+
+    /// where the entry with the given id is stored below this container.
+    pub fn path_of(&self, id: &str) -> Result<Option<EntryRef>> {
+        self.iter_entries()
+            .find(|(_, entry)| entry.id == id)
+            .map(|(path, entry)| self.entry_ref(&path, entry))
+            .transpose()
+    }
+
+    /// the entries below this container linking to the entry with the given id, and how.
+    pub fn links_to(&self, id: &str) -> Result<Vec<(EntryRef, Relation)>> {
+        let mut found = Vec::new();
+        for (path, entry) in self.iter_entries() {
+            for link in entry.links.iter().filter(|link| link.target == id) {
+                found.push((self.entry_ref(&path, entry)?, link.relation));
+            }
+        }
+        found.sort_by_key(|(entry_ref, _)| entry_ref.to_string());
+        Ok(found)
+    }
+
+    /// the EntryRef of an entry yielded by iter_entries at path. container names in vaults written before names were
+    /// validated can be empty or contain '/', so the path may not parse or may lead somewhere else; both fail with
+    /// PassrusError::InvalidPath rather than pointing at the wrong entry.
+    pub fn entry_ref(&self, path: &str, entry: &Entry) -> Result<EntryRef> {
+        let unreachable = || {
+            PassrusError::InvalidPath(format!(
+                "{:?} in container {:?} can't be addressed by path, rename the container",
+                entry.url, path
+            ))
+        };
+        let container = ContainerPath::parse(path).map_err(|_| unreachable())?;
+        let found = self
+            .get_container(&container)
+            .ok()
+            .and_then(|c| c.entries.get(&entry.url));
+        match found {
+            Some(found) if std::ptr::eq(found, entry) => Ok(EntryRef::new(container, &entry.url)),
+            _ => Err(unreachable()),
+        }
+    }
+
+    /// mutable version of find_by_id.
+    pub fn find_by_id_mut(&mut self, id: &str) -> Option<&mut Entry> {
+        if self.entries.values().any(|entry| entry.id == id) {
+            return self.entries.values_mut().find(|entry| entry.id == id);
+        }
+        self.children
+            .values_mut()
+            .find_map(|child| child.find_by_id_mut(id))
+    }
+
+    /// returns the policy for entries in the container at path: the one set on the deepest container along the path, if any.
+    pub fn policy_for(&self, path: &ContainerPath) -> Option<&PasswordPolicy> {
+        let mut current = self;
+        let mut policy = self.policy.as_ref();
+        for name in path.segments() {
+            current = match current.children.get(name) {
+                Some(child) => child,
+                None => break,
+            };
+            policy = current.policy.as_ref().or(policy);
+        }
+        policy
+    }
+
+    /// set the policy of the container at path, or remove it with None so the one above applies again.
+    /// entries already stored aren't checked against it, audit reports those.
+    pub fn set_policy(&mut self, path: &ContainerPath, policy: Option<PasswordPolicy>) -> Result<()> {
+        self.get_container_mut(path)?.policy = policy;
+        Ok(())
+    }
+
+    /// add an entry to the container at path after checking its plaintext password against the applicable policy.
+    /// with an enforcing policy violations fail with PassrusError::PolicyViolation unless allow_violations is set,
+    /// otherwise the entry is added and the violations are returned as warnings.
+    /// passwords guessable from the entry itself (policy::guessable) are always returned as warnings, never rejected.
+    /// the entry is validated against its kind's schema first; only logins are checked against password policies.
+    pub fn add_entry_checked(
+        &mut self,
+        path: &ContainerPath,
+        entry: Entry,
+        plaintext: &str,
+        allow_violations: bool,
+    ) -> Result<Vec<Violation>> {
+        entry.validate()?;
+        if !entry.kind.is_login() {
+            self.get_container_mut(path)?.add_entry(entry);
+            return Ok(Vec::new());
+        }
+        let (mut violations, enforce) = match self.policy_for(path) {
+            Some(policy) => (policy.check(plaintext, &entry.username), policy.enforce),
+            None => (Vec::new(), false),
+        };
+        if enforce && !allow_violations && !violations.is_empty() {
+            return Err(PassrusError::PolicyViolation(violations));
+        }
+        for warning in policy::guessable(&entry, plaintext) {
+            if !violations.contains(&warning) {
+                violations.push(warning);
+            }
+        }
+        self.get_container_mut(path)?.add_entry(entry);
+        Ok(violations)
+    }
+
+    /// returns the entry stored under url in this container, or PassrusError::EntryNotFound.
+    pub fn get_entry(&self, url: &str) -> Result<&Entry> {
+        self.entries
+            .get(url)
+            .ok_or_else(|| PassrusError::EntryNotFound(url.to_owned()))
+    }
+
+    /// returns a mutable reference to the entry stored under url in this container, or PassrusError::EntryNotFound.
+    pub fn get_entry_mut(&mut self, url: &str) -> Result<&mut Entry> {
+        self.entries
+            .get_mut(url)
+            .ok_or_else(|| PassrusError::EntryNotFound(url.to_owned()))
+    }
+
+    /// returns a JSON representation of the container as a string.
+    pub fn to_json_string(&mut self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// populate a container from a &[u8] array,of a JSON serialisation of a container. returns a Result<(),PassrusError>
+    /// older schema versions are migrated to the current one.
+    pub fn from_json_arr(&mut self, arr: &[u8]) -> Result<()> {
+        let mut value: serde_json::Value = serde_json::from_slice(arr)?;
+        schema::migrate(&mut value)?;
+        let from_json: Container = serde_json::from_value(value)?;
+        *self = from_json;
+        Ok(())
+    }
+
+    /// populate a container from a &str, of a  JSON serialisation of a container. returns a Result<(),PassrusError>
+    /// older schema versions are migrated to the current one.
+    pub fn from_json_string(&mut self, s: &str) -> Result<()> {
+        self.from_json_arr(s.as_bytes())
+    }
+
+    // instantiate a new container, expects a name. Returns a container.
+    pub fn new(name: &str) -> Self {
+        let parent = "none";
+        let children: HashMap<String, Container> = HashMap::new();
+        let entries: HashMap<String, Entry> = HashMap::new();
+        Container {
+            name: name.to_owned(),
+            parent: parent.to_owned(),
+            children,
+            entries,
+            schema_version: schema::CURRENT_SCHEMA_VERSION,
+            policy: None,
+            refreshed_at: None,
+            revision: 0,
+        }
+    }
+
+    /// returns a borrowed, depth first iterator over every entry in this container and its children.
+    /// each item is the path of the container holding the entry ("child/grandchild", "" for this container) and the entry itself.
+    pub fn iter_entries(&self) -> EntryIter<'_> {
+        EntryIter {
+            stack: vec![(String::new(), self)],
+            current: None,
+        }
+    }
+
+    /// calls f with the container path and a reference to every entry in the tree, without cloning anything.
+    pub fn walk<F: FnMut(&str, &Entry)>(&self, mut f: F) {
+        self.walk_inner(&mut String::new(), &mut f);
+    }
+
+    fn walk_inner<F: FnMut(&str, &Entry)>(&self, path: &mut String, f: &mut F) {
+        for entry in self.entries.values() {
+            f(path, entry);
+        }
+        for (name, child) in &self.children {
+            let len = path.len();
+            push_path(path, name);
+            child.walk_inner(path, f);
+            path.truncate(len);
+        }
+    }
+
+    /// re-encrypt every entry password and the entries' sealed fields in the tree from one password to another, with
+    /// a fresh salt and nonces. protected fields not sealed yet are sealed. every stored password must decrypt with from; if one doesn't, the tree is left as it was and the error returned.
+    /// one Argon2 key is derived for the whole tree, each entry gets its own nonce and its own key from it via HKDF.
+    pub fn reencrypt_passwords(&mut self, from: &str, to: &str) -> Result<()> {
+        let mut tree = self.clone();
+        tree.decrypt_passwords(from)?;
+        tree.encrypt_passwords(to)?;
+        *self = tree;
+        Ok(())
+    }
+
+    /// decrypt every entry password in the tree in place and open the sealed fields, stopping at the first that
+    /// doesn't decrypt with pass. the tree holds plaintext afterwards, so callers work on a copy and
+    /// encrypt_passwords it again.
+    pub(crate) fn decrypt_passwords(&mut self, pass: &str) -> Result<()> {
+        let mut res = Ok(());
+        self.walk_mut(|_, entry| {
+            if res.is_ok() && !entry.pass_vec.is_empty() {
+                res = entry.decrypt_password(pass);
+            }
+            if res.is_ok() {
+                res = entry.open_fields(pass);
+            }
+        });
+        res
+    }
+
+    /// encrypt every plaintext entry password and seal the protected fields in the tree under pass, the inverse of
+    /// decrypt_passwords.
+    pub(crate) fn encrypt_passwords(&mut self, pass: &str) -> Result<()> {
+        let mut salt = [0u8; 32];
+        OsRng.fill_bytes(&mut salt);
+        let (key, salt) = cryptman::pass_2_key(pass, salt)?;
+
+        let mut res = Ok(());
+        self.walk_mut(|_, entry| {
+            if res.is_err() {
+                return;
+            }
+            let mut nonce = [0u8; 24];
+            if !entry.pass_vec.is_empty() {
+                OsRng.fill_bytes(&mut nonce);
+                res = entry.encrypt_password(key, nonce, salt);
+            }
+            if res.is_ok() && entry.sealed.is_empty() {
+                OsRng.fill_bytes(&mut nonce);
+                res = entry.seal_fields(key, nonce, salt);
+            }
+        });
+        res
+    }
+
+    /// calls f with the container path and a mutable reference to every entry in the tree.
+    pub fn walk_mut<F: FnMut(&str, &mut Entry)>(&mut self, mut f: F) {
+        self.walk_mut_inner(&mut String::new(), &mut f);
+    }
+
+    fn walk_mut_inner<F: FnMut(&str, &mut Entry)>(&mut self, path: &mut String, f: &mut F) {
+        for entry in self.entries.values_mut() {
+            f(path, entry);
+        }
+        for (name, child) in self.children.iter_mut() {
+            let len = path.len();
+            push_path(path, name);
+            child.walk_mut_inner(path, f);
+            path.truncate(len);
+        }
+    }
+}
+
+fn dangling(alias: &dyn std::fmt::Display, id: &str) -> PassrusError {
+    PassrusError::EntryNotFound(format!("{} is an alias of {}, which no longer exists", alias, id))
+}
+
+fn is_zero(revision: &u64) -> bool {
+    *revision == 0
+}
+
+fn is_false(flag: &bool) -> bool {
+    !*flag
+}
+
+/// a new random (version 4) UUID for an entry.
+pub fn new_id() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+fn push_path(path: &mut String, name: &str) {
+    if !path.is_empty() {
+        path.push('/');
+    }
+    path.push_str(name);
+}
+
+/// depth first iterator over the entries of a container tree, see Container::iter_entries.
+pub struct EntryIter<'a> {
+    stack: Vec<(String, &'a Container)>,
+    current: Option<(String, Values<'a, String, Entry>)>,
+}
+
+impl<'a> Iterator for EntryIter<'a> {
+    type Item = (String, &'a Entry);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((path, entries)) = &mut self.current {
+                if let Some(entry) = entries.next() {
+                    return Some((path.clone(), entry));
+                }
+            }
+
+            // current container exhausted, move on to the next one and queue its children.
+            let (path, container) = self.stack.pop()?;
+            for (name, child) in &container.children {
+                let mut child_path = path.clone();
+                push_path(&mut child_path, name);
+                self.stack.push((child_path, child));
+            }
+            self.current = Some((path, container.entries.values()));
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// random UUID, stable across renames and moves. aliases point at it.
+    #[serde(default)]
+    pub id: String,
+    pub username: String,
+    pub pass_vec: Vec<u8>,
+    /// pass_vec is encrypted with a key of the entry's own, derived from the vault password's key and the id (see
+    /// cryptman::entry_key). entries encrypted before then use the vault password's key directly.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub entry_key: bool,
+    pub email: String,
+    pub url: String,
+    pub parent: String,
+    /// the protected fields of the kind (see kind::FieldSpec), encrypted under the entry's own key like pass_vec.
+    /// they are empty in kind while sealed, see seal_fields and open_fields.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sealed: Vec<u8>,
+    /// one time password seed for the account, if it uses 2FA codes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub otp: Option<OtpSeed>,
+    /// what the entry holds beyond the common fields. logins, the default, aren't written out.
+    #[serde(default, skip_serializing_if = "EntryKind::is_login")]
+    pub kind: EntryKind,
+    /// which pages the entry is offered on, see matching::matches. empty means its base domain.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matching_rules: Vec<MatchRule>,
+    /// related entries, e.g. the mail account used for recovery. see link.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<Link>,
+    /// how often the password should change, see rotation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<Rotation>,
+    /// bumped on every save that changes the entry, see revision.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub revision: u64,
+}
+
+impl Entry {
+
+    /// returns a JSON representation of the entry as a string.
+    pub fn to_json_string(&mut self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// populate an entry from a &[u8] array,of a JSON serialisation of an entry. returns a Result<(),PassrusError>
+    pub fn from_json_arr(&mut self, arr: &[u8]) -> Result<()> {
+        let from_json: Entry = serde_json::from_slice(arr)?;
+        *self = from_json;
+        Ok(())
+    }
+
+    /// populate an entry from a &str, of a  JSON serialisation of an entry. returns a Result<(),PassrusError>
+    pub fn from_json_string(&mut self, s: &str) -> Result<()> {
+        let from_json_str: Entry = serde_json::from_str(s)?;
+        *self = from_json_str;
+        Ok(())
+    }
+
+    /// instantiate a new entry, expects a name,encrypted password, email and url. Returns an entry.
+    pub fn new(username: &str, pass_vec: Vec<u8>, email: &str, url: &str) -> Self {
+        Entry {
+            id: new_id(),
+            username: username.to_owned(),
+            pass_vec,
+            entry_key: false,
+            email: email.to_owned(),
+            url: url.to_owned(),
+            parent: "".to_owned(),
+            sealed: Vec::new(),
+            otp: None,
+            kind: EntryKind::Login,
+            matching_rules: Vec::new(),
+            links: Vec::new(),
+            rotation: None,
+            revision: 0,
+        }
+    }
+    /// check the entry's kind specific fields against the kind's schema, see EntryKind::validate, and its matching rules.
+    /// a sealed kind was validated by set_kind before it was sealed, and isn't checked again.
+    pub fn validate(&self) -> Result<()> {
+        if self.sealed.is_empty() {
+            self.kind.validate()?;
+        }
+        self.matching_rules.iter().try_for_each(MatchRule::validate)
+    }
+
+    /// encrypt the password in pass_vec under the entry's own key, derived from key (the vault password's, with salt)
+    /// and the entry's id. the id must not change while it is encrypted, see subtree::renew_ids.
+    pub fn encrypt_password(&mut self,key:[u8;32],nonce:[u8;24],salt:[u8;32]) -> Result<()> {
+
+        let key = cryptman::entry_key(&key, &self.id);
+        let binding = cryptman::encrypt_file_mem_with_salt(self.pass_vec.clone(), "", &key, &nonce, &salt)?;
+        self.pass_vec = binding;
+        self.entry_key = true;
+        Ok(())
+    }
+
+    /// replace the password with plaintext, encrypted under a key from the vault password with a fresh salt and nonce.
+    /// a rotation interval restarts from now.
+    pub fn set_password(&mut self, plaintext: &[u8], pass: &str, now: u64) -> Result<()> {
+        let mut salt = [0u8; 32];
+        OsRng.fill_bytes(&mut salt);
+        let (key, salt) = cryptman::pass_2_key(pass, salt)?;
+        let mut nonce = [0u8; 24];
+        OsRng.fill_bytes(&mut nonce);
+        self.pass_vec = plaintext.to_vec();
+        self.encrypt_password(key, nonce, salt)?;
+        if let Some(rotation) = &mut self.rotation {
+            rotation.changed_at = now;
+        }
+        Ok(())
+    }
+
+    /// validate kind and make it the entry's, with its protected fields sealed under a key from the vault password
+    /// with a fresh salt and nonce.
+    pub fn set_kind(&mut self, kind: EntryKind, pass: &str) -> Result<()> {
+        kind.validate()?;
+        self.kind = kind;
+        self.sealed.clear();
+        let mut salt = [0u8; 32];
+        OsRng.fill_bytes(&mut salt);
+        let (key, salt) = cryptman::pass_2_key(pass, salt)?;
+        let mut nonce = [0u8; 24];
+        OsRng.fill_bytes(&mut nonce);
+        self.seal_fields(key, nonce, salt)
+    }
+
+    /// move the protected fields of the kind into sealed, encrypted under the entry's own key like encrypt_password
+    /// does pass_vec. nothing is sealed if they are all empty. nonce must not be the one pass_vec was encrypted with.
+    pub fn seal_fields(&mut self, key: [u8; 32], nonce: [u8; 24], salt: [u8; 32]) -> Result<()> {
+        let fields = self.kind.take_protected();
+        if fields.values().flatten().all(String::is_empty) {
+            return self.kind.put_protected(fields);
+        }
+        let key = cryptman::entry_key(&key, &self.id);
+        self.sealed = cryptman::encrypt_file_mem_with_salt(serde_json::to_vec(&fields)?, "", &key, &nonce, &salt)?;
+        Ok(())
+    }
+
+    /// decrypt sealed with a key from the vault password and put the fields back into the kind. nothing to do if the
+    /// entry has no sealed fields.
+    pub fn open_fields(&mut self, password: &str) -> Result<()> {
+        if self.sealed.is_empty() {
+            return Ok(());
+        }
+        let salt = format::parse(&self.sealed)?.salt;
+        let key = cryptman::entry_key(&cryptman::pass_2_key(password, salt)?.0, &self.id);
+        let fields = cryptman::decrypt_mem_with_key(self.sealed.clone(), &key)?;
+        self.kind.put_protected(serde_json::from_slice(&fields)?)?;
+        self.sealed.clear();
+        Ok(())
+    }
+
+    /// change only the given fields: each (field, Some(value)) sets one, (field, None) clears it. the values given
+    /// for a repeated field (an identity's address) replace all of its current ones. fields are username, email,
+    /// password and those in the schema of the entry's kind; the url is the entry's key and can't be patched.
+    /// the entry is left as it was unless the patched entry validates.
+    pub fn apply_patch(&mut self, patch: &[(&str, Option<&str>)], pass: &str, now: u64) -> Result<()> {
+        let schema = self.kind.schema();
+        let mut patched = self.clone();
+        let mut kind_fields = Vec::new();
+        for (field, value) in patch {
+            match *field {
+                "username" => patched.username = value.unwrap_or_default().to_owned(),
+                "email" => patched.email = value.unwrap_or_default().to_owned(),
+                "password" => match value {
+                    Some(password) => patched.set_password(password.as_bytes(), pass, now)?,
+                    None => {
+                        patched.pass_vec.clear();
+                        patched.entry_key = false;
+                    }
+                },
+                "url" => {
+                    return Err(PassrusError::InvalidEntry(
+                        "the url is the entry's key and can't be patched".to_owned(),
+                    ))
+                }
+                name if schema.iter().any(|spec| spec.name == name) => kind_fields.push(name),
+                other => {
+                    return Err(PassrusError::InvalidEntry(format!(
+                        "{} entries have no field {:?}",
+                        self.kind.name(),
+                        other
+                    )))
+                }
+            }
+        }
+
+        // rebuild the kind from its current fields with the patched ones swapped in, so it is checked as a whole.
+        if !kind_fields.is_empty() {
+            let mut opened = self.clone();
+            opened.open_fields(pass)?;
+            let mut fields: Vec<(&str, String)> = Vec::new();
+            for spec in schema {
+                if kind_fields.contains(&spec.name) {
+                    fields.extend(
+                        patch
+                            .iter()
+                            .filter(|(field, _)| *field == spec.name)
+                            .filter_map(|(_, value)| value.map(|value| (spec.name, value.to_owned()))),
+                    );
+                    continue;
+                }
+                let current = opened.kind.field_values(spec.name);
+                if spec.repeated {
+                    fields.extend(current.iter().map(|value| (spec.name, (*value).to_owned())));
+                } else if current.iter().any(|value| !value.is_empty()) {
+                    fields.push((spec.name, current.join(", ")));
+                }
+            }
+            let fields: Vec<(&str, &str)> = fields.iter().map(|(name, value)| (*name, value.as_str())).collect();
+            patched.set_kind(EntryKind::from_fields(self.kind.name(), &fields)?, pass)?;
+        }
+        patched.validate()?;
+        *self = patched;
+        Ok(())
+    }
+
+    /// decrypt pass_vec in place with a key from the vault password, the entry's own if it has one.
+    pub fn decrypt_password(&mut self,password:&str) -> Result<()> {
+
+        let binding = if self.entry_key {
+            let salt = format::parse(&self.pass_vec)?.salt;
+            let key = cryptman::entry_key(&cryptman::pass_2_key(password, salt)?.0, &self.id);
+            cryptman::decrypt_mem_with_key(self.pass_vec.clone(), &key)?
+        } else {
+            cryptman::decrypt_file_mem_gen_key(self.pass_vec.clone(),"", password)?
+        };
+        self.pass_vec = binding;
+        self.entry_key = false;
+        Ok(())
+    }
+}
+
+pub fn get_entries_by_field(container: &Container, field_name: &str, target_value: &str) -> Vec<Entry> {
+    let mut result = Vec::new();
+
+    // Check entries in the current container
+    for entry in container.entries.values() {
+        match field_name {
+            "url" => {
+                if entry.url == target_value {
+                    result.push(entry.clone()); // Clone the Entry
+                }
+            }
+            "email" => {
+                if entry.email == target_value {
+                    result.push(entry.clone()); // Clone the Entry
+                }
+            }
+            "parent" => {
+                if entry.parent == target_value {
+                    result.push(entry.clone()); // Clone the Entry
+                }
+            }
+            "username" => {
+                if entry.username == target_value {
+                    result.push(entry.clone());
+                }
+            }
+            // kind specific fields, e.g. an identity's name or phone.
+            other => {
+                if entry.kind.field_values(other).contains(&target_value) {
+                    result.push(entry.clone());
+                }
+            }
+        }
+    }
+
+    // Recursively check subcontainers
+    for child_container in container.children.values() {
+        result.extend(get_entries_by_field(child_container, field_name, target_value));
+    }
+
+    result
+}
+
+pub fn flatten(
+    parent: &Container,
+) -> Result<HashMap<String, Entry>> {
+    let mut entries: HashMap<String, Entry> = HashMap::new();
+
+    // Add entries from the current container.
+    for (key, value) in &parent.entries {
+        entries.insert(key.to_owned(), value.clone());
+    }
+
+    // Recursively process nested containers.
+    for nested_container in parent.children.values() {
+        let nested_entries = flatten(nested_container)?;
+        entries.extend(nested_entries);
+    }
+
+    Ok(entries)
+}
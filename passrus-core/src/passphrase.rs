@@ -0,0 +1,79 @@
+//! passphrases of random words, diceware style.
+//!
+//! no wordlist is built in, one is read from a file: plain (one word per line) or diceware format ("11111\tword"),
+//! such as the EFF large and short lists or a list in the user's language. lists are checked before use, a short list
+//! or one with repeated words gives fewer bits per word than it appears to.
+use crate::error::{PassrusError, Result};
+use rand::{rngs::OsRng, Rng};
+use std::collections::HashSet;
+
+/// fewest words a list may have, 10 bits per word. the EFF short lists have 1296, the large list 7776.
+pub const MIN_LIST_LEN: usize = 1024;
+
+/// words in a passphrase unless asked otherwise. about 77 bits with the EFF large list.
+pub const DEFAULT_WORDS: usize = 6;
+
+/// a checked list of distinct words.
+pub struct Wordlist {
+    words: Vec<String>,
+}
+
+impl Wordlist {
+    /// parse a wordlist, one word per line. a leading run of dice digits followed by whitespace is dropped, blank
+    /// lines and lines starting with '#' are skipped. fails if fewer than MIN_LIST_LEN words are left, if a word is
+    /// repeated (ignoring case) or contains whitespace.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut words = Vec::new();
+        let mut seen = HashSet::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let word = match line.split_once(char::is_whitespace) {
+                Some((dice, word)) if dice.chars().all(|c| c.is_ascii_digit()) => word.trim(),
+                _ => line,
+            };
+            if word.contains(char::is_whitespace) {
+                return Err(invalid(format!("{:?} is more than one word", word)));
+            }
+            if !seen.insert(word.to_lowercase()) {
+                return Err(invalid(format!("{:?} appears more than once", word)));
+            }
+            words.push(word.to_owned());
+        }
+        if words.len() < MIN_LIST_LEN {
+            return Err(invalid(format!(
+                "{} words, at least {} are needed",
+                words.len(),
+                MIN_LIST_LEN
+            )));
+        }
+        Ok(Wordlist { words })
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// entropy each randomly chosen word adds.
+    pub fn bits_per_word(&self) -> f64 {
+        (self.words.len() as f64).log2()
+    }
+
+    /// count words chosen uniformly at random with OsRng, joined with separator.
+    pub fn generate(&self, count: usize, separator: &str) -> String {
+        (0..count)
+            .map(|_| self.words[OsRng.gen_range(0..self.words.len())].as_str())
+            .collect::<Vec<&str>>()
+            .join(separator)
+    }
+}
+
+fn invalid(reason: String) -> PassrusError {
+    PassrusError::InvalidWordlist(reason)
+}
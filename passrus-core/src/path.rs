@@ -0,0 +1,131 @@
+use crate::error::{PassrusError, Result};
+use std::{fmt, str::FromStr};
+
+/// checks a container name can be used as a path segment: not empty, and no '/'.
+pub fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        return Err(PassrusError::InvalidPath("container names can't be empty".to_owned()));
+    }
+    if name.contains('/') {
+        return Err(PassrusError::InvalidPath(format!(
+            "container name {:?} contains '/'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// path to a container below the root, written "a/b/c". the empty path is the root itself.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ContainerPath {
+    segments: Vec<String>,
+}
+
+impl ContainerPath {
+    /// the path of the root container.
+    pub fn root() -> Self {
+        ContainerPath::default()
+    }
+
+    /// parse "a/b/c". "" is the root, empty segments ("a//b", "/a") are rejected.
+    pub fn parse(s: &str) -> Result<Self> {
+        if s.is_empty() {
+            return Ok(ContainerPath::root());
+        }
+        let segments = s
+            .split('/')
+            .map(|segment| {
+                validate_name(segment)
+                    .map(|_| segment.to_owned())
+                    .map_err(|_| PassrusError::InvalidPath(format!("empty segment in path {:?}", s)))
+            })
+            .collect::<Result<Vec<String>>>()?;
+        Ok(ContainerPath { segments })
+    }
+
+    /// the container names from the root down.
+    pub fn segments(&self) -> &[String] {
+        &self.segments
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// returns the path of the child called name.
+    pub fn join(&self, name: &str) -> Result<Self> {
+        validate_name(name)?;
+        let mut segments = self.segments.clone();
+        segments.push(name.to_owned());
+        Ok(ContainerPath { segments })
+    }
+
+    /// returns the path one level up, None for the root.
+    pub fn parent(&self) -> Option<Self> {
+        let (_, rest) = self.segments.split_last()?;
+        Some(ContainerPath {
+            segments: rest.to_vec(),
+        })
+    }
+}
+
+impl fmt::Display for ContainerPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.segments.join("/"))
+    }
+}
+
+impl FromStr for ContainerPath {
+    type Err = PassrusError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        ContainerPath::parse(s)
+    }
+}
+
+/// reference to an entry: the path of its container plus the key (url) it is stored under.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EntryRef {
+    pub container: ContainerPath,
+    pub key: String,
+}
+
+impl EntryRef {
+    pub fn new(container: ContainerPath, key: &str) -> Self {
+        EntryRef {
+            container,
+            key: key.to_owned(),
+        }
+    }
+
+    /// parse "a/b/key": everything up to the last '/' is the container path, the rest is the entry key.
+    /// keys containing '/' can't be written this way, build those with EntryRef::new.
+    pub fn parse(s: &str) -> Result<Self> {
+        let (container, key) = match s.rsplit_once('/') {
+            Some((container, key)) => (ContainerPath::parse(container)?, key),
+            None => (ContainerPath::root(), s),
+        };
+        if key.is_empty() {
+            return Err(PassrusError::InvalidPath(format!("no entry key in {:?}", s)));
+        }
+        Ok(EntryRef::new(container, key))
+    }
+}
+
+impl fmt::Display for EntryRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.container.is_root() {
+            f.write_str(&self.key)
+        } else {
+            write!(f, "{}/{}", self.container, self.key)
+        }
+    }
+}
+
+impl FromStr for EntryRef {
+    type Err = PassrusError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        EntryRef::parse(s)
+    }
+}
@@ -0,0 +1,319 @@
+//! minimum password policies, stored per container.
+//!
+//! the policy that applies to an entry is the one on the deepest container along its path that has one,
+//! see Container::policy_for. a policy can also refuse passwords found in a local copy of the Pwned Passwords corpus,
+//! see breach_count; nothing is looked up over the network.
+//!
+//! independent of any policy, guessable flags passwords that can be read straight off the entry (username, email, url).
+//! estimate_bits rates a master password, which no container policy covers.
+use crate::error::{PassrusError, Result};
+use crate::Entry;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::{fmt, fs, io, path::Path};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasswordPolicy {
+    /// minimum length in characters.
+    pub min_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    /// anything that isn't an ascii letter or digit counts as a symbol.
+    pub require_symbol: bool,
+    /// reject passwords equal to the entry's username (ignoring case).
+    pub forbid_username: bool,
+    /// reject passwords found in the breach corpus in this directory, see breach_count.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub breach_list: Option<String>,
+    /// when true violations are rejected unless explicitly overridden, when false they are only reported as warnings.
+    pub enforce: bool,
+}
+
+/// a single way a password fails a policy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Violation {
+    TooShort { min: usize, actual: usize },
+    MissingLowercase,
+    MissingUppercase,
+    MissingDigit,
+    MissingSymbol,
+    EqualsUsername,
+    EqualsEmailLocalPart,
+    EqualsUrlHost,
+    Breached(u64),
+    BreachListUnreadable(String),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::TooShort { min, actual } => {
+                write!(f, "shorter than {} characters ({})", min, actual)
+            }
+            Violation::MissingLowercase => f.write_str("no lowercase letter"),
+            Violation::MissingUppercase => f.write_str("no uppercase letter"),
+            Violation::MissingDigit => f.write_str("no digit"),
+            Violation::MissingSymbol => f.write_str("no symbol"),
+            Violation::EqualsUsername => f.write_str("same as the username"),
+            Violation::EqualsEmailLocalPart => {
+                f.write_str("same as the email address before the '@'")
+            }
+            Violation::EqualsUrlHost => f.write_str("same as the site's host name"),
+            Violation::Breached(count) => write!(f, "found {} times in known breaches", count),
+            Violation::BreachListUnreadable(reason) => {
+                write!(f, "breach list unreadable ({})", reason)
+            }
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// build a policy from rules as written on the command line: min-length=<n>, lowercase, uppercase, digit, symbol,
+    /// no-username, not-in-breach=<dir> and enforce. rules not given stay off.
+    pub fn parse<S: AsRef<str>>(rules: &[S]) -> Result<Self> {
+        let mut policy = PasswordPolicy::default();
+        for rule in rules {
+            let rule = rule.as_ref();
+            match rule {
+                "lowercase" => policy.require_lowercase = true,
+                "uppercase" => policy.require_uppercase = true,
+                "digit" => policy.require_digit = true,
+                "symbol" => policy.require_symbol = true,
+                "no-username" => policy.forbid_username = true,
+                "enforce" => policy.enforce = true,
+                _ if rule.starts_with("not-in-breach=") => {
+                    let dir = &rule["not-in-breach=".len()..];
+                    if dir.is_empty() {
+                        return Err(PassrusError::InvalidEntry(
+                            "not-in-breach needs the directory of the breach list".to_owned(),
+                        ));
+                    }
+                    policy.breach_list = Some(dir.to_owned());
+                }
+                _ => match rule.strip_prefix("min-length=").map(str::parse) {
+                    Some(Ok(min)) => policy.min_length = min,
+                    _ => {
+                        return Err(PassrusError::InvalidEntry(format!(
+                            "unknown policy rule {:?}",
+                            rule
+                        )))
+                    }
+                },
+            }
+        }
+        Ok(policy)
+    }
+
+    /// returns every rule password breaks, empty if it complies.
+    pub fn check(&self, password: &str, username: &str) -> Vec<Violation> {
+        let mut violations = Vec::new();
+
+        let len = password.chars().count();
+        if len < self.min_length {
+            violations.push(Violation::TooShort {
+                min: self.min_length,
+                actual: len,
+            });
+        }
+        if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            violations.push(Violation::MissingLowercase);
+        }
+        if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            violations.push(Violation::MissingUppercase);
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            violations.push(Violation::MissingDigit);
+        }
+        if self.require_symbol && !password.chars().any(|c| !c.is_ascii_alphanumeric()) {
+            violations.push(Violation::MissingSymbol);
+        }
+        if self.forbid_username && !username.is_empty() && password.eq_ignore_ascii_case(username) {
+            violations.push(Violation::EqualsUsername);
+        }
+        if let Some(dir) = &self.breach_list {
+            match breach_count(Path::new(dir), password) {
+                Ok(0) => {}
+                Ok(count) => violations.push(Violation::Breached(count)),
+                Err(err) => violations.push(Violation::BreachListUnreadable(err.to_string())),
+            }
+        }
+        violations
+    }
+}
+
+/// how often password appears in the breach corpus in dir, 0 if it doesn't. the corpus is the Pwned Passwords list
+/// split by hash prefix, as the haveibeenpwned downloader writes it with `-s false`: a file per first five hex digits
+/// of the upper case SHA-1 of a password, named <prefix>.txt (or just <prefix>), holding <rest of the hash>:<count>
+/// lines. a range file fetched from the range API has the same shape. only that one file is read.
+pub fn breach_count(dir: &Path, password: &str) -> io::Result<u64> {
+    let hash = hex::encode_upper(Sha1::digest(password.as_bytes()));
+    let (prefix, suffix) = hash.split_at(5);
+    let range = match fs::read_to_string(dir.join(format!("{}.txt", prefix))) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => fs::read_to_string(dir.join(prefix)),
+        read => read,
+    }?;
+    for line in range.lines() {
+        if let Some((rest, count)) = line.trim().split_once(':') {
+            if rest.eq_ignore_ascii_case(suffix) {
+                // padding lines in range responses have a count of 0.
+                return Ok(count.trim().parse().unwrap_or(0));
+            }
+        }
+    }
+    Ok(0)
+}
+
+/// passwords that are trivially guessable from the entry's other fields: its username, the local part of its email,
+/// or the host of its url (also without "www." and without the top level domain). case is ignored.
+/// these are always warnings, add_entry_checked never rejects an entry because of them.
+pub fn guessable(entry: &Entry, password: &str) -> Vec<Violation> {
+    let mut warnings = Vec::new();
+    if password.is_empty() {
+        return warnings;
+    }
+    let same = |candidate: &str| !candidate.is_empty() && password.eq_ignore_ascii_case(candidate);
+
+    if same(&entry.username) {
+        warnings.push(Violation::EqualsUsername);
+    }
+    if entry
+        .email
+        .split_once('@')
+        .is_some_and(|(local, _)| same(local))
+    {
+        warnings.push(Violation::EqualsEmailLocalPart);
+    }
+    let host = url_host(&entry.url);
+    let bare = host.strip_prefix("www.").unwrap_or(host);
+    let name = bare.split('.').next().unwrap_or(bare);
+    if same(host) || same(bare) || same(name) {
+        warnings.push(Violation::EqualsUrlHost);
+    }
+    warnings
+}
+
+/// master passwords scoring fewer bits are refused when a vault is created.
+pub const MIN_MASTER_BITS: u32 = 50;
+/// master passwords scoring fewer bits are accepted with a warning.
+pub const GOOD_MASTER_BITS: u32 = 70;
+
+/// common passwords and the words they are built from, see common_passwords.txt.
+const COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+/// a rough estimate of the bits of guessing work password takes. a common password (see COMMON_PASSWORDS), possibly
+/// capitalised, in leetspeak or with digits and symbols before or after it, scores the log2 of the list size plus a bit
+/// per variation and the affixes' own bits. anything else gets log2 of the smallest character set covering it
+/// (lowercase, uppercase, digits, ascii symbols, anything else) for every character, except that characters
+/// continuing a run (aaa, abc, 321) don't count. words outside the list aren't recognised, so this is an upper bound:
+/// a sentence of common words scores far higher than it should.
+pub fn estimate_bits(password: &str) -> f64 {
+    let bits = character_bits(password);
+    match common_password_bits(password) {
+        Some(common) => bits.min(common),
+        None => bits,
+    }
+}
+
+/// the character set estimate of estimate_bits, 0 for an empty password.
+fn character_bits(password: &str) -> f64 {
+    let chars: Vec<char> = password.chars().collect();
+    let mut pool = 0;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) {
+        pool += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) {
+        pool += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_digit()) {
+        pool += 10;
+    }
+    if chars
+        .iter()
+        .any(|c| c.is_ascii() && !c.is_ascii_alphanumeric())
+    {
+        pool += 33;
+    }
+    if chars.iter().any(|c| !c.is_ascii()) {
+        pool += 100;
+    }
+
+    let step = |i: usize| i64::from(u32::from(chars[i])) - i64::from(u32::from(chars[i - 1]));
+    let counted = (0..chars.len())
+        .filter(|&i| !(i >= 2 && step(i).abs() <= 1 && step(i) == step(i - 1)))
+        .count();
+    if counted == 0 {
+        return 0.0;
+    }
+    counted as f64 * f64::from(pool).log2()
+}
+
+/// the bits of password as a variation of a common password, None if its core isn't in COMMON_PASSWORDS. the core is
+/// what is left after trimming digits and symbols from both ends, with 0, 1, 3, 4, @, 5, $ and 7 read as letters.
+fn common_password_bits(password: &str) -> Option<f64> {
+    let is_affix = |c: char| c.is_ascii_digit() || c.is_ascii_punctuation();
+    let rest = password.trim_start_matches(is_affix);
+    let core = rest.trim_end_matches(is_affix);
+    let prefix = &password[..password.len() - rest.len()];
+    let suffix = &rest[core.len()..];
+
+    let lowercase = core.to_lowercase();
+    let letters: String = lowercase
+        .chars()
+        .map(|c| match c {
+            '0' => 'o',
+            '1' => 'i',
+            '3' => 'e',
+            '4' | '@' => 'a',
+            '5' | '$' => 's',
+            '7' => 't',
+            c => c,
+        })
+        .collect();
+    let mut words = COMMON_PASSWORDS
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+    let listed = words.clone().count();
+    if letters.is_empty() || !words.any(|word| word == letters) {
+        return None;
+    }
+
+    let capitals = core.chars().filter(char::is_ascii_uppercase).count();
+    let capitalised = core.chars().next().is_some_and(|c| c.is_ascii_uppercase());
+    let capital_bits = match capitals {
+        0 => 0,
+        1 if capitalised => 1,
+        // any letter could be either case.
+        _ => core.chars().filter(char::is_ascii_alphabetic).count(),
+    };
+    let leet_bits = lowercase
+        .chars()
+        .zip(letters.chars())
+        .filter(|(a, b)| a != b)
+        .count();
+    Some(
+        (listed as f64).log2()
+            + (capital_bits + leet_bits) as f64
+            + character_bits(prefix)
+            + character_bits(suffix),
+    )
+}
+
+/// the host part of url: scheme, userinfo, port and path are dropped. urls without a scheme are taken as a bare host.
+pub(crate) fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    host.split(':').next().unwrap_or(host)
+}
+
+/// comma separated description of violations, for error messages.
+pub fn describe(violations: &[Violation]) -> String {
+    violations
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
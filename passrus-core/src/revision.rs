@@ -0,0 +1,88 @@
+//! revision numbers for optimistic concurrency.
+//!
+//! every entry and container has a revision, bumped by Vault::save when it differs from the vault as opened. a
+//! container's revision also moves when anything below it changes. a client remembers the revision it read and passes
+//! it back with a change; check fails with a conflict if someone else saved in between, instead of silently
+//! overwriting their change.
+use crate::error::{PassrusError, Result};
+use crate::{Container, Entry};
+use std::{collections::HashMap, fmt};
+
+/// fails with PassrusError::Conflict if an expected revision was given and what (an entry or container) is at another.
+pub fn check(what: &dyn fmt::Display, expected: Option<u64>, actual: u64) -> Result<()> {
+    match expected {
+        Some(expected) if expected != actual => Err(PassrusError::Conflict {
+            target: what.to_string(),
+            expected,
+            actual,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// advance the revision of every entry and container of current that changed since opened, the same tree as it was
+/// read. entries are matched by id, so a moved or renamed entry counts as changed; containers by path. new entries
+/// and containers keep the revision they have.
+pub fn bump(current: &mut Container, opened: &Container) {
+    let by_id: HashMap<&str, &Entry> = opened
+        .iter_entries()
+        .map(|(_, entry)| (entry.id.as_str(), entry))
+        .collect();
+    bump_container(current, Some(opened), &by_id);
+}
+
+fn bump_container(
+    current: &mut Container,
+    before: Option<&Container>,
+    by_id: &HashMap<&str, &Entry>,
+) {
+    for entry in current.entries.values_mut() {
+        if let Some(old) = by_id.get(entry.id.as_str()) {
+            // compare with the old revision in place, so only the other fields count.
+            let revision = entry.revision;
+            entry.revision = old.revision;
+            entry.revision = if *entry != **old {
+                old.revision + 1
+            } else {
+                revision
+            };
+        }
+    }
+    for (name, child) in current.children.iter_mut() {
+        bump_container(child, before.and_then(|b| b.children.get(name)), by_id);
+    }
+    if let Some(before) = before {
+        if container_changed(current, before) {
+            current.revision = before.revision + 1;
+        }
+    }
+}
+
+/// whether a container differs from before other than in its own revision. its entries and children are bumped
+/// first, so one that changed no longer has the revision it had before, and comparing ids and revisions is enough.
+fn container_changed(now: &Container, before: &Container) -> bool {
+    let Container {
+        name,
+        children,
+        entries,
+        parent,
+        schema_version,
+        policy,
+        refreshed_at,
+        revision: _,
+    } = now;
+    *name != before.name
+        || *parent != before.parent
+        || *schema_version != before.schema_version
+        || *policy != before.policy
+        || *refreshed_at != before.refreshed_at
+        || entries.len() != before.entries.len()
+        || entries.iter().any(|(key, entry)| {
+            before.entries.get(key).map(|old| (&old.id, old.revision))
+                != Some((&entry.id, entry.revision))
+        })
+        || children.len() != before.children.len()
+        || children.iter().any(|(name, child)| {
+            before.children.get(name).map(|old| old.revision) != Some(child.revision)
+        })
+}
@@ -0,0 +1,168 @@
+//! password rotation reminders.
+//!
+//! an entry can carry a rotation schedule (Entry::rotation) together with when its password last changed: a number of
+//! days, or the first day of every month, quarter or year (UTC). it is due once the next change date has passed; due
+//! lists every such entry in a tree, most overdue first. changing the password (Entry::set_password) starts it over.
+use crate::error::{PassrusError, Result};
+use crate::{Container, EntryRef};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// a calendar period, for changes due on the first day of each.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    Month,
+    Quarter,
+    Year,
+}
+
+impl Period {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Period::Month => "month",
+            Period::Quarter => "quarter",
+            Period::Year => "year",
+        }
+    }
+
+    /// unix time of the first day of the period after the one holding time.
+    fn next_start(&self, time: u64) -> u64 {
+        let (year, month, _) = civil_from_days((time / DAY) as i64);
+        let (year, month) = match self {
+            Period::Month => (year, month + 1),
+            Period::Quarter => (year, (month - 1) / 3 * 3 + 4),
+            Period::Year => (year + 1, 1),
+        };
+        let (year, month) = if month > 12 {
+            (year + 1, month - 12)
+        } else {
+            (year, month)
+        };
+        days_from_civil(year, month, 1) as u64 * DAY
+    }
+}
+
+/// how often an entry's password should change, and when it last did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rotation {
+    /// days between changes, 0 if the schedule is first_of.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub every_days: u32,
+    /// change on the first day of every such period instead of after a number of days.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_of: Option<Period>,
+    /// unix time of the last change, or of when the schedule was set if the password hasn't changed since.
+    pub changed_at: u64,
+}
+
+impl Rotation {
+    pub fn new(every_days: u32, now: u64) -> Self {
+        Rotation {
+            every_days,
+            first_of: None,
+            changed_at: now,
+        }
+    }
+
+    /// parse a schedule: a number of days, or month, quarter or year for the first day of each.
+    pub fn parse(schedule: &str, now: u64) -> Result<Self> {
+        let first_of = match schedule {
+            "month" => Period::Month,
+            "quarter" => Period::Quarter,
+            "year" => Period::Year,
+            days => {
+                return match days.parse::<u32>() {
+                    Ok(days) if days > 0 => Ok(Rotation::new(days, now)),
+                    _ => Err(PassrusError::InvalidEntry(format!(
+                        "{:?} is not a rotation schedule, expected a number of days, month, quarter or year",
+                        schedule
+                    ))),
+                }
+            }
+        };
+        Ok(Rotation {
+            every_days: 0,
+            first_of: Some(first_of),
+            changed_at: now,
+        })
+    }
+
+    /// unix time the next change is due.
+    pub fn due_at(&self) -> u64 {
+        match self.first_of {
+            Some(period) => period.next_start(self.changed_at),
+            None => self
+                .changed_at
+                .saturating_add(u64::from(self.every_days) * DAY),
+        }
+    }
+
+    /// whole days past due at now, None if not due yet.
+    pub fn overdue_days(&self, now: u64) -> Option<u64> {
+        now.checked_sub(self.due_at()).map(|late| late / DAY)
+    }
+}
+
+impl fmt::Display for Rotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.first_of {
+            Some(period) => write!(f, "on the first of every {}", period.name()),
+            None => write!(f, "every {} days", self.every_days),
+        }
+    }
+}
+
+fn is_zero(days: &u32) -> bool {
+    *days == 0
+}
+
+/// (year, month, day) of the day days after 1970-01-01, in the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// days from 1970-01-01 to the given date, the inverse of civil_from_days.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// the entries below root due for rotation at now, with the days they are overdue, most overdue first.
+pub fn due(root: &Container, now: u64) -> Result<Vec<(EntryRef, u64)>> {
+    let mut due = Vec::new();
+    for (path, entry) in root.iter_entries() {
+        if let Some(overdue) = entry
+            .rotation
+            .and_then(|rotation| rotation.overdue_days(now))
+        {
+            due.push((root.entry_ref(&path, entry)?, overdue));
+        }
+    }
+    due.sort_by(|a, b| {
+        b.1.cmp(&a.1)
+            .then_with(|| a.0.to_string().cmp(&b.0.to_string()))
+    });
+    Ok(due)
+}
@@ -0,0 +1,81 @@
+//! versioning of the serialised Container shape.
+//!
+//! every container carries a schema_version. documents written before versioning have none and are treated as version 0.
+//! on load, the JSON is upgraded one version at a time through MIGRATIONS before being deserialised,
+//! so new fields can be added without breaking existing vaults.
+use crate::error::{PassrusError, Result};
+use crate::passman;
+use serde_json::Value;
+use tracing::info;
+
+/// the version written by this build.
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+/// upgrades a document in place from one version to the next.
+type Migration = fn(&mut Value) -> Result<()>;
+
+/// MIGRATIONS[n] upgrades a document from version n to n + 1. append new migrations, never reorder.
+const MIGRATIONS: [Migration; CURRENT_SCHEMA_VERSION as usize] = [v0_to_v1, v1_to_v2];
+
+/// returns the schema_version of a serialised container, 0 if it has none.
+pub fn version_of(value: &Value) -> u32 {
+    value
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as u32
+}
+
+/// upgrade a serialised container to CURRENT_SCHEMA_VERSION. fails if it was written by a newer version.
+pub fn migrate(value: &mut Value) -> Result<()> {
+    let mut version = version_of(value);
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(PassrusError::CorruptFile {
+            reason: format!(
+                "schema version {} is newer than the supported version {}",
+                version, CURRENT_SCHEMA_VERSION
+            ),
+        });
+    }
+
+    while version < CURRENT_SCHEMA_VERSION {
+        info!(target:"schema_migrate", "migrating container from schema version {} to {}", version, version + 1);
+        MIGRATIONS[version as usize](value)?;
+        version += 1;
+    }
+    Ok(())
+}
+
+/// calls f on a serialised container and every container nested below it.
+fn for_each_container(value: &mut Value, f: &mut dyn FnMut(&mut serde_json::Map<String, Value>)) {
+    if let Some(container) = value.as_object_mut() {
+        f(container);
+        if let Some(children) = container.get_mut("children").and_then(Value::as_object_mut) {
+            for child in children.values_mut() {
+                for_each_container(child, f);
+            }
+        }
+    }
+}
+
+/// v1 introduces schema_version itself, the rest of the shape is unchanged.
+fn v0_to_v1(value: &mut Value) -> Result<()> {
+    for_each_container(value, &mut |container| {
+        container.insert("schema_version".to_owned(), Value::from(1));
+    });
+    Ok(())
+}
+
+/// v2 gives every entry an id, which aliases refer to.
+fn v1_to_v2(value: &mut Value) -> Result<()> {
+    for_each_container(value, &mut |container| {
+        container.insert("schema_version".to_owned(), Value::from(2));
+        if let Some(entries) = container.get_mut("entries").and_then(Value::as_object_mut) {
+            for entry in entries.values_mut().filter_map(Value::as_object_mut) {
+                if entry.get("id").and_then(Value::as_str).is_none_or(str::is_empty) {
+                    entry.insert("id".to_owned(), Value::from(passman::new_id()));
+                }
+            }
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,173 @@
+//! the sharded vault layout, for vaults large enough that rewriting a single file on every save hurts.
+//!
+//! a sharded vault is a directory. each top-level container lives in its own "<random>.shard" file, encrypted with a
+//! random key of its own (format v0 layout, the salt in its trailer is unused). manifest.bin holds the root container
+//! without its children and, for each child, the file it is in, that file's key and a hash of its plaintext. the
+//! manifest is encrypted like a single-file vault, with a key from the password, so its tag vouches for every shard
+//! key: a shard swapped for another, or for an older copy of itself (every rewrite gets a new key), fails to decrypt.
+//!
+//! a save rewrites only the shards whose container changed, then the manifest, and then removes shard files the
+//! manifest no longer lists. opening takes a single Argon2 derivation however many shards there are.
+use crate::error::{PassrusError, Result};
+use crate::{cryptman, format, shred, Container};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use std::{collections::BTreeMap, fs, path::Path};
+use tracing::info;
+
+/// the manifest's file name inside the vault directory.
+pub const MANIFEST: &str = "manifest.bin";
+
+const SHARD_EXTENSION: &str = "shard";
+
+/// where each top-level container of a sharded vault is stored, as its manifest lists them.
+#[derive(Clone, Default)]
+pub struct Manifest {
+    shards: BTreeMap<String, Shard>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Shard {
+    /// file name inside the vault directory.
+    file: String,
+    /// hex of the random key the file is encrypted with.
+    key: String,
+    /// hex SHA3-256 of the container's JSON, to tell whether it changed since.
+    hash: String,
+}
+
+#[derive(Serialize)]
+struct ManifestFile<'a> {
+    root: &'a Container,
+    shards: &'a BTreeMap<String, Shard>,
+}
+
+#[derive(Deserialize)]
+struct ManifestFileOwned {
+    root: serde_json::Value,
+    shards: BTreeMap<String, Shard>,
+}
+
+/// open the sharded vault in dir: decrypt the manifest with pass and every shard with its key from the manifest.
+/// the root and each shard are migrated from older schema versions like a single-file vault.
+pub fn open(dir: &str, pass: &str) -> Result<(Container, Manifest)> {
+    let data = fs::read(Path::new(dir).join(MANIFEST))?;
+    let json = cryptman::decrypt_file_mem_gen_key(data, "", pass)?;
+    let manifest: ManifestFileOwned = serde_json::from_slice(&json)?;
+
+    let mut root = Container::new("");
+    root.from_json_arr(&serde_json::to_vec(&manifest.root)?)?;
+    for (name, shard) in &manifest.shards {
+        info!(target:"shard_open", "reading container {} from {}", name, shard.file);
+        let data = fs::read(Path::new(dir).join(&shard.file))?;
+        let json = cryptman::decrypt_mem_with_key(data, &shard.key()?)?;
+        let mut child = Container::new("");
+        child.from_json_arr(&json)?;
+        if child.name != *name {
+            return Err(corrupt(format!(
+                "{} holds container {:?}, the manifest expects {:?}",
+                shard.file, child.name, name
+            )));
+        }
+        root.children.insert(name.clone(), child);
+    }
+    Ok((
+        root,
+        Manifest {
+            shards: manifest.shards,
+        },
+    ))
+}
+
+/// write root to the vault directory dir, creating it if needed. top-level containers unchanged since previous, the
+/// manifest returned by the last open or save, keep their shard files. returns the manifest now on disk.
+pub fn save(dir: &str, root: &mut Container, previous: &Manifest, pass: &str) -> Result<Manifest> {
+    fs::create_dir_all(dir)?;
+    let mut shards = BTreeMap::new();
+    for (name, child) in &root.children {
+        let json = serde_json::to_vec(child)?;
+        let hash = hex::encode(Sha3_256::digest(&json));
+        let shard = match previous.shards.get(name) {
+            Some(shard) if shard.hash == hash && Path::new(dir).join(&shard.file).exists() => {
+                shard.clone()
+            }
+            _ => write_shard(dir, name, json, hash)?,
+        };
+        shards.insert(name.clone(), shard);
+    }
+
+    // the root goes into the manifest without its children, they are in the shards.
+    let children = std::mem::take(&mut root.children);
+    let json = serde_json::to_vec(&ManifestFile {
+        root,
+        shards: &shards,
+    });
+    root.children = children;
+    let mut json = json?;
+
+    let mut salt = [0u8; 32];
+    OsRng.fill_bytes(&mut salt);
+    let (key, salt) = cryptman::pass_2_key(pass, salt)?;
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+    json.reserve_exact(format::TAG_LEN + format::NONCE_LEN + format::SALT_LEN);
+    let manifest_path = format!("{}/{}", dir, MANIFEST);
+    cryptman::encrypt_file_mem_with_salt(json, &manifest_path, &key, &nonce, &salt)?;
+
+    // only now that the manifest no longer lists them can replaced shards go. leftovers of an interrupted save go too.
+    for file in fs::read_dir(dir)? {
+        let path = file?.path();
+        let listed = shards.values().any(|shard| {
+            path.file_name()
+                .is_some_and(|name| name == shard.file.as_str())
+        });
+        if path.extension().is_some_and(|ext| ext == SHARD_EXTENSION) && !listed {
+            info!(target:"shard_save", "removing {}", path.display());
+            shred::remove_file(&path)?;
+        }
+    }
+    Ok(Manifest { shards })
+}
+
+/// encrypt a container's JSON into a new shard file under a fresh random key and name.
+fn write_shard(dir: &str, name: &str, json: Vec<u8>, hash: String) -> Result<Shard> {
+    let mut key = [0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    let mut file_id = [0u8; 16];
+    OsRng.fill_bytes(&mut file_id);
+    let file = format!("{}.{}", hex::encode(file_id), SHARD_EXTENSION);
+    info!(target:"shard_save", "writing container {} to {}", name, file);
+
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+    let mut unused_salt = [0u8; 32];
+    OsRng.fill_bytes(&mut unused_salt);
+    let mut json = json;
+    json.reserve_exact(format::TAG_LEN + format::NONCE_LEN + format::SALT_LEN);
+    cryptman::encrypt_file_mem_with_salt(
+        json,
+        &format!("{}/{}", dir, file),
+        &key,
+        &nonce,
+        &unused_salt,
+    )?;
+    Ok(Shard {
+        file,
+        key: hex::encode(key),
+        hash,
+    })
+}
+
+impl Shard {
+    fn key(&self) -> Result<[u8; 32]> {
+        let mut key = [0u8; 32];
+        hex::decode_to_slice(&self.key, &mut key)
+            .map_err(|_| corrupt(format!("the manifest's key for {} is malformed", self.file)))?;
+        Ok(key)
+    }
+}
+
+fn corrupt(reason: String) -> PassrusError {
+    PassrusError::CorruptFile { reason }
+}
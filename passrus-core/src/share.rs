@@ -0,0 +1,73 @@
+//! one-time sharing of a single entry.
+//!
+//! the entry is serialised on its own, encrypted with a freshly generated random passphrase, and encoded as a text blob.
+//! the blob can travel over any channel; the passphrase is shown once and should go over a different one.
+//! the entry's pass_vec and sealed fields are shared as-is, so decrypt the password and open the fields
+//! (Entry::open_fields) before sharing if they are encrypted with vault keys.
+use crate::error::{PassrusError, Result};
+use crate::{cryptman, Entry};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::{rngs::OsRng, Rng, RngCore};
+
+/// prefix identifying a share blob and its version.
+pub const BLOB_PREFIX: &str = "passrus-share-v1:";
+
+/// no 0/O/1/l/I, so the passphrase survives being read aloud or copied by hand.
+const PASSPHRASE_ALPHABET: &[u8] = b"23456789abcdefghijkmnpqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ";
+/// 6 groups of 4 characters, ~139 bits.
+const PASSPHRASE_GROUPS: usize = 6;
+
+/// a shared entry: the blob to send, and the one-time passphrase that opens it.
+pub struct SharedEntry {
+    pub blob: String,
+    pub passphrase: String,
+}
+
+/// encrypt a copy of entry under a new random passphrase. the parent is cleared, the receiver picks where it goes.
+pub fn share_entry(entry: &Entry) -> Result<SharedEntry> {
+    let mut entry = entry.clone();
+    entry.parent = String::new();
+    let json = serde_json::to_vec(&entry)?;
+
+    let passphrase = generate_passphrase();
+    let mut salt = [0u8; 32];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+    let (key, salt) = cryptman::pass_2_key(&passphrase, salt)?;
+
+    let encrypted = cryptman::encrypt_file_mem_with_salt(json, "", &key, &nonce, &salt)?;
+    Ok(SharedEntry {
+        blob: format!("{}{}", BLOB_PREFIX, URL_SAFE_NO_PAD.encode(encrypted)),
+        passphrase,
+    })
+}
+
+/// open a blob produced by share_entry with its passphrase.
+pub fn import_shared(blob: &str, passphrase: &str) -> Result<Entry> {
+    let encoded = blob
+        .trim()
+        .strip_prefix(BLOB_PREFIX)
+        .ok_or_else(|| PassrusError::CorruptFile {
+            reason: "not a passrus share blob".to_owned(),
+        })?;
+    let encrypted = URL_SAFE_NO_PAD
+        .decode(encoded)
+        .map_err(|err| PassrusError::CorruptFile {
+            reason: format!("share blob is not valid base64: {}", err),
+        })?;
+
+    let json = cryptman::decrypt_file_mem_gen_key(encrypted, "", passphrase.trim())?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+fn generate_passphrase() -> String {
+    let mut groups = Vec::with_capacity(PASSPHRASE_GROUPS);
+    for _ in 0..PASSPHRASE_GROUPS {
+        let group: String = (0..4)
+            .map(|_| PASSPHRASE_ALPHABET[OsRng.gen_range(0..PASSPHRASE_ALPHABET.len())] as char)
+            .collect();
+        groups.push(group);
+    }
+    groups.join("-")
+}
@@ -0,0 +1,80 @@
+//! best effort removal of files that held secrets.
+//!
+//! the file's contents are overwritten with zeros and synced before it is unlinked, so a plain delete doesn't leave the
+//! old bytes in free blocks. this only helps on filesystems that overwrite in place (ext4, xfs, ntfs). copy on write
+//! filesystems (btrfs, zfs, apfs), snapshots, journals with data logging and SSD wear levelling can all keep older
+//! copies that no userspace overwrite reaches; full disk encryption is the only real answer there.
+use crate::error::Result;
+use std::{
+    fs::{self, File, Metadata, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+use tracing::info;
+
+const BLOCK: usize = 64 * 1024;
+
+/// overwrite the file at path with zeros, sync it, then remove it. a missing file is not an error. a symlink or a
+/// file with other hard links is only removed.
+pub fn remove_file<P: AsRef<Path>>(path: P) -> Result<()> {
+    let path = path.as_ref();
+    match overwrite(path) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        // still unlink what we couldn't overwrite, e.g. a read only file.
+        Err(err) => info!(target:"shred", "could not overwrite {}: {}", path.display(), err),
+    }
+    match fs::remove_file(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        res => Ok(res?),
+    }
+}
+
+/// rename from over to, like fs::rename, then overwrite and sync the file that was at to, through a handle opened
+/// before the rename. readers of to see the old file or the new one, never neither, and the old contents don't stay
+/// behind in free blocks (a reader that already had the old file open may read zeros). failing to overwrite the old
+/// file is logged, not an error; the rename has happened by then.
+pub fn replace<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> Result<()> {
+    let to = to.as_ref();
+    let old = match fs::symlink_metadata(to) {
+        Ok(metadata) if owns_contents(&metadata) => OpenOptions::new().write(true).open(to).ok(),
+        _ => None,
+    };
+    fs::rename(from, to)?;
+    if let Some(old) = old {
+        if let Err(err) = zero(&old) {
+            info!(target:"shred", "could not overwrite the old {}: {}", to.display(), err);
+        }
+    }
+    Ok(())
+}
+
+fn overwrite(path: &Path) -> io::Result<()> {
+    if !owns_contents(&fs::symlink_metadata(path)?) {
+        return Ok(());
+    }
+    zero(&OpenOptions::new().write(true).open(path)?)
+}
+
+/// whether path is the only name of its contents: a regular file, not a symlink (whose target could be anything) nor
+/// one with other hard links, which would be zeroed too.
+fn owns_contents(metadata: &Metadata) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        metadata.is_file() && metadata.nlink() == 1
+    }
+    #[cfg(not(unix))]
+    metadata.is_file()
+}
+
+fn zero(mut f: &File) -> io::Result<()> {
+    let mut remaining = f.metadata()?.len();
+    let zeros = [0u8; BLOCK];
+    while remaining > 0 {
+        let n = remaining.min(BLOCK as u64) as usize;
+        f.write_all(&zeros[..n])?;
+        remaining -= n as u64;
+    }
+    f.sync_all()
+}
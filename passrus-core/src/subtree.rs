@@ -0,0 +1,73 @@
+//! moving a container subtree between vaults.
+//!
+//! an exported subtree is a vault file in its own right (format v0 around the subtree's container JSON) encrypted with
+//! its own password, so it can also be opened on its own. entry passwords encrypted with the source vault's password
+//! are re-encrypted under the export password on the way out, and under the target vault's password on the way in.
+use crate::error::{PassrusError, Result};
+use crate::kind::EntryKind;
+use crate::{cryptman, format, passman, Container, ContainerPath};
+use rand::{rngs::OsRng, RngCore};
+use std::collections::HashMap;
+use tracing::info;
+
+/// encrypt a copy of the container at path below root, with the entry passwords moved from vault_pass to export_pass.
+pub fn export(
+    root: &Container,
+    path: &ContainerPath,
+    vault_pass: &str,
+    export_pass: &str,
+) -> Result<Vec<u8>> {
+    let mut subtree = root.get_container(path)?.clone();
+    subtree.parent = String::new();
+    subtree.reencrypt_passwords(vault_pass, export_pass)?;
+
+    let mut salt = [0u8; 32];
+    OsRng.fill_bytes(&mut salt);
+    let (key, salt) = cryptman::pass_2_key(export_pass, salt)?;
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut json = serde_json::to_vec(&subtree)?;
+    json.reserve_exact(format::TAG_LEN + format::NONCE_LEN + format::SALT_LEN);
+    info!(target:"subtree_export", "exporting container {}", path);
+    cryptman::encrypt_file_mem_with_salt(json, "", &key, &nonce, &salt)
+}
+
+/// open a subtree written by export, and move its entry passwords from export_pass to vault_pass.
+/// older schema versions are migrated like a vault's.
+pub fn import(data: Vec<u8>, export_pass: &str, vault_pass: &str) -> Result<Container> {
+    let json = cryptman::decrypt_file_mem_gen_key(data, "", export_pass)?;
+    let mut subtree = Container::new("");
+    subtree.from_json_arr(&json)?;
+    if subtree.name.is_empty() {
+        return Err(PassrusError::CorruptFile {
+            reason: "exported subtree has no container name".to_owned(),
+        });
+    }
+    subtree.reencrypt_passwords(export_pass, vault_pass)?;
+    Ok(subtree)
+}
+
+/// give every entry in the tree a new id, updating aliases within the tree to match. for importing a subtree whose ids
+/// are already taken in the target vault, e.g. a second copy of the same export. entry keys are derived from the id,
+/// so the passwords, encrypted with vault_pass, are re-encrypted under the new ids. if a password doesn't decrypt with
+/// vault_pass the tree is left as it was and the error returned, since new ids would make it unrecoverable.
+pub fn renew_ids(container: &mut Container, vault_pass: &str) -> Result<()> {
+    let mut tree = container.clone();
+    tree.decrypt_passwords(vault_pass)?;
+    let mut renamed = HashMap::new();
+    tree.walk_mut(|_, entry| {
+        let id = passman::new_id();
+        renamed.insert(std::mem::replace(&mut entry.id, id.clone()), id);
+    });
+    tree.walk_mut(|_, entry| {
+        if let EntryKind::Alias(alias) = &mut entry.kind {
+            if let Some(id) = renamed.get(&alias.target) {
+                alias.target = id.clone();
+            }
+        }
+    });
+    tree.encrypt_passwords(vault_pass)?;
+    *container = tree;
+    Ok(())
+}
@@ -0,0 +1,157 @@
+use crate::error::Result;
+use crate::{cryptman, format, passman, revision, shard, Container, Entry};
+use tracing::{info, instrument};
+use rand::{rngs::OsRng, RngCore};
+use std::{fs, io, path::Path};
+
+/// how a vault is stored on disk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    /// one encrypted file, see format.
+    File,
+    /// a directory with a file per top-level container, see shard.
+    Sharded,
+}
+
+/// a container tree together with the path of the encrypted file (or sharded directory) it lives in.
+pub struct Vault {
+    pub path: String,
+    pub root: Container,
+    /// the tree as last read or written, to tell which entries and containers a save changes.
+    saved: Option<Container>,
+    /// where the shards are, for a sharded vault.
+    shards: Option<shard::Manifest>,
+}
+
+impl Vault {
+    /// instantiate a new, empty vault, expects the file path and the name of the root container. nothing is written until save is called.
+    pub fn new(path: &str, name: &str) -> Self {
+        Vault {
+            path: path.to_owned(),
+            root: Container::new(name),
+            saved: None,
+            shards: None,
+        }
+    }
+
+    /// read the encrypted file at path, decrypt it with the given password and parse the container tree from it.
+    /// a directory is opened as a sharded vault.
+    #[instrument(skip_all, fields(path = path))]
+    pub fn open(path: &str, pass: &str) -> Result<Self> {
+        if Path::new(path).is_dir() {
+            info!(target:"vault_open", "opening sharded vault:{}...", path);
+            let (root, manifest) = shard::open(path, pass)?;
+            return Ok(Vault {
+                path: path.to_owned(),
+                saved: Some(root.clone()),
+                root,
+                shards: Some(manifest),
+            });
+        }
+        info!(target:"vault_open", "opening vault file:{}...", path);
+        let file_data = fs::read(path)?;
+        let decrypted = cryptman::decrypt_file_mem_gen_key(file_data, "", pass)?;
+
+        let mut root = Container::new("");
+        root.from_json_arr(decrypted.as_slice())?;
+        info!(target:"vault_open", "vault opened successfully.");
+        Ok(Vault {
+            path: path.to_owned(),
+            saved: Some(root.clone()),
+            root,
+            shards: None,
+        })
+    }
+
+    /// serialise the container tree and write it to the vault's path, encrypted with a key derived from the password.
+    /// a fresh salt and nonce are generated on every save. the revisions of whatever changed since are bumped first.
+    #[instrument(skip_all, fields(path = %self.path))]
+    pub fn save(&mut self, pass: &str) -> Result<()> {
+        info!(target:"vault_save", "saving vault file:{}...", self.path);
+        if let Some(saved) = &self.saved {
+            revision::bump(&mut self.root, saved);
+        }
+        if let Some(manifest) = &self.shards {
+            self.shards = Some(shard::save(&self.path, &mut self.root, manifest, pass)?);
+            self.saved = Some(self.root.clone());
+            info!(target:"vault_save", "vault saved successfully.");
+            return Ok(());
+        }
+        let mut salt = [0u8; 32];
+        OsRng.fill_bytes(&mut salt);
+        let (key, salt) = cryptman::pass_2_key(pass, salt)?;
+
+        let mut nonce = [0u8; 24];
+        OsRng.fill_bytes(&mut nonce);
+
+        // the plaintext buffer is encrypted in place and reused as the file contents, reserve room for the tag and trailer up front.
+        let mut json = Vec::new();
+        serde_json::to_writer(&mut json, &self.root)?;
+        json.reserve_exact(format::TAG_LEN + format::NONCE_LEN + format::SALT_LEN);
+        cryptman::encrypt_file_mem_with_salt(json, &self.path, &key, &nonce, &salt)?;
+        self.saved = Some(self.root.clone());
+        info!(target:"vault_save", "vault saved successfully.");
+        Ok(())
+    }
+
+    /// apply f to the tree and save it, all or nothing: if f or the save fails, the vault is left as it was, in
+    /// memory and on disk. for changes made of several steps that must not be saved half done, like moving an entry
+    /// (remove it, then add it elsewhere) or an import.
+    pub fn transaction<T>(
+        &mut self,
+        pass: &str,
+        f: impl FnOnce(&mut Container) -> Result<T>,
+    ) -> Result<T> {
+        let before = self.root.clone();
+        let result = f(&mut self.root).and_then(|value| self.save(pass).map(|()| value));
+        if result.is_err() {
+            info!(target:"vault_transaction", "rolling back");
+            self.root = before;
+        }
+        result
+    }
+
+    /// how the vault is stored, a directory opened by open is Sharded.
+    pub fn layout(&self) -> Layout {
+        match self.shards {
+            Some(_) => Layout::Sharded,
+            None => Layout::File,
+        }
+    }
+
+    /// save the vault to a new path in the given layout, which is where later saves go too. path must not exist yet.
+    pub fn save_as(&mut self, path: &str, layout: Layout, pass: &str) -> Result<()> {
+        if Path::new(path).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", path),
+            )
+            .into());
+        }
+        self.path = path.to_owned();
+        self.shards = match layout {
+            Layout::File => None,
+            Layout::Sharded => Some(shard::Manifest::default()),
+        };
+        self.save(pass)
+    }
+
+    /// re-encrypt every entry password and then the vault itself under fresh salts and nonces, so ciphertext captured
+    /// before (old backups, copies of the file) no longer matches anything current. now, the unix time, is recorded
+    /// as the root container's refreshed_at.
+    pub fn refresh_crypto(&mut self, pass: &str, now: u64) -> Result<()> {
+        info!(target:"vault_refresh_crypto", "re-encrypting entry passwords...");
+        self.root.reencrypt_passwords(pass, pass)?;
+        self.root.refreshed_at = Some(now);
+        if self.shards.is_some() {
+            // forget which shards are unchanged, so every one is rewritten under a new key.
+            self.shards = Some(shard::Manifest::default());
+        }
+        self.save(pass)
+    }
+
+    /// returns every entry in the vault whose field_name matches target_value. see passman::get_entries_by_field.
+    pub fn search(&self, field_name: &str, target_value: &str) -> Vec<Entry> {
+        passman::get_entries_by_field(&self.root, field_name, target_value)
+    }
+}
@@ -0,0 +1,171 @@
+//! encryption of arbitrary files with the same primitives as vaults: an Argon2 key from a passphrase and XChaCha20Poly1305.
+//!
+//! unlike vault files, these are streamed in fixed size chunks (the STREAM construction, big endian 32 bit counter),
+//! so files of any size can be handled in constant memory. layout:
+//!
+//! | length          | content                                                           |
+//! |-----------------|-------------------------------------------------------------------|
+//! | 4               | magic `PRSF`                                                      |
+//! | 1               | version, currently 1                                              |
+//! | 32              | Argon2 salt                                                       |
+//! | 19              | STREAM nonce prefix                                               |
+//! | CHUNK_LEN + 16  | repeated: full chunks of ciphertext and tag                       |
+//! | <= CHUNK_LEN+16 | final chunk, encrypted with the last-block flag (may be just a tag) |
+use crate::{cryptman, shred};
+use crate::error::{PassrusError, Result};
+use chacha20poly1305::{
+    aead::{stream, NewAead},
+    XChaCha20Poly1305,
+};
+use rand::{rngs::OsRng, RngCore};
+use std::{
+    fs::File,
+    io::{Read, Write},
+};
+use tracing::{info, instrument};
+
+pub const MAGIC: &[u8; 4] = b"PRSF";
+pub const VERSION: u8 = 1;
+/// plaintext bytes per chunk.
+pub const CHUNK_LEN: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+const SALT_LEN: usize = 32;
+const NONCE_LEN: usize = 19;
+const HEADER_LEN: usize = MAGIC.len() + 1 + SALT_LEN + NONCE_LEN;
+
+/// encrypt the file at src into dst with a key derived from pass.
+/// progress is called after every chunk with the plaintext bytes processed so far and the total.
+#[instrument(skip_all, fields(src = src, dst = dst))]
+pub fn encrypt_file(
+    src: &str,
+    dst: &str,
+    pass: &str,
+    progress: &mut dyn FnMut(u64, u64),
+) -> Result<()> {
+    info!(target:"vaultfile_encrypt", "encrypting {} to {}...", src, dst);
+    let mut input = File::open(src)?;
+    let total = input.metadata()?.len();
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let key = cryptman::pass_2_key(pass, salt)?.0;
+
+    let mut output = File::create(dst)?;
+    output.write_all(MAGIC)?;
+    output.write_all(&[VERSION])?;
+    output.write_all(&salt)?;
+    output.write_all(&nonce)?;
+
+    let cipher = XChaCha20Poly1305::new(&key.into());
+    let mut encryptor = stream::EncryptorBE32::from_aead(cipher, nonce.as_ref().into());
+
+    let mut buf = vec![0u8; CHUNK_LEN];
+    let mut done: u64 = 0;
+    loop {
+        let n = read_full(&mut input, &mut buf)?;
+        done += n as u64;
+        if n == CHUNK_LEN {
+            let chunk = encryptor
+                .encrypt_next(buf.as_slice())
+                .map_err(|err| PassrusError::Crypto(format!("encrypting chunk: {}", err)))?;
+            output.write_all(&chunk)?;
+            progress(done, total);
+        } else {
+            let chunk = encryptor
+                .encrypt_last(&buf[..n])
+                .map_err(|err| PassrusError::Crypto(format!("encrypting last chunk: {}", err)))?;
+            output.write_all(&chunk)?;
+            progress(done, total);
+            break;
+        }
+    }
+    output.sync_all()?;
+    info!(target:"vaultfile_encrypt", "encrypted {} bytes.", done);
+    Ok(())
+}
+
+/// decrypt a file written by encrypt_file from src into dst.
+/// progress is called after every chunk with the encrypted bytes processed so far and the total.
+/// on failure the partially written dst is overwritten and removed, so no unauthenticated plaintext is left behind.
+#[instrument(skip_all, fields(src = src, dst = dst))]
+pub fn decrypt_file(
+    src: &str,
+    dst: &str,
+    pass: &str,
+    progress: &mut dyn FnMut(u64, u64),
+) -> Result<()> {
+    info!(target:"vaultfile_decrypt", "decrypting {} to {}...", src, dst);
+    let res = decrypt_file_inner(src, dst, pass, progress);
+    if res.is_err() {
+        let _ = shred::remove_file(dst);
+    }
+    res
+}
+
+fn decrypt_file_inner(
+    src: &str,
+    dst: &str,
+    pass: &str,
+    progress: &mut dyn FnMut(u64, u64),
+) -> Result<()> {
+    let mut input = File::open(src)?;
+    let total = input.metadata()?.len();
+
+    let mut header = [0u8; HEADER_LEN];
+    if read_full(&mut input, &mut header)? != HEADER_LEN || &header[..MAGIC.len()] != MAGIC {
+        return Err(PassrusError::CorruptFile {
+            reason: format!("{} is not a passrus encrypted file", src),
+        });
+    }
+    if header[MAGIC.len()] != VERSION {
+        return Err(PassrusError::CorruptFile {
+            reason: format!("unsupported encrypted file version {}", header[MAGIC.len()]),
+        });
+    }
+    let mut salt = [0u8; SALT_LEN];
+    salt.copy_from_slice(&header[MAGIC.len() + 1..MAGIC.len() + 1 + SALT_LEN]);
+    let nonce = &header[MAGIC.len() + 1 + SALT_LEN..];
+
+    let key = cryptman::pass_2_key(pass, salt)?.0;
+    let cipher = XChaCha20Poly1305::new(&key.into());
+    let mut decryptor = stream::DecryptorBE32::from_aead(cipher, nonce.into());
+
+    let mut output = File::create(dst)?;
+    let mut buf = vec![0u8; CHUNK_LEN + TAG_LEN];
+    let mut done = HEADER_LEN as u64;
+    loop {
+        let n = read_full(&mut input, &mut buf)?;
+        done += n as u64;
+        if n == buf.len() {
+            let chunk = decryptor
+                .decrypt_next(buf.as_slice())
+                .map_err(|_| PassrusError::WrongPassword)?;
+            output.write_all(&chunk)?;
+            progress(done, total);
+        } else {
+            let chunk = decryptor
+                .decrypt_last(&buf[..n])
+                .map_err(|_| PassrusError::WrongPassword)?;
+            output.write_all(&chunk)?;
+            progress(done, total);
+            break;
+        }
+    }
+    output.sync_all()?;
+    info!(target:"vaultfile_decrypt", "decrypted {} bytes.", done);
+    Ok(())
+}
+
+/// read until buf is full or the reader is exhausted, returning the number of bytes read.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
@@ -0,0 +1,66 @@
+use passrus_core::{Container, Entry, EntryRef, PassrusError};
+
+fn entry(url: &str) -> Entry {
+    Entry::new("alice", Vec::new(), "", url)
+}
+
+#[test]
+fn entry_ref_names_nested_entries() {
+    let mut root = Container::new("root");
+    let mut work = Container::new("work");
+    work.add_entry(entry("github.com"));
+    root.add_child(work).unwrap();
+    root.add_entry(entry("example.com"));
+
+    let mut refs: Vec<String> = root
+        .iter_entries()
+        .map(|(path, entry)| root.entry_ref(&path, entry).unwrap().to_string())
+        .collect();
+    refs.sort();
+    assert_eq!(refs, ["example.com", "work/github.com"]);
+    let github = EntryRef::parse("work/github.com").unwrap();
+    let id = root.resolve(&github).unwrap().id.clone();
+    assert_eq!(root.path_of(&id).unwrap(), Some(github));
+}
+
+#[test]
+fn entry_ref_rejects_unaddressable_container_names() {
+    // vaults written before container names were validated can hold these, bypassing add_child.
+    for name in ["a/b", ""] {
+        let mut root = Container::new("root");
+        let mut odd = Container::new(name);
+        odd.add_entry(entry("github.com"));
+        root.children.insert(name.to_owned(), odd);
+
+        let (path, found) = root.iter_entries().next().unwrap();
+        assert!(matches!(
+            root.entry_ref(&path, found),
+            Err(PassrusError::InvalidPath(_))
+        ));
+        assert!(matches!(
+            root.path_of(&found.id),
+            Err(PassrusError::InvalidPath(_))
+        ));
+    }
+}
+
+#[test]
+fn entry_ref_rejects_a_slash_name_shadowing_a_real_path() {
+    // "a/b" as one name renders like the real path a -> b, which must not be taken for it.
+    let mut root = Container::new("root");
+    let mut a = Container::new("a");
+    let mut b = Container::new("b");
+    b.add_entry(entry("github.com"));
+    a.add_child(b).unwrap();
+    root.add_child(a).unwrap();
+    let mut odd = Container::new("a/b");
+    odd.add_entry(entry("github.com"));
+    root.children.insert("a/b".to_owned(), odd);
+
+    let results: Vec<bool> = root
+        .iter_entries()
+        .map(|(path, entry)| root.entry_ref(&path, entry).is_ok())
+        .collect();
+    assert_eq!(results.iter().filter(|ok| **ok).count(), 1);
+    assert_eq!(results.len(), 2);
+}
@@ -0,0 +1,58 @@
+use passrus_core::{cryptman, format, PassrusError};
+
+const PASS: &str = "password";
+const BIN: &[u8] = include_bytes!("golden/v0_basic.bin");
+const JSON: &[u8] = include_bytes!("golden/v0_basic.json");
+
+fn golden_salt() -> [u8; 32] {
+    let mut salt = [0u8; 32];
+    for (i, b) in salt.iter_mut().enumerate() {
+        *b = i as u8;
+    }
+    salt
+}
+
+fn golden_nonce() -> [u8; 24] {
+    let mut nonce = [0u8; 24];
+    for (i, b) in nonce.iter_mut().enumerate() {
+        *b = 0x80 + i as u8;
+    }
+    nonce
+}
+
+#[test]
+fn parse_splits_nonce_and_salt_from_tail() {
+    let file = format::parse(BIN).unwrap();
+    assert_eq!(file.salt, golden_salt());
+    assert_eq!(file.nonce, golden_nonce());
+    assert_eq!(file.ciphertext.len(), JSON.len() + format::TAG_LEN);
+    assert_eq!(file.to_bytes(), BIN);
+}
+
+#[test]
+fn golden_file_decrypts_to_golden_json() {
+    let decrypted = cryptman::decrypt_file_mem_gen_key(BIN.to_vec(), "", PASS).unwrap();
+    assert_eq!(decrypted, JSON);
+    format::validate(BIN, Some(PASS)).unwrap();
+}
+
+#[test]
+fn golden_json_encrypts_to_golden_file() {
+    let (key, salt) = cryptman::pass_2_key(PASS, golden_salt()).unwrap();
+    let encrypted =
+        cryptman::encrypt_file_mem_with_salt(JSON.to_vec(), "", &key, &golden_nonce(), &salt)
+            .unwrap();
+    assert_eq!(encrypted, BIN);
+}
+
+#[test]
+fn validate_rejects_truncated_and_wrong_password() {
+    assert!(matches!(
+        format::validate(&BIN[..format::MIN_LEN - 1], None),
+        Err(PassrusError::CorruptFile { .. })
+    ));
+    assert!(matches!(
+        format::validate(BIN, Some("not the password")),
+        Err(PassrusError::WrongPassword)
+    ));
+}
@@ -0,0 +1,279 @@
+use passrus_core::kind::{Card, EntryKind, RecoveryCodes};
+use passrus_core::{Container, Entry, PassrusError};
+
+const PASS: &str = "vault password";
+
+fn card(number: &str) -> Result<Card, PassrusError> {
+    Card::from_fields([("number", number), ("expiry", "09/29"), ("cvv", "123")])
+}
+
+fn card_entry() -> Entry {
+    let mut entry = Entry::new("", Vec::new(), "", "visa");
+    entry
+        .set_kind(EntryKind::Card(card("4111 1111 1111 1111").unwrap()), PASS)
+        .unwrap();
+    entry
+}
+
+fn number(entry: &Entry) -> String {
+    entry.kind.field_values("number").concat()
+}
+
+#[test]
+fn card_numbers_must_pass_the_luhn_check() {
+    for valid in [
+        "4111 1111 1111 1111",
+        "5500-0055-5555-5559",
+        "378282246310005",
+        "6011111111111117",
+    ] {
+        assert!(card(valid).is_ok(), "{}", valid);
+    }
+    for invalid in [
+        // one digit off.
+        "4111 1111 1111 1112",
+        // two adjacent digits swapped.
+        "1411 1111 1111 1111",
+        "41111111111",
+        "41111111111111111111",
+        "4111 1111 1111 111a",
+        "",
+    ] {
+        assert!(
+            matches!(card(invalid), Err(PassrusError::InvalidEntry(_))),
+            "{}",
+            invalid
+        );
+    }
+    assert_eq!(
+        card("4111-1111 1111-1111").unwrap().number,
+        "4111111111111111"
+    );
+}
+
+#[test]
+fn masked_numbers_keep_the_last_four_characters() {
+    let kind = EntryKind::Card(card("4111111111111111").unwrap());
+    assert!(kind
+        .display_fields(false)
+        .contains(&("number", "•••• 1111".to_owned())));
+
+    // numbers aren't validated when read from a vault file, multi byte characters must not split.
+    let odd = EntryKind::Card(Card {
+        number: "4111 ٤٣٢١".to_owned(),
+        ..Card::default()
+    });
+    assert!(odd
+        .display_fields(false)
+        .contains(&("number", "•••• ٤٣٢١".to_owned())));
+}
+
+#[test]
+fn protected_fields_are_sealed_under_the_entry_key() {
+    let entry = card_entry();
+    assert!(!entry.sealed.is_empty());
+    assert_eq!(number(&entry), "");
+    assert_eq!(entry.kind.field_values("cvv"), vec![""]);
+    assert_eq!(entry.kind.field_values("expiry"), vec!["09/29"]);
+    let json = serde_json::to_string(&entry).unwrap();
+    assert!(!json.contains("4111111111111111") && !json.contains("\"123\""));
+
+    let mut wrong = entry.clone();
+    assert!(matches!(
+        wrong.open_fields("not the password"),
+        Err(PassrusError::WrongPassword)
+    ));
+    let mut opened = entry.clone();
+    opened.open_fields(PASS).unwrap();
+    assert!(opened.sealed.is_empty());
+    assert_eq!(number(&opened), "4111111111111111");
+    assert_eq!(opened.kind.field_values("cvv"), vec!["123"]);
+
+    // sealed under this entry's key, not any entry's.
+    let mut moved = entry.clone();
+    moved.id = "another id".to_owned();
+    assert!(moved.open_fields(PASS).is_err());
+}
+
+#[test]
+fn every_protected_field_of_every_kind_is_sealed() {
+    let kinds: [(&str, &[(&str, &str)]); 4] = [
+        (
+            "card",
+            &[
+                ("cardholder", "Alice"),
+                ("number", "4111 1111 1111 1111"),
+                ("expiry", "09/29"),
+                ("cvv", "123"),
+                ("pin", "4321"),
+            ],
+        ),
+        (
+            "identity",
+            &[
+                ("name", "Alice"),
+                ("national_id", "AB123456C"),
+                ("passport_number", "P1234567"),
+            ],
+        ),
+        ("note", &[("title", "wifi"), ("body", "hunter2")]),
+        (
+            "recovery_codes",
+            &[("code", "aaaa-1111"), ("code", "bbbb-2222")],
+        ),
+    ];
+    // a new kind with protected fields has to be listed here too.
+    for name in EntryKind::NAMES {
+        let schema = EntryKind::schema_of(name).unwrap();
+        let listed = kinds.iter().any(|(kind, _)| *kind == name);
+        assert_eq!(listed, schema.iter().any(|spec| spec.protected), "{}", name);
+    }
+
+    for (name, fields) in kinds {
+        let kind = EntryKind::from_fields(name, fields).unwrap();
+        let mut entry = Entry::new("", Vec::new(), "", name);
+        entry.set_kind(kind.clone(), PASS).unwrap();
+        for spec in kind.schema().iter().filter(|spec| spec.protected) {
+            assert!(
+                entry
+                    .kind
+                    .field_values(spec.name)
+                    .iter()
+                    .all(|v| v.is_empty()),
+                "{}.{} is not sealed",
+                name,
+                spec.name
+            );
+        }
+        entry.open_fields(PASS).unwrap();
+        assert_eq!(entry.kind, kind);
+    }
+}
+
+#[test]
+fn every_field_reads_back_what_was_parsed() {
+    let kinds: [(&str, &[(&str, &str)]); 5] = [
+        (
+            "card",
+            &[
+                ("cardholder", "Alice"),
+                ("number", "4111111111111111"),
+                ("expiry", "09/29"),
+                ("cvv", "123"),
+                ("pin", "4321"),
+            ],
+        ),
+        (
+            "identity",
+            &[
+                ("name", "Alice"),
+                ("address", "1 Main St"),
+                ("address", "2 High St"),
+                ("phone", "555 0100"),
+                ("national_id", "AB123456C"),
+                ("passport_number", "P1234567"),
+                ("protect", "phone"),
+            ],
+        ),
+        ("note", &[("title", "wifi"), ("body", "hunter2")]),
+        ("alias", &[("target", "some-id")]),
+        ("recovery_codes", &[("code", "aaaa-1111")]),
+    ];
+    // every field of every kind but a login's has to be given above.
+    for name in EntryKind::NAMES {
+        let given: Vec<&str> = kinds
+            .iter()
+            .filter(|(kind, _)| *kind == name)
+            .flat_map(|(_, fields)| fields.iter().map(|(field, _)| *field))
+            .collect();
+        for spec in EntryKind::schema_of(name).unwrap() {
+            assert!(given.contains(&spec.name), "{}.{}", name, spec.name);
+        }
+    }
+
+    for (name, fields) in kinds {
+        let kind = EntryKind::from_fields(name, fields).unwrap();
+        for (field, value) in fields {
+            assert!(
+                kind.field_values(field).contains(value),
+                "{}.{}",
+                name,
+                field
+            );
+        }
+    }
+}
+
+#[test]
+fn patching_a_sealed_card_keeps_the_other_fields() {
+    let mut entry = card_entry();
+    entry
+        .apply_patch(&[("expiry", Some("10/30"))], PASS, 0)
+        .unwrap();
+    assert!(!entry.sealed.is_empty());
+    entry.open_fields(PASS).unwrap();
+    assert_eq!(number(&entry), "4111111111111111");
+    assert_eq!(entry.kind.field_values("expiry"), vec!["10/30"]);
+
+    let mut entry = card_entry();
+    assert!(entry
+        .apply_patch(&[("number", Some("4111111111111112"))], PASS, 0)
+        .is_err());
+    assert!(entry
+        .apply_patch(&[("cvv", Some("999"))], "wrong", 0)
+        .is_err());
+}
+
+#[test]
+fn reencrypting_moves_sealed_fields_and_seals_old_ones() {
+    let mut root = Container::new("root");
+    root.add_entry(card_entry());
+    // a card from before fields were sealed.
+    let mut legacy = Entry::new("", Vec::new(), "", "legacy");
+    legacy.kind = EntryKind::Card(card("5500005555555559").unwrap());
+    root.add_entry(legacy);
+
+    root.reencrypt_passwords(PASS, "new password").unwrap();
+    for (key, expected) in [("visa", "4111111111111111"), ("legacy", "5500005555555559")] {
+        let mut entry = root.get_entry(key).unwrap().clone();
+        assert_eq!(number(&entry), "", "{}", key);
+        assert!(entry.open_fields(PASS).is_err());
+        entry.open_fields("new password").unwrap();
+        assert_eq!(number(&entry), expected);
+    }
+}
+
+#[test]
+fn recovery_codes_are_sealed_but_their_use_is_not() {
+    let mut codes = RecoveryCodes::new(["aaaa-1111", "bbbb-2222", "cccc-3333"].map(str::to_owned));
+    codes.use_next(100);
+    let mut entry = Entry::new("", Vec::new(), "", "github-recovery");
+    entry
+        .set_kind(EntryKind::RecoveryCodes(codes), PASS)
+        .unwrap();
+
+    let json = serde_json::to_string(&entry).unwrap();
+    assert!(!json.contains("aaaa-1111") && !json.contains("cccc-3333"));
+    assert!(entry
+        .kind
+        .display_fields(false)
+        .contains(&("remaining", "2 of 3".to_owned())));
+
+    entry.open_fields(PASS).unwrap();
+    match &mut entry.kind {
+        EntryKind::RecoveryCodes(codes) => {
+            assert_eq!(codes.use_next(200).as_deref(), Some("bbbb-2222"))
+        }
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn recovery_codes_need_at_least_one_code() {
+    let mut entry = Entry::new("", Vec::new(), "", "github-recovery");
+    let empty = RecoveryCodes::new(["  ".to_owned()]);
+    assert!(matches!(
+        entry.set_kind(EntryKind::RecoveryCodes(empty), PASS),
+        Err(PassrusError::InvalidEntry(_))
+    ));
+}
@@ -0,0 +1,79 @@
+use passrus_core::matching::{self, MatchRule};
+use passrus_core::{Entry, PassrusError};
+
+fn entry(url: &str, rules: &[&str]) -> Entry {
+    let mut entry = Entry::new("alice", Vec::new(), "", url);
+    entry.matching_rules = rules
+        .iter()
+        .map(|rule| MatchRule::parse(rule).unwrap())
+        .collect();
+    entry
+}
+
+#[test]
+fn without_rules_the_base_domain_matches() {
+    let github = entry("https://github.com/login", &[]);
+    assert!(matching::matches(&github, "https://github.com/"));
+    assert!(matching::matches(&github, "https://gist.GitHub.com./x"));
+    assert!(!matching::matches(&github, "https://github.io/"));
+    assert!(!matching::matches(&github, "https://notgithub.com/"));
+    assert!(!matching::matches(&github, "about:blank"));
+
+    // country code second levels belong to the base domain.
+    let shop = entry("https://www.shop.co.uk", &[]);
+    assert!(matching::matches(&shop, "https://pay.shop.co.uk/"));
+    assert!(!matching::matches(&shop, "https://other.co.uk/"));
+
+    // ip addresses only match themselves.
+    let router = entry("http://192.168.1.1/admin", &[]);
+    assert!(matching::matches(&router, "http://192.168.1.1:8080/"));
+    assert!(!matching::matches(&router, "http://10.168.1.1/"));
+}
+
+#[test]
+fn exact_host_and_subdomains() {
+    let exact = entry("https://app.example.com", &["exact-host"]);
+    assert!(matching::matches(&exact, "https://app.example.com/login"));
+    assert!(!matching::matches(&exact, "https://eu.app.example.com/"));
+    assert!(!matching::matches(&exact, "https://example.com/"));
+
+    let below = entry("https://app.example.com", &["subdomains"]);
+    assert!(matching::matches(&below, "https://app.example.com/"));
+    assert!(matching::matches(&below, "https://eu.app.example.com/"));
+    assert!(!matching::matches(&below, "https://example.com/"));
+    assert!(!matching::matches(&below, "https://evilapp.example.com/"));
+}
+
+#[test]
+fn regex_matches_the_whole_url_and_any_rule_is_enough() {
+    let rules = ["regex:^https://example\\.com/admin/", "exact-host"];
+    let admin = entry("https://intranet.example.org", &rules);
+    assert!(matching::matches(&admin, "https://example.com/admin/users"));
+    assert!(!matching::matches(&admin, "https://example.com/login"));
+    assert!(matching::matches(&admin, "https://intranet.example.org/"));
+}
+
+#[test]
+fn never_wins_over_other_rules() {
+    let hidden = entry("https://github.com", &["exact-host", "never"]);
+    assert!(!matching::matches(&hidden, "https://github.com/"));
+}
+
+#[test]
+fn parse_reads_what_display_writes() {
+    for rule in [
+        "exact-host",
+        "base-domain",
+        "subdomains",
+        "never",
+        "regex:^https://a\\.b/",
+    ] {
+        assert_eq!(MatchRule::parse(rule).unwrap().to_string(), rule);
+    }
+    for rule in ["exact", "regex:(unclosed", ""] {
+        assert!(matches!(
+            MatchRule::parse(rule),
+            Err(PassrusError::InvalidEntry(_))
+        ));
+    }
+}
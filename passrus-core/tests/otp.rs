@@ -0,0 +1,294 @@
+use passrus_core::otp::{self, OtpKind, OtpSeed};
+use passrus_core::PassrusError;
+
+const SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+fn seed() -> OtpSeed {
+    otp::parse_uri(&format!("otpauth://totp/Example:alice?secret={}", SECRET)).unwrap()
+}
+
+fn invalid<T: std::fmt::Debug>(res: Result<T, PassrusError>) -> bool {
+    matches!(res, Err(PassrusError::InvalidOtp(_)))
+}
+
+#[test]
+fn out_of_range_digits_and_periods_are_rejected_when_parsing() {
+    for query in [
+        "digits=5",
+        "digits=11",
+        "digits=20",
+        // wraps around to 6 if truncated to u32.
+        "digits=4294967302",
+        "period=0",
+    ] {
+        let uri = format!("otpauth://totp/alice?secret={}&{}", SECRET, query);
+        assert!(invalid(otp::parse_uri(&uri)), "{}", query);
+    }
+    let uri = format!("otpauth://totp/alice?secret={}&digits=10&period=1", SECRET);
+    assert!(otp::parse_uri(&uri).is_ok());
+}
+
+#[test]
+fn out_of_range_digits_and_periods_are_rejected_when_deserialising() {
+    let json = serde_json::to_value(seed()).unwrap();
+    assert_eq!(
+        serde_json::from_value::<OtpSeed>(json.clone()).unwrap(),
+        seed()
+    );
+
+    for (field, value) in [
+        ("digits", serde_json::json!(20)),
+        ("digits", serde_json::json!(0)),
+        ("kind", serde_json::json!({"totp": {"period": 0}})),
+    ] {
+        let mut json = json.clone();
+        json[field] = value;
+        let err = serde_json::from_value::<OtpSeed>(json).unwrap_err();
+        assert!(err.to_string().contains("invalid otp seed"), "{}", err);
+    }
+}
+
+#[test]
+fn seeds_changed_by_hand_fail_instead_of_panicking() {
+    let mut zero_period = seed();
+    zero_period.kind = OtpKind::Totp { period: 0 };
+    assert!(invalid(zero_period.generate(59)));
+
+    let mut long = seed();
+    long.digits = 20;
+    assert!(invalid(long.generate(59)));
+    assert!(invalid(otp::hotp(b"key", otp::Algorithm::Sha1, 20, 0)));
+}
+
+#[test]
+fn hotp_matches_rfc_4226() {
+    let expected = [
+        "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583", "399871",
+        "520489",
+    ];
+    for (counter, code) in expected.iter().enumerate() {
+        assert_eq!(
+            otp::hotp(
+                b"12345678901234567890",
+                otp::Algorithm::Sha1,
+                6,
+                counter as u64
+            )
+            .unwrap(),
+            *code
+        );
+    }
+}
+
+#[test]
+fn totp_matches_rfc_6238() {
+    let sha1 = b"12345678901234567890".as_slice();
+    let sha256 = b"12345678901234567890123456789012".as_slice();
+    let sha512 = b"1234567890123456789012345678901234567890123456789012345678901234".as_slice();
+    let vectors = [
+        (59, ["94287082", "46119246", "90693936"]),
+        (1111111109, ["07081804", "68084774", "25091201"]),
+        (1111111111, ["14050471", "67062674", "99943326"]),
+        (1234567890, ["89005924", "91819424", "93441116"]),
+        (2000000000, ["69279037", "90698825", "38618901"]),
+        (20000000000, ["65353130", "77737706", "47863826"]),
+    ];
+    for (now, codes) in vectors {
+        for ((secret, algorithm), code) in [
+            (sha1, otp::Algorithm::Sha1),
+            (sha256, otp::Algorithm::Sha256),
+            (sha512, otp::Algorithm::Sha512),
+        ]
+        .into_iter()
+        .zip(codes)
+        {
+            let mut seed = OtpSeed {
+                kind: OtpKind::Totp { period: 30 },
+                secret: secret.to_vec(),
+                algorithm,
+                digits: 8,
+                issuer: String::new(),
+                account: String::new(),
+            };
+            assert_eq!(
+                seed.generate(now).unwrap(),
+                code,
+                "{:?} at {}",
+                algorithm,
+                now
+            );
+        }
+    }
+}
+
+#[test]
+fn hotp_seeds_advance_and_resync() {
+    let uri = format!(
+        "otpauth://hotp/alice?secret={}&counter=0",
+        "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"
+    );
+    let mut seed = otp::parse_uri(&uri).unwrap();
+    assert_eq!(seed.generate(0).unwrap(), "755224");
+    assert_eq!(seed.generate(0).unwrap(), "287082");
+
+    // codes 5 and 6 were used on the token only.
+    seed.resync(&["254676", "287922"], otp::RESYNC_WINDOW)
+        .unwrap();
+    assert_eq!(seed.kind, OtpKind::Hotp { counter: 7 });
+    assert!(invalid(seed.resync(&["000000"], 2)));
+}
+
+#[test]
+fn uris_are_parsed_and_written_back() {
+    let seed = otp::parse_uri(
+        "otpauth://totp/ACME%20Co:john.doe%40email.com?secret=HXDMVJECJJWSRB3HWIZR4IFUGFTMXBOZ\
+         &issuer=ACME%20Co&algorithm=SHA256&digits=8&period=60&image=https%3A%2F%2Fexample.com%2Fp.png",
+    )
+    .unwrap();
+    assert_eq!(seed.issuer, "ACME Co");
+    assert_eq!(seed.account, "john.doe@email.com");
+    assert_eq!(seed.algorithm, otp::Algorithm::Sha256);
+    assert_eq!(seed.digits, 8);
+    assert_eq!(seed.kind, OtpKind::Totp { period: 60 });
+    assert_eq!(otp::parse_uri(&seed.to_uri()).unwrap(), seed);
+
+    // the issuer parameter wins over the label's, defaults are SHA1, 6 digits and 30 seconds.
+    let seed = otp::parse_uri(&format!(
+        "otpauth://totp/Old:alice?secret={}&issuer=New",
+        SECRET
+    ))
+    .unwrap();
+    assert_eq!(
+        (seed.issuer.as_str(), seed.account.as_str()),
+        ("New", "alice")
+    );
+    assert_eq!((seed.algorithm, seed.digits), (otp::Algorithm::Sha1, 6));
+    assert_eq!(seed.kind, OtpKind::Totp { period: 30 });
+}
+
+#[test]
+fn bad_uris_are_rejected() {
+    for uri in [
+        "https://example.com/?secret=GEZDGNBV",
+        "otpauth://totp",
+        "otpauth://sotp/alice?secret=GEZDGNBV",
+        "otpauth://totp/alice",
+        "otpauth://totp/alice?secret=",
+        "otpauth://totp/alice?secret=GEZ1",
+        "otpauth://totp/alice?secret=GEZDGNBV&algorithm=MD5",
+        "otpauth://totp/alice?secret=GEZDGNBV&period=soon",
+        "otpauth://totp/al%2?secret=GEZDGNBV",
+        "otpauth://hotp/alice?secret=GEZDGNBV",
+    ] {
+        assert!(invalid(otp::parse_uri(uri)), "{}", uri);
+    }
+}
+
+/// a protobuf field with a length delimited value.
+fn bytes_field(field: u8, value: &[u8]) -> Vec<u8> {
+    assert!(value.len() < 128);
+    let mut out = vec![field << 3 | 2, value.len() as u8];
+    out.extend_from_slice(value);
+    out
+}
+
+fn varint_field(field: u8, value: u8) -> Vec<u8> {
+    assert!(value < 128);
+    vec![field << 3, value]
+}
+
+fn migration_uri(payload: &[u8]) -> String {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    format!(
+        "otpauth-migration://offline?data={}",
+        STANDARD.encode(payload)
+    )
+}
+
+#[test]
+fn migration_payloads_are_parsed() {
+    let totp = [
+        bytes_field(1, b"12345678901234567890"),
+        bytes_field(2, b"Example:alice@example.com"),
+        varint_field(4, 2),
+        varint_field(5, 2),
+        varint_field(6, 2),
+    ]
+    .concat();
+    let hotp = [
+        bytes_field(1, b"secret"),
+        bytes_field(2, b"bob"),
+        bytes_field(3, b"Issuer"),
+        varint_field(6, 1),
+        varint_field(7, 42),
+    ]
+    .concat();
+    // the version and batch fields after the entries are ignored.
+    let payload = [
+        bytes_field(1, &totp),
+        bytes_field(1, &hotp),
+        varint_field(2, 1),
+        varint_field(3, 1),
+    ]
+    .concat();
+
+    let seeds = otp::parse(&migration_uri(&payload)).unwrap();
+    assert_eq!(
+        seeds,
+        vec![
+            OtpSeed {
+                kind: OtpKind::Totp { period: 30 },
+                secret: b"12345678901234567890".to_vec(),
+                algorithm: otp::Algorithm::Sha256,
+                digits: 8,
+                issuer: "Example".to_owned(),
+                account: "alice@example.com".to_owned(),
+            },
+            OtpSeed {
+                kind: OtpKind::Hotp { counter: 42 },
+                secret: b"secret".to_vec(),
+                algorithm: otp::Algorithm::Sha1,
+                digits: 6,
+                issuer: "Issuer".to_owned(),
+                account: "bob".to_owned(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn truncated_and_invalid_migration_payloads_are_rejected() {
+    let entry = bytes_field(1, &bytes_field(1, b"secret"));
+    let mut too_long = entry.clone();
+    too_long[1] += 1;
+    let cases: Vec<Vec<u8>> = vec![
+        // cut inside the secret.
+        entry[..entry.len() - 1].to_vec(),
+        // a length running past the end.
+        too_long,
+        // a varint whose last byte still has the continuation bit.
+        vec![0x10, 0x80],
+        // a varint longer than 64 bits.
+        [vec![0x10], vec![0xff; 10]].concat(),
+        // wire type 3 (start group) isn't supported.
+        vec![0x0b],
+        // an entry without a secret.
+        bytes_field(1, &bytes_field(2, b"alice")),
+        // an unknown algorithm.
+        bytes_field(1, &[bytes_field(1, b"secret"), varint_field(4, 9)].concat()),
+    ];
+    for payload in cases {
+        assert!(
+            invalid(otp::parse(&migration_uri(&payload))),
+            "{:02x?}",
+            payload
+        );
+    }
+    for uri in [
+        "otpauth-migration://offline?data=not%20base64!",
+        "otpauth-migration://offline?version=1",
+        "otpauth-migration://online?data=",
+    ] {
+        assert!(invalid(otp::parse(uri)), "{}", uri);
+    }
+}
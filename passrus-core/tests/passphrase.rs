@@ -0,0 +1,48 @@
+use passrus_core::passphrase::{Wordlist, MIN_LIST_LEN};
+use passrus_core::PassrusError;
+
+fn words(count: usize) -> Vec<String> {
+    (0..count).map(|n| format!("word{}", n)).collect()
+}
+
+#[test]
+fn plain_and_diceware_lists_parse_alike() {
+    let plain = words(MIN_LIST_LEN).join("\n");
+    let diceware: String = words(MIN_LIST_LEN)
+        .iter()
+        .enumerate()
+        .map(|(n, word)| format!("{:05}\t{}\n", n, word))
+        .collect();
+    for text in [plain, format!("# a comment\n\n{}", diceware)] {
+        let list = Wordlist::parse(&text).unwrap();
+        assert_eq!(list.len(), MIN_LIST_LEN);
+        assert_eq!(list.bits_per_word(), 10.0);
+    }
+}
+
+#[test]
+fn generate_joins_words_from_the_list() {
+    let all = words(MIN_LIST_LEN);
+    let list = Wordlist::parse(&all.join("\n")).unwrap();
+    let phrase = list.generate(6, "-");
+    let chosen: Vec<&str> = phrase.split('-').collect();
+    assert_eq!(chosen.len(), 6);
+    assert!(chosen.iter().all(|word| all.iter().any(|w| w == word)));
+    assert_eq!(list.generate(0, "-"), "");
+}
+
+#[test]
+fn weak_lists_are_refused() {
+    let short = words(MIN_LIST_LEN - 1).join("\n");
+    let mut repeated = words(MIN_LIST_LEN);
+    repeated.push("WORD7".to_owned());
+    let mut phrases = words(MIN_LIST_LEN);
+    phrases.push("two words".to_owned());
+
+    for text in [short, repeated.join("\n"), phrases.join("\n")] {
+        assert!(matches!(
+            Wordlist::parse(&text),
+            Err(PassrusError::InvalidWordlist(_))
+        ));
+    }
+}
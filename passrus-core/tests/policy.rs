@@ -0,0 +1,257 @@
+use passrus_core::policy::{self, PasswordPolicy, Violation};
+use passrus_core::{Container, ContainerPath, Entry, PassrusError};
+use std::{env, fs, path::PathBuf};
+
+fn path(s: &str) -> ContainerPath {
+    ContainerPath::parse(s).unwrap()
+}
+
+fn login(key: &str) -> Entry {
+    Entry::new("alice", Vec::new(), "", key)
+}
+
+fn tree() -> Container {
+    let mut root = Container::new("root");
+    let mut work = Container::new("work");
+    work.add_child(Container::new("ops")).unwrap();
+    root.add_child(work).unwrap();
+    root
+}
+
+#[test]
+fn parse_sets_only_the_given_rules() {
+    let policy = PasswordPolicy::parse(&["min-length=12", "digit", "enforce"]).unwrap();
+    assert_eq!(
+        policy,
+        PasswordPolicy {
+            min_length: 12,
+            require_digit: true,
+            enforce: true,
+            ..PasswordPolicy::default()
+        }
+    );
+    for bad in [
+        "min-length=",
+        "min-length=-1",
+        "digits",
+        "not-in-breach=",
+        "",
+    ] {
+        assert!(
+            matches!(
+                PasswordPolicy::parse(&[bad]),
+                Err(PassrusError::InvalidEntry(_))
+            ),
+            "{:?}",
+            bad
+        );
+    }
+}
+
+#[test]
+fn check_lists_every_broken_rule() {
+    let policy = PasswordPolicy::parse(&[
+        "min-length=10",
+        "lowercase",
+        "uppercase",
+        "digit",
+        "symbol",
+        "no-username",
+    ])
+    .unwrap();
+    assert_eq!(
+        policy.check("ALICE", "alice"),
+        vec![
+            Violation::TooShort { min: 10, actual: 5 },
+            Violation::MissingLowercase,
+            Violation::MissingDigit,
+            Violation::MissingSymbol,
+            Violation::EqualsUsername,
+        ]
+    );
+    assert!(policy.check("Tr0ub4dor&3x", "alice").is_empty());
+}
+
+/// a breach list holding the range file for "password123" (SHA-1 CBFDAC6008F9CAB4083784CBD1874F76618D2A97),
+/// with a padding line for the hash of "Tr0ub4dor&3x".
+fn breach_list() -> PathBuf {
+    let dir = env::temp_dir().join(format!("passrus-breach-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("CBFDA.txt"),
+        "0018A45C4D1DEF81644B54AB7F969B88D65:3\r\nC6008F9CAB4083784CBD1874F76618D2A97:2254650\r\n",
+    )
+    .unwrap();
+    fs::write(dir.join("C6432"), "46DB75853796634F3ACB9C5218398F34D98:0\n").unwrap();
+    dir
+}
+
+#[test]
+fn not_in_breach_refuses_listed_passwords() {
+    let dir = breach_list();
+    let rule = format!("not-in-breach={}", dir.display());
+    let policy = PasswordPolicy::parse(&[rule.as_str()]).unwrap();
+    assert_eq!(policy.breach_list, Some(dir.display().to_string()));
+
+    assert_eq!(
+        policy.check("password123", "alice"),
+        vec![Violation::Breached(2254650)]
+    );
+    // a padding line is not a breach, and the suffix is matched whatever its case.
+    assert!(policy.check("Tr0ub4dor&3x", "alice").is_empty());
+    fs::write(
+        dir.join("CBFDA.txt"),
+        "c6008f9cab4083784cbd1874f76618d2a97:7\n",
+    )
+    .unwrap();
+    assert_eq!(policy::breach_count(&dir, "password123").unwrap(), 7);
+}
+
+#[test]
+fn an_unreadable_breach_list_is_a_violation() {
+    let rule = format!(
+        "not-in-breach={}",
+        env::temp_dir()
+            .join("passrus-no-such-breach-list")
+            .display()
+    );
+    let policy = PasswordPolicy::parse(&[rule.as_str()]).unwrap();
+    assert!(matches!(
+        policy.check("password123", "alice")[..],
+        [Violation::BreachListUnreadable(_)]
+    ));
+}
+
+#[test]
+fn guessable_flags_passwords_read_off_the_entry() {
+    let entry = Entry::new(
+        "alice",
+        Vec::new(),
+        "bob@example.com",
+        "https://www.github.com/login",
+    );
+    assert_eq!(
+        policy::guessable(&entry, "Alice"),
+        vec![Violation::EqualsUsername]
+    );
+    assert_eq!(
+        policy::guessable(&entry, "bob"),
+        vec![Violation::EqualsEmailLocalPart]
+    );
+    for host in ["www.github.com", "github.com", "GitHub"] {
+        assert_eq!(
+            policy::guessable(&entry, host),
+            vec![Violation::EqualsUrlHost]
+        );
+    }
+    assert!(policy::guessable(&entry, "unrelated").is_empty());
+}
+
+#[test]
+fn the_deepest_policy_applies() {
+    let mut root = tree();
+    let strict = PasswordPolicy::parse(&["min-length=20", "enforce"]).unwrap();
+    let lax = PasswordPolicy::parse(&["min-length=4"]).unwrap();
+    root.set_policy(&path("work"), Some(strict.clone()))
+        .unwrap();
+    assert_eq!(root.policy_for(&path("work/ops")), Some(&strict));
+    assert_eq!(root.policy_for(&path("")), None);
+
+    root.set_policy(&path("work/ops"), Some(lax.clone()))
+        .unwrap();
+    assert_eq!(root.policy_for(&path("work/ops")), Some(&lax));
+    root.set_policy(&path("work/ops"), None).unwrap();
+    assert_eq!(root.policy_for(&path("work/ops")), Some(&strict));
+
+    assert!(matches!(
+        root.set_policy(&path("nowhere"), None),
+        Err(PassrusError::ContainerNotFound(_))
+    ));
+}
+
+#[test]
+fn an_enforced_policy_rejects_unless_overridden() {
+    let mut root = tree();
+    root.set_policy(
+        &path("work"),
+        Some(PasswordPolicy::parse(&["min-length=20", "enforce"]).unwrap()),
+    )
+    .unwrap();
+
+    let err = root
+        .add_entry_checked(&path("work/ops"), login("short"), "too-short", false)
+        .unwrap_err();
+    assert!(
+        matches!(err, PassrusError::PolicyViolation(v) if v == vec![Violation::TooShort { min: 20, actual: 9 }])
+    );
+    assert!(root
+        .get_container(&path("work/ops"))
+        .unwrap()
+        .entries
+        .is_empty());
+
+    let warnings = root
+        .add_entry_checked(&path("work/ops"), login("short"), "too-short", true)
+        .unwrap();
+    assert_eq!(warnings, vec![Violation::TooShort { min: 20, actual: 9 }]);
+    assert!(root
+        .get_container(&path("work/ops"))
+        .unwrap()
+        .entries
+        .contains_key("short"));
+}
+
+#[test]
+fn a_policy_that_is_not_enforced_only_warns() {
+    let mut root = tree();
+    root.set_policy(
+        &path("work"),
+        Some(PasswordPolicy::parse(&["digit"]).unwrap()),
+    )
+    .unwrap();
+    let warnings = root
+        .add_entry_checked(&path("work"), login("site"), "alice", false)
+        .unwrap();
+    assert_eq!(
+        warnings,
+        vec![Violation::MissingDigit, Violation::EqualsUsername]
+    );
+    assert!(root
+        .get_container(&path("work"))
+        .unwrap()
+        .entries
+        .contains_key("site"));
+}
+
+#[test]
+fn estimate_bits_ignores_runs_but_not_sentences() {
+    assert_eq!(policy::estimate_bits(""), 0.0);
+    assert!(policy::estimate_bits("aaaaaaaa") < policy::estimate_bits("abqzmxke"));
+    assert!(policy::estimate_bits("abcdefgh") < 20.0);
+    // only single common passwords are known: four common words look as strong as random letters, which is why it is
+    // only an upper bound.
+    assert!(
+        policy::estimate_bits("correcthorsebatterystaple") > f64::from(policy::GOOD_MASTER_BITS)
+    );
+}
+
+#[test]
+fn estimate_bits_sees_through_common_password_variations() {
+    let min = f64::from(policy::MIN_MASTER_BITS);
+    for weak in [
+        "password123",
+        "Password123!",
+        "P@ssw0rd2024",
+        "!!Dragon1987",
+        "qwertyuiop",
+        "iloveyou2",
+    ] {
+        let bits = policy::estimate_bits(weak);
+        assert!(bits < min, "{} scored {}", weak, bits);
+    }
+    // each variation costs a guesser something, random capitals more than one.
+    assert!(policy::estimate_bits("password") < policy::estimate_bits("Password"));
+    assert!(policy::estimate_bits("Password") < policy::estimate_bits("pAsSwoRd"));
+    // a listed word inside other letters isn't the word.
+    assert!(policy::estimate_bits("xpasswordqz") > min);
+}
@@ -0,0 +1,69 @@
+use passrus_core::{cryptman, Container, Entry, EntryRef, PassrusError};
+
+const FROM: &str = "old password";
+const TO: &str = "new password";
+
+fn password(root: &Container, entry: &str, pass: &str) -> Vec<u8> {
+    let mut entry = root
+        .resolve(&EntryRef::parse(entry).unwrap())
+        .unwrap()
+        .clone();
+    entry.decrypt_password(pass).unwrap();
+    entry.pass_vec
+}
+
+/// a tree with an entry under its own key, one under the vault key as written before entry keys, and one without a
+/// password, spread over a child container.
+fn mixed_tree() -> Container {
+    let mut root = Container::new("root");
+    let mut own_key = Entry::new("alice", Vec::new(), "", "github.com");
+    own_key.set_password(b"s3cret", FROM, 0).unwrap();
+    root.add_entry(own_key);
+
+    let mut work = Container::new("work");
+    let (key, salt) = cryptman::pass_2_key(FROM, [7u8; 32]).unwrap();
+    let legacy =
+        cryptman::encrypt_file_mem_with_salt(b"legacy".to_vec(), "", &key, &[9u8; 24], &salt)
+            .unwrap();
+    work.add_entry(Entry::new("bob", legacy, "", "intranet"));
+    work.add_entry(Entry::new("carol", Vec::new(), "", "empty"));
+    root.add_child(work).unwrap();
+    root
+}
+
+#[test]
+fn reencrypt_moves_a_mixed_tree_to_the_new_password() {
+    let mut root = mixed_tree();
+    root.reencrypt_passwords(FROM, TO).unwrap();
+
+    assert_eq!(password(&root, "github.com", TO), b"s3cret");
+    assert_eq!(password(&root, "work/intranet", TO), b"legacy");
+    let empty = root
+        .resolve(&EntryRef::parse("work/empty").unwrap())
+        .unwrap();
+    assert!(empty.pass_vec.is_empty());
+    for (_, entry) in root.iter_entries().filter(|(_, e)| !e.pass_vec.is_empty()) {
+        assert!(entry.entry_key);
+        assert!(entry.clone().decrypt_password(FROM).is_err());
+    }
+}
+
+#[test]
+fn reencrypt_with_the_wrong_password_leaves_the_tree_alone() {
+    let mut root = mixed_tree();
+    let before: Vec<Vec<u8>> = root
+        .iter_entries()
+        .map(|(_, e)| e.pass_vec.clone())
+        .collect();
+
+    assert!(matches!(
+        root.reencrypt_passwords("not the password", TO),
+        Err(PassrusError::WrongPassword)
+    ));
+    let after: Vec<Vec<u8>> = root
+        .iter_entries()
+        .map(|(_, e)| e.pass_vec.clone())
+        .collect();
+    assert_eq!(before, after);
+    assert_eq!(password(&root, "work/intranet", FROM), b"legacy");
+}
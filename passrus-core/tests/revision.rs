@@ -0,0 +1,85 @@
+use passrus_core::{revision, Container, ContainerPath, Entry, EntryRef, PassrusError};
+
+fn tree() -> Container {
+    let mut root = Container::new("root");
+    let mut work = Container::new("work");
+    let mut home = Container::new("home");
+    work.add_entry(Entry::new("alice", Vec::new(), "", "github.com"));
+    work.add_entry(Entry::new("alice", Vec::new(), "", "gitlab.com"));
+    home.add_entry(Entry::new("alice", Vec::new(), "", "mail.com"));
+    root.add_child(work).unwrap();
+    root.add_child(home).unwrap();
+    root
+}
+
+fn entry<'a>(root: &'a Container, path: &str) -> &'a Entry {
+    root.resolve(&EntryRef::parse(path).unwrap()).unwrap()
+}
+
+fn container<'a>(root: &'a Container, path: &str) -> &'a Container {
+    root.get_container(&ContainerPath::parse(path).unwrap())
+        .unwrap()
+}
+
+#[test]
+fn an_unchanged_tree_keeps_its_revisions() {
+    let opened = tree();
+    let mut current = opened.clone();
+    revision::bump(&mut current, &opened);
+    assert_eq!(current.revision, 0);
+    assert_eq!(container(&current, "work").revision, 0);
+    assert_eq!(entry(&current, "work/github.com").revision, 0);
+}
+
+#[test]
+fn a_change_bumps_the_entry_and_its_containers() {
+    let opened = tree();
+    let mut current = opened.clone();
+    current
+        .resolve_mut(&EntryRef::parse("work/github.com").unwrap())
+        .unwrap()
+        .username = "bob".to_owned();
+    revision::bump(&mut current, &opened);
+
+    assert_eq!(entry(&current, "work/github.com").revision, 1);
+    assert_eq!(entry(&current, "work/gitlab.com").revision, 0);
+    assert_eq!(container(&current, "work").revision, 1);
+    assert_eq!(container(&current, "home").revision, 0);
+    assert_eq!(current.revision, 1);
+}
+
+#[test]
+fn a_moved_entry_counts_as_changed() {
+    let opened = tree();
+    let mut current = opened.clone();
+    let moved = current
+        .get_container_mut(&ContainerPath::parse("work").unwrap())
+        .unwrap()
+        .entries
+        .remove("gitlab.com")
+        .unwrap();
+    current
+        .get_container_mut(&ContainerPath::parse("home").unwrap())
+        .unwrap()
+        .add_entry(moved);
+    revision::bump(&mut current, &opened);
+
+    assert_eq!(entry(&current, "home/gitlab.com").revision, 1);
+    assert_eq!(container(&current, "work").revision, 1);
+    assert_eq!(container(&current, "home").revision, 1);
+}
+
+#[test]
+fn check_reports_a_conflict() {
+    let what = EntryRef::parse("work/github.com").unwrap();
+    assert!(revision::check(&what, None, 3).is_ok());
+    assert!(revision::check(&what, Some(3), 3).is_ok());
+    assert!(matches!(
+        revision::check(&what, Some(2), 3),
+        Err(PassrusError::Conflict {
+            expected: 2,
+            actual: 3,
+            ..
+        })
+    ));
+}
@@ -0,0 +1,102 @@
+use passrus_core::rotation::{self, Period, Rotation};
+use passrus_core::{Container, Entry, PassrusError};
+
+const DAY: u64 = 24 * 60 * 60;
+/// 2024-02-15 00:00 UTC, in a leap year.
+const FEB_15_2024: u64 = 1_707_955_200;
+
+#[test]
+fn parse_reads_a_number_of_days() {
+    let days = Rotation::parse("90", FEB_15_2024).unwrap();
+    assert_eq!(days, Rotation::new(90, FEB_15_2024));
+    assert_eq!(days.to_string(), "every 90 days");
+
+    for schedule in ["0", "-1", "weekly", ""] {
+        assert!(matches!(
+            Rotation::parse(schedule, FEB_15_2024),
+            Err(PassrusError::InvalidEntry(_))
+        ));
+    }
+}
+
+#[test]
+fn due_at_counts_days_from_the_last_change() {
+    assert_eq!(
+        Rotation::new(30, FEB_15_2024).due_at(),
+        FEB_15_2024 + 30 * DAY
+    );
+}
+
+#[test]
+fn overdue_days_are_whole_days_past_due() {
+    let rotation = Rotation::new(1, FEB_15_2024);
+    assert_eq!(rotation.overdue_days(FEB_15_2024), None);
+    assert_eq!(rotation.overdue_days(FEB_15_2024 + DAY), Some(0));
+    assert_eq!(rotation.overdue_days(FEB_15_2024 + 3 * DAY + 1), Some(2));
+}
+
+#[test]
+fn due_lists_most_overdue_first_and_set_password_starts_over() {
+    let mut root = Container::new("root");
+    let mut work = Container::new("work");
+    for (url, days) in [("a.example.com", 10), ("b.example.com", 1)] {
+        let mut entry = Entry::new("alice", Vec::new(), "", url);
+        entry.rotation = Some(Rotation::new(days, FEB_15_2024));
+        work.add_entry(entry);
+    }
+    work.add_entry(Entry::new("alice", Vec::new(), "", "never.example.com"));
+    root.add_child(work).unwrap();
+
+    let now = FEB_15_2024 + 20 * DAY;
+    let due: Vec<(String, u64)> = rotation::due(&root, now)
+        .unwrap()
+        .into_iter()
+        .map(|(entry_ref, days)| (entry_ref.to_string(), days))
+        .collect();
+    assert_eq!(
+        due,
+        [
+            ("work/b.example.com".to_owned(), 19),
+            ("work/a.example.com".to_owned(), 10),
+        ]
+    );
+
+    let work = root.children.get_mut("work").unwrap();
+    let entry = work.entries.get_mut("b.example.com").unwrap();
+    entry.set_password(b"n3w", "vault password", now).unwrap();
+    assert_eq!(rotation::due(&root, now).unwrap().len(), 1);
+}
+
+#[test]
+fn parse_reads_periods() {
+    for (schedule, period) in [
+        ("month", Period::Month),
+        ("quarter", Period::Quarter),
+        ("year", Period::Year),
+    ] {
+        let rotation = Rotation::parse(schedule, FEB_15_2024).unwrap();
+        assert_eq!(rotation.first_of, Some(period));
+        assert_eq!(rotation.every_days, 0);
+        assert_eq!(
+            rotation.to_string(),
+            format!("on the first of every {}", schedule)
+        );
+    }
+}
+
+#[test]
+fn due_at_is_the_first_day_of_the_next_period() {
+    let due =
+        |schedule: &str, changed_at: u64| Rotation::parse(schedule, changed_at).unwrap().due_at();
+    // 2024-03-01, 2024-04-01 and 2025-01-01.
+    assert_eq!(due("month", FEB_15_2024), 1_709_251_200);
+    assert_eq!(due("quarter", FEB_15_2024), 1_711_929_600);
+    assert_eq!(due("year", FEB_15_2024), 1_735_689_600);
+    // from 2023-12-10, and for the quarter from 2023-11-01, the next period starts in the new year: 2024-01-01.
+    assert_eq!(due("month", 1_702_166_400), 1_704_067_200);
+    for changed_at in [1_702_166_400, 1_698_796_800] {
+        assert_eq!(due("quarter", changed_at), 1_704_067_200);
+    }
+    // the first of a period is in it, the change is due at the next one.
+    assert_eq!(due("month", 1_709_251_200), 1_711_929_600);
+}
@@ -0,0 +1,91 @@
+use passrus_core::{schema, Container, Entry, PassrusError};
+use serde_json::{json, Value};
+
+/// a root with one entry and a child holding another, serialised the way schema version 1 wrote it: no entry ids.
+fn v1_document() -> Value {
+    let mut root = Container::new("root");
+    root.add_entry(Entry::new("alice", Vec::new(), "", "github.com"));
+    let mut work = Container::new("work");
+    work.add_entry(Entry::new("bob", Vec::new(), "", "gitlab.com"));
+    root.add_child(work).unwrap();
+
+    let mut value = serde_json::to_value(&root).unwrap();
+    to_v1(&mut value["children"]["work"]);
+    to_v1(&mut value);
+    value
+}
+
+fn to_v1(container: &mut Value) {
+    container["schema_version"] = json!(1);
+    for entry in container["entries"].as_object_mut().unwrap().values_mut() {
+        entry.as_object_mut().unwrap().remove("id");
+    }
+}
+
+fn load(value: &Value) -> Result<Container, PassrusError> {
+    let mut root = Container::new("");
+    root.from_json_string(&value.to_string())?;
+    Ok(root)
+}
+
+#[test]
+fn v1_entries_get_distinct_ids_at_every_level() {
+    let root = load(&v1_document()).unwrap();
+    let work = &root.children["work"];
+    assert_eq!(root.schema_version, schema::CURRENT_SCHEMA_VERSION);
+    assert_eq!(work.schema_version, schema::CURRENT_SCHEMA_VERSION);
+
+    let github = &root.get_entry("github.com").unwrap().id;
+    let gitlab = &work.get_entry("gitlab.com").unwrap().id;
+    assert!(!github.is_empty() && !gitlab.is_empty());
+    assert_ne!(github, gitlab);
+}
+
+#[test]
+fn v1_ids_already_set_are_kept() {
+    let mut value = v1_document();
+    value["entries"]["github.com"]["id"] = json!("kept-id");
+    value["children"]["work"]["entries"]["gitlab.com"]["id"] = json!("");
+
+    let root = load(&value).unwrap();
+    assert_eq!(root.get_entry("github.com").unwrap().id, "kept-id");
+    // an empty id is as good as none.
+    assert!(!root.children["work"]
+        .get_entry("gitlab.com")
+        .unwrap()
+        .id
+        .is_empty());
+}
+
+#[test]
+fn unversioned_documents_migrate_all_the_way() {
+    let mut value = v1_document();
+    value.as_object_mut().unwrap().remove("schema_version");
+    assert_eq!(schema::version_of(&value), 0);
+
+    schema::migrate(&mut value).unwrap();
+    assert_eq!(schema::version_of(&value), schema::CURRENT_SCHEMA_VERSION);
+    assert!(value["entries"]["github.com"]["id"].is_string());
+}
+
+#[test]
+fn migrating_the_current_version_changes_nothing() {
+    let mut root = Container::new("root");
+    root.add_entry(Entry::new("alice", Vec::new(), "", "github.com"));
+    let value = serde_json::to_value(&root).unwrap();
+
+    let mut migrated = value.clone();
+    schema::migrate(&mut migrated).unwrap();
+    assert_eq!(migrated, value);
+}
+
+#[test]
+fn newer_versions_are_refused() {
+    let mut value = v1_document();
+    value["schema_version"] = json!(schema::CURRENT_SCHEMA_VERSION + 1);
+    assert!(matches!(
+        schema::migrate(&mut value),
+        Err(PassrusError::CorruptFile { .. })
+    ));
+    assert!(load(&value).is_err());
+}
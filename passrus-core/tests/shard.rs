@@ -0,0 +1,164 @@
+use passrus_core::shard::{self, Manifest, MANIFEST};
+use passrus_core::{Container, Entry, PassrusError};
+use std::{
+    env, fs,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+const PASS: &str = "vault password";
+
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// a fresh directory for one test, removed on drop. the vault directory is a subdirectory, save creates it.
+struct Dir(PathBuf);
+
+impl Dir {
+    fn new() -> Self {
+        let dir = env::temp_dir().join(format!(
+            "passrus-shard-test-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Dir(dir)
+    }
+
+    fn vault(&self) -> String {
+        self.0.join("vault.d").to_string_lossy().into_owned()
+    }
+
+    /// the shard files in the vault directory, sorted.
+    fn shards(&self) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(self.0.join("vault.d"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".shard"))
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn shard_path(&self, name: &str) -> PathBuf {
+        self.0.join("vault.d").join(name)
+    }
+}
+
+impl Drop for Dir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn tree() -> Container {
+    let mut root = Container::new("root");
+    root.add_entry(Entry::new("alice", Vec::new(), "", "top.example.com"));
+    for name in ["personal", "work"] {
+        let mut child = Container::new(name);
+        child.add_entry(Entry::new(
+            "alice",
+            Vec::new(),
+            "",
+            &format!("{}.example.com", name),
+        ));
+        root.add_child(child).unwrap();
+    }
+    root
+}
+
+fn json(root: &Container) -> serde_json::Value {
+    serde_json::to_value(root).unwrap()
+}
+
+#[test]
+fn save_and_open_round_trip_one_shard_per_child() {
+    let dir = Dir::new();
+    let mut root = tree();
+    shard::save(&dir.vault(), &mut root, &Manifest::default(), PASS).unwrap();
+    assert!(dir.shard_path(MANIFEST).exists());
+    assert_eq!(dir.shards().len(), 2);
+
+    let (opened, _) = shard::open(&dir.vault(), PASS).unwrap();
+    assert_eq!(json(&opened), json(&root));
+    assert!(matches!(
+        shard::open(&dir.vault(), "not the password"),
+        Err(PassrusError::WrongPassword)
+    ));
+}
+
+#[test]
+fn save_rewrites_only_changed_shards() {
+    let dir = Dir::new();
+    let mut root = tree();
+    let manifest = shard::save(&dir.vault(), &mut root, &Manifest::default(), PASS).unwrap();
+    let before = dir.shards();
+
+    // nothing changed, every shard stays.
+    let manifest = shard::save(&dir.vault(), &mut root, &manifest, PASS).unwrap();
+    assert_eq!(dir.shards(), before);
+
+    root.children.get_mut("work").unwrap().add_entry(Entry::new(
+        "alice",
+        Vec::new(),
+        "",
+        "new.example.com",
+    ));
+    shard::save(&dir.vault(), &mut root, &manifest, PASS).unwrap();
+    let after = dir.shards();
+    assert_eq!(after.len(), 2);
+    // one file kept, the replaced one removed.
+    assert_eq!(after.iter().filter(|name| before.contains(name)).count(), 1);
+
+    let (opened, _) = shard::open(&dir.vault(), PASS).unwrap();
+    assert_eq!(json(&opened), json(&root));
+}
+
+#[test]
+fn save_removes_leftover_shards() {
+    let dir = Dir::new();
+    let mut root = tree();
+    let manifest = shard::save(&dir.vault(), &mut root, &Manifest::default(), PASS).unwrap();
+    let listed = dir.shards();
+    fs::write(dir.shard_path("interrupted.shard"), "half written").unwrap();
+
+    root.children.remove("personal");
+    shard::save(&dir.vault(), &mut root, &manifest, PASS).unwrap();
+    let left = dir.shards();
+    assert_eq!(left.len(), 1);
+    assert!(listed.contains(&left[0]));
+}
+
+#[test]
+fn an_older_copy_of_a_shard_fails_to_open() {
+    let dir = Dir::new();
+    let mut root = tree();
+    let manifest = shard::save(&dir.vault(), &mut root, &Manifest::default(), PASS).unwrap();
+    let old: Vec<Vec<u8>> = dir
+        .shards()
+        .iter()
+        .map(|name| fs::read(dir.shard_path(name)).unwrap())
+        .collect();
+
+    root.children.get_mut("work").unwrap().add_entry(Entry::new(
+        "alice",
+        Vec::new(),
+        "",
+        "new.example.com",
+    ));
+    shard::save(&dir.vault(), &mut root, &manifest, PASS).unwrap();
+    let new = dir
+        .shards()
+        .into_iter()
+        .find(|name| {
+            let data = fs::read(dir.shard_path(name)).unwrap();
+            !old.contains(&data)
+        })
+        .unwrap();
+
+    // roll the rewritten shard back to each of the old ones, under the new file name.
+    for data in &old {
+        fs::write(dir.shard_path(&new), data).unwrap();
+        assert!(shard::open(&dir.vault(), PASS).is_err());
+    }
+}
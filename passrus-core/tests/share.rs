@@ -0,0 +1,65 @@
+use passrus_core::share::{self, BLOB_PREFIX};
+use passrus_core::{Entry, PassrusError};
+
+fn github() -> Entry {
+    let mut entry = Entry::new(
+        "alice",
+        b"s3cret".to_vec(),
+        "alice@example.com",
+        "github.com",
+    );
+    entry.parent = "work".to_owned();
+    entry
+}
+
+#[test]
+fn shared_entry_opens_with_its_passphrase() {
+    let github = github();
+    let shared = share::share_entry(&github).unwrap();
+    assert!(shared.blob.starts_with(BLOB_PREFIX));
+
+    // blob and passphrase survive being pasted with surrounding whitespace.
+    let blob = format!("  {}\n", shared.blob);
+    let passphrase = format!("{}\n", shared.passphrase);
+    let entry = share::import_shared(&blob, &passphrase).unwrap();
+    let mut expected = github;
+    expected.parent = String::new();
+    assert_eq!(
+        serde_json::to_value(&entry).unwrap(),
+        serde_json::to_value(&expected).unwrap()
+    );
+}
+
+#[test]
+fn passphrases_are_fresh_and_readable() {
+    let first = share::share_entry(&github()).unwrap().passphrase;
+    let second = share::share_entry(&github()).unwrap().passphrase;
+    assert_ne!(first, second);
+
+    let groups: Vec<&str> = first.split('-').collect();
+    assert_eq!(groups.len(), 6);
+    for group in groups {
+        assert_eq!(group.len(), 4);
+        assert!(group
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() && !"01lIO".contains(c)));
+    }
+}
+
+#[test]
+fn wrong_passphrase_or_mangled_blob_is_refused() {
+    let shared = share::share_entry(&github()).unwrap();
+    assert!(matches!(
+        share::import_shared(&shared.blob, "aaaa-bbbb-cccc-dddd-eeee-ffff"),
+        Err(PassrusError::WrongPassword)
+    ));
+
+    let unprefixed = shared.blob.trim_start_matches(BLOB_PREFIX);
+    let not_base64 = format!("{}!!", BLOB_PREFIX);
+    for blob in [unprefixed, not_base64.as_str()] {
+        assert!(matches!(
+            share::import_shared(blob, &shared.passphrase),
+            Err(PassrusError::CorruptFile { .. })
+        ));
+    }
+}
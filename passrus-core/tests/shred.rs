@@ -0,0 +1,108 @@
+use passrus_core::shred;
+use std::{
+    env, fs,
+    io::Read,
+    os::unix::fs::symlink,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// a fresh directory for one test, removed on drop.
+struct Dir(PathBuf);
+
+impl Dir {
+    fn new() -> Self {
+        let dir = env::temp_dir().join(format!(
+            "passrus-shred-test-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Dir(dir)
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for Dir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn read_all(mut file: &fs::File) -> Vec<u8> {
+    let mut data = Vec::new();
+    file.read_to_end(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn remove_file_overwrites_before_unlinking() {
+    let dir = Dir::new();
+    let path = dir.path("secret");
+    fs::write(&path, "hunter2").unwrap();
+    let reader = fs::File::open(&path).unwrap();
+
+    shred::remove_file(&path).unwrap();
+    assert!(fs::metadata(&path).is_err());
+    assert_eq!(read_all(&reader), [0; 7]);
+    // already gone is fine.
+    shred::remove_file(&path).unwrap();
+}
+
+#[test]
+fn remove_file_leaves_other_names_alone() {
+    let dir = Dir::new();
+    let target = dir.path("target");
+    fs::write(&target, "keep me").unwrap();
+    let link = dir.path("link");
+    symlink(&target, &link).unwrap();
+    let hard = dir.path("hard");
+    fs::hard_link(&target, &hard).unwrap();
+
+    shred::remove_file(&link).unwrap();
+    shred::remove_file(&hard).unwrap();
+    assert!(fs::symlink_metadata(&link).is_err() && fs::metadata(&hard).is_err());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "keep me");
+}
+
+#[test]
+fn replace_overwrites_the_replaced_file() {
+    let dir = Dir::new();
+    let path = dir.path("vault.bin");
+    fs::write(&path, "old").unwrap();
+    let reader = fs::File::open(&path).unwrap();
+    let new = dir.path("vault.bin.tmp");
+    fs::write(&new, "new").unwrap();
+
+    shred::replace(&new, &path).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+    assert!(fs::metadata(&new).is_err());
+    assert_eq!(read_all(&reader), [0; 3]);
+
+    // nothing to overwrite the first time.
+    let fresh = dir.path("fresh");
+    fs::write(&new, "new").unwrap();
+    shred::replace(&new, &fresh).unwrap();
+    assert_eq!(fs::read_to_string(&fresh).unwrap(), "new");
+}
+
+#[test]
+fn replace_leaves_a_symlink_target_alone() {
+    let dir = Dir::new();
+    let target = dir.path("target");
+    fs::write(&target, "keep me").unwrap();
+    let link = dir.path("link");
+    symlink(&target, &link).unwrap();
+    let new = dir.path("new");
+    fs::write(&new, "new").unwrap();
+
+    shred::replace(&new, &link).unwrap();
+    assert_eq!(fs::read_to_string(&link).unwrap(), "new");
+    assert_eq!(fs::read_to_string(&target).unwrap(), "keep me");
+}
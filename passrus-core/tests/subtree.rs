@@ -0,0 +1,76 @@
+use passrus_core::kind::{Alias, EntryKind};
+use passrus_core::{subtree, Container, ContainerPath, Entry, EntryRef, PassrusError};
+
+const PASS: &str = "vault password";
+
+fn tree() -> Container {
+    let mut root = Container::new("root");
+    let mut work = Container::new("work");
+    let mut github = Entry::new("alice", Vec::new(), "", "github.com");
+    github.set_password(b"s3cret", PASS, 0).unwrap();
+    let mut alias = Entry::new("", Vec::new(), "", "gh");
+    alias.kind = EntryKind::Alias(Alias {
+        target: github.id.clone(),
+    });
+    work.add_entry(github);
+    work.add_entry(alias);
+    root.add_child(work).unwrap();
+    root
+}
+
+fn entry<'a>(root: &'a Container, path: &str) -> &'a Entry {
+    root.resolve(&EntryRef::parse(path).unwrap()).unwrap()
+}
+
+#[test]
+fn renew_ids_keeps_passwords_and_aliases() {
+    let mut root = tree();
+    let old_id = entry(&root, "work/github.com").id.clone();
+    subtree::renew_ids(&mut root, PASS).unwrap();
+
+    let github = entry(&root, "work/github.com").clone();
+    assert_ne!(github.id, old_id);
+    // the alias follows the new id.
+    assert_eq!(entry(&root, "work/gh").id, github.id);
+    let mut github = github;
+    github.decrypt_password(PASS).unwrap();
+    assert_eq!(github.pass_vec, b"s3cret");
+}
+
+#[test]
+fn renew_ids_with_the_wrong_password_changes_nothing() {
+    let mut root = tree();
+    let before = entry(&root, "work/github.com").clone();
+    assert!(matches!(
+        subtree::renew_ids(&mut root, "not the password"),
+        Err(PassrusError::WrongPassword)
+    ));
+    let after = entry(&root, "work/github.com");
+    assert_eq!(after.id, before.id);
+    assert_eq!(after.pass_vec, before.pass_vec);
+}
+
+#[test]
+fn export_and_import_move_passwords_between_vault_passwords() {
+    let root = tree();
+    let path = ContainerPath::parse("work").unwrap();
+    let data = subtree::export(&root, &path, PASS, "export password").unwrap();
+    assert!(matches!(
+        subtree::import(data.clone(), "wrong", "target password"),
+        Err(PassrusError::WrongPassword)
+    ));
+
+    let imported = subtree::import(data, "export password", "target password").unwrap();
+    assert_eq!(imported.name, "work");
+    let mut github = imported.get_entry("github.com").unwrap().clone();
+    assert!(github.clone().decrypt_password(PASS).is_err());
+    github.decrypt_password("target password").unwrap();
+    assert_eq!(github.pass_vec, b"s3cret");
+}
+
+#[test]
+fn export_of_a_missing_container_fails() {
+    let root = tree();
+    let path = ContainerPath::parse("personal").unwrap();
+    assert!(subtree::export(&root, &path, PASS, "export password").is_err());
+}
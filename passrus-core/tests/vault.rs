@@ -0,0 +1,158 @@
+use passrus_core::{Container, Entry, PassrusError, Vault};
+use std::{
+    env, fs,
+    os::unix::fs::PermissionsExt,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+const PASS: &str = "vault password";
+
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// a fresh directory for one test, removed on drop.
+struct Dir(PathBuf);
+
+impl Dir {
+    fn new() -> Self {
+        let dir = env::temp_dir().join(format!(
+            "passrus-vault-test-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Dir(dir)
+    }
+
+    fn path(&self, name: &str) -> String {
+        self.0.join(name).to_string_lossy().into_owned()
+    }
+
+    /// the names of the files in the directory, sorted.
+    fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(&self.0)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+impl Drop for Dir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn add_login(root: &mut Container, name: &str) -> Result<(), PassrusError> {
+    let mut entry = Entry::new("alice", Vec::new(), "", name);
+    entry.set_password(b"s3cret", PASS, 0)?;
+    root.add_entry(entry);
+    Ok(())
+}
+
+fn json(root: &Container) -> serde_json::Value {
+    serde_json::to_value(root).unwrap()
+}
+
+fn mode(path: &str) -> u32 {
+    fs::metadata(path).unwrap().permissions().mode() & 0o777
+}
+
+#[test]
+fn save_leaves_only_the_vault_file() {
+    let dir = Dir::new();
+    let path = dir.path("vault.bin");
+    let mut vault = Vault::new(&path, "");
+    add_login(&mut vault.root, "github.com").unwrap();
+    vault.save(PASS).unwrap();
+    vault.save(PASS).unwrap();
+
+    assert_eq!(dir.names(), ["vault.bin"]);
+    assert_eq!(mode(&path), 0o600);
+    let reopened = Vault::open(&path, PASS).unwrap();
+    assert_eq!(json(&reopened.root), json(&vault.root));
+}
+
+#[test]
+fn save_keeps_the_permissions_of_an_existing_file() {
+    let dir = Dir::new();
+    let path = dir.path("vault.bin");
+    let mut vault = Vault::new(&path, "");
+    vault.save(PASS).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+    add_login(&mut vault.root, "github.com").unwrap();
+    vault.save(PASS).unwrap();
+    assert_eq!(mode(&path), 0o640);
+}
+
+#[test]
+fn failed_save_removes_its_temporary_file() {
+    let dir = Dir::new();
+    // a non-empty directory in the way makes the final rename fail, after the data has been written.
+    let path = dir.path("vault.bin");
+    fs::create_dir(&path).unwrap();
+    fs::write(dir.path("vault.bin/keep"), "keep me").unwrap();
+
+    let mut vault = Vault::new(&path, "");
+    add_login(&mut vault.root, "github.com").unwrap();
+    assert!(vault.save(PASS).is_err());
+    assert_eq!(dir.names(), ["vault.bin"]);
+    assert_eq!(
+        fs::read_to_string(dir.path("vault.bin/keep")).unwrap(),
+        "keep me"
+    );
+}
+
+#[test]
+fn transaction_saves_on_success() {
+    let dir = Dir::new();
+    let path = dir.path("vault.bin");
+    let mut vault = Vault::new(&path, "");
+    vault.save(PASS).unwrap();
+
+    vault
+        .transaction(PASS, |root| add_login(root, "github.com"))
+        .unwrap();
+    let reopened = Vault::open(&path, PASS).unwrap();
+    assert_eq!(reopened.root.entries.len(), 1);
+    assert_eq!(json(&reopened.root), json(&vault.root));
+}
+
+#[test]
+fn transaction_rolls_back_when_the_change_fails() {
+    let dir = Dir::new();
+    let path = dir.path("vault.bin");
+    let mut vault = Vault::new(&path, "");
+    add_login(&mut vault.root, "github.com").unwrap();
+    vault.save(PASS).unwrap();
+    let before = json(&vault.root);
+    let bytes = fs::read(&path).unwrap();
+
+    let result: Result<(), _> = vault.transaction(PASS, |root| {
+        add_login(root, "gitlab.com")?;
+        Err(PassrusError::InvalidEntry("halfway".to_owned()))
+    });
+    assert!(matches!(result, Err(PassrusError::InvalidEntry(_))));
+    assert_eq!(json(&vault.root), before);
+    assert_eq!(fs::read(&path).unwrap(), bytes);
+}
+
+#[test]
+fn transaction_rolls_back_when_the_save_fails() {
+    let dir = Dir::new();
+    let path = dir.path("vault.bin");
+    fs::create_dir(&path).unwrap();
+    fs::write(dir.path("vault.bin/keep"), "keep me").unwrap();
+
+    let mut vault = Vault::new(&path, "");
+    add_login(&mut vault.root, "github.com").unwrap();
+    let before = json(&vault.root);
+    let result = vault.transaction(PASS, |root| add_login(root, "gitlab.com"));
+    assert!(result.is_err());
+    assert_eq!(json(&vault.root), before);
+    assert_eq!(dir.names(), ["vault.bin"]);
+}
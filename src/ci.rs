@@ -0,0 +1,111 @@
+use passrus_core::PassrusError;
+use rand::{rngs::OsRng, RngCore};
+use std::{collections::BTreeMap, fmt::Write, str::FromStr};
+
+/// output formats understood by CI runners.
+#[derive(Clone, Copy)]
+pub enum CiFormat {
+    /// `NAME="value"` lines with backslash escapes, for dotenv loaders: docker compose's env_file, direnv's dotenv,
+    /// python-dotenv. not for `docker run --env-file`, which takes values verbatim and would keep the quotes.
+    Dotenv,
+    /// multi-line safe `NAME<<delimiter` blocks, to append to $GITHUB_ENV.
+    GithubEnv,
+    /// a flat JSON object of name to value.
+    Json,
+}
+
+impl FromStr for CiFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dotenv" => Ok(CiFormat::Dotenv),
+            "github-env" => Ok(CiFormat::GithubEnv),
+            "json" => Ok(CiFormat::Json),
+            other => Err(format!(
+                "unknown format {:?}, expected dotenv, github-env or json",
+                other
+            )),
+        }
+    }
+}
+
+/// environment variable name for an entry key: uppercased, anything outside [A-Z0-9_] becomes '_',
+/// and names starting with a digit get a leading '_'. "api.example.com" becomes "API_EXAMPLE_COM".
+pub fn var_name(key: &str) -> String {
+    let mut name: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// name each (entry key, value) pair with var_name. two keys that map to the same name, such as "api.example.com" and
+/// "api-example-com", are an error rather than one silently replacing the other.
+pub fn variables<I: IntoIterator<Item = (String, String)>>(
+    secrets: I,
+) -> Result<BTreeMap<String, String>, PassrusError> {
+    let mut keys = BTreeMap::new();
+    let mut vars = BTreeMap::new();
+    for (key, value) in secrets {
+        let name = var_name(&key);
+        if let Some(other) = keys.insert(name.clone(), key.clone()) {
+            return Err(PassrusError::InvalidEntry(format!(
+                "{:?} and {:?} would both be exported as {}, rename one of them",
+                other, key, name
+            )));
+        }
+        vars.insert(name, value);
+    }
+    Ok(vars)
+}
+
+/// render name/value pairs in the given format. names are emitted in sorted order.
+pub fn render(vars: &BTreeMap<String, String>, format: CiFormat) -> String {
+    let mut out = String::new();
+    match format {
+        CiFormat::Dotenv => {
+            for (name, value) in vars {
+                let escaped = value
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('$', "\\$")
+                    .replace('\n', "\\n")
+                    .replace('\r', "\\r");
+                let _ = writeln!(out, "{}=\"{}\"", name, escaped);
+            }
+        }
+        CiFormat::GithubEnv => {
+            for (name, value) in vars {
+                let delimiter = heredoc_delimiter(value);
+                let _ = writeln!(out, "{}<<{}\n{}\n{}", name, delimiter, value, delimiter);
+            }
+        }
+        CiFormat::Json => {
+            out = serde_json::to_string_pretty(vars).unwrap_or_default();
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// a random delimiter that does not occur in value, so a secret can't end the block early and inject variables.
+fn heredoc_delimiter(value: &str) -> String {
+    loop {
+        let mut bytes = [0u8; 8];
+        OsRng.fill_bytes(&mut bytes);
+        let delimiter = format!("PASSRUS_EOF_{}", hex::encode(bytes));
+        if !value.contains(&delimiter) {
+            return delimiter;
+        }
+    }
+}
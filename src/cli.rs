@@ -0,0 +1,343 @@
+//! the command line interface: run dispatches to one function per subcommand, grouped by area in the modules below.
+//! every command takes its arguments without the program and subcommand names and returns the process exit code,
+//! 2 for bad usage. the helpers here are shared by all of them.
+mod container;
+mod entry;
+mod export;
+#[cfg(feature = "vault-kv")]
+mod kv;
+mod onetime;
+mod password;
+mod vault;
+
+use passrus_core::{vaultfile, Entry, EntryRef, Layout, PassrusError};
+use std::{env, time::SystemTime};
+use tracing::error;
+
+/// dispatch a subcommand, args excludes the program name. returns the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    match args.first().map(String::as_str) {
+        Some("init") => vault::init_cmd(&args[1..]),
+        Some("fsck") => vault::fsck(&args[1..]),
+        Some("encrypt-file") => vault::crypt_file(&args[1..], vaultfile::encrypt_file),
+        Some("decrypt-file") => vault::crypt_file(&args[1..], vaultfile::decrypt_file),
+        Some("share") => export::share_cmd(&args[1..]),
+        Some("import-shared") => export::import_shared_cmd(&args[1..]),
+        Some("audit") => password::audit(&args[1..]),
+        Some("doctor") => vault::doctor_cmd(&args[1..]),
+        Some("paper-export") => vault::paper_export(&args[1..]),
+        Some("paper-import") => vault::paper_import(&args[1..]),
+        Some("import-otp") => onetime::import_otp_cmd(&args[1..]),
+        Some("otp") => onetime::otp_cmd(&args[1..]),
+        Some("show") => entry::show_cmd(&args[1..]),
+        Some("ls") => container::ls_cmd(&args[1..]),
+        Some("mkdir") => container::mkdir_cmd(&args[1..]),
+        Some("set-policy") => container::set_policy_cmd(&args[1..]),
+        Some("match-url") => entry::match_url_cmd(&args[1..]),
+        Some("set-match") => entry::set_match_cmd(&args[1..]),
+        Some("clip") => entry::clip_cmd(&args[1..]),
+        Some("add") => match &args[1..] {
+            [_, _, kind] => entry::add_kind_cmd(&args[1..3], kind),
+            _ => usage(),
+        },
+        Some("add-card") => entry::add_kind_cmd(&args[1..], "card"),
+        Some("add-identity") => entry::add_kind_cmd(&args[1..], "identity"),
+        Some("add-note") => entry::add_note_cmd(&args[1..]),
+        Some("alias") => entry::alias_cmd(&args[1..]),
+        Some("link") => entry::link_cmd(&args[1..]),
+        Some("unlink") => entry::unlink_cmd(&args[1..]),
+        Some("search") => entry::search_cmd(&args[1..]),
+        Some("passphrase") => password::passphrase_cmd(&args[1..]),
+        Some("recovery-codes") => onetime::recovery_codes_cmd(&args[1..]),
+        Some("use-recovery-code") => onetime::use_recovery_code_cmd(&args[1..]),
+        Some("ci-export") => export::ci_export(&args[1..]),
+        Some("ci-token") => export::ci_token(&args[1..]),
+        Some("secrets-dir") => export::secrets_dir_cmd(&args[1..]),
+        Some("export-pass") => export::export_pass_cmd(&args[1..]),
+        #[cfg(feature = "vault-kv")]
+        Some("kv-push") => kv::kv_push_cmd(&args[1..]),
+        #[cfg(feature = "vault-kv")]
+        Some("kv-pull") => kv::kv_pull_cmd(&args[1..]),
+        #[cfg(not(feature = "vault-kv"))]
+        Some("kv-push" | "kv-pull") => {
+            eprintln!("passrus was built without the vault-kv feature");
+            2
+        }
+        Some("refresh-crypto") => vault::refresh_crypto_cmd(&args[1..]),
+        Some("rotate-every") => password::rotate_every_cmd(&args[1..]),
+        Some("rotate") => password::rotate_cmd(&args[1..]),
+        Some("due") => password::due_cmd(&args[1..]),
+        Some("edit") => entry::edit_cmd(&args[1..]),
+        Some("mv") => entry::mv_cmd(&args[1..]),
+        Some("shard") => vault::reshape_cmd(&args[1..], Layout::Sharded),
+        Some("unshard") => vault::reshape_cmd(&args[1..], Layout::File),
+        Some("export-container") => container::export_container_cmd(&args[1..]),
+        Some("import-container") => container::import_container_cmd(&args[1..]),
+        Some(other) => {
+            eprintln!("unknown command: {}", other);
+            usage()
+        }
+        None => usage(),
+    }
+}
+
+/// print the usage to stderr. returns 2, the exit code for bad usage.
+fn usage() -> i32 {
+    eprintln!("usage:");
+    eprintln!("  passrus init [vault]                create a new, empty vault, asking for what isn't given");
+    eprintln!("  passrus fsck --spec <file>          check a file against the on-disk format spec");
+    eprintln!("  passrus encrypt-file <src> <dst>    encrypt any file with a password");
+    eprintln!("  passrus decrypt-file <src> <dst>    decrypt a file written by encrypt-file");
+    eprintln!("  passrus share <vault> <entry>       export one entry under a one-time passphrase");
+    eprintln!("  passrus import-shared <vault> <container>");
+    eprintln!("                                      read a share blob and its passphrase from stdin, add the entry");
+    eprintln!("  passrus audit <vault>               list entries whose password breaks a policy or is easy to guess");
+    eprintln!("  passrus doctor <vault>...           check vault files, their permissions and surroundings for problems");
+    eprintln!("  passrus paper-export <vault> <out.svg>");
+    eprintln!("                                      print the encrypted vault as a sheet of QR codes for cold storage");
+    eprintln!("  passrus paper-import <vault>        rebuild a vault from scanned paper-export codes on stdin, one per line");
+    eprintln!("  passrus import-otp <vault> <container>");
+    eprintln!("                                      read otpauth:// and otpauth-migration:// URIs from stdin, attach the seeds");
+    eprintln!("  passrus otp <vault> <entry> [--resync <code> [<code>]]");
+    eprintln!("                                      print the entry's current one time password, or resync an HOTP counter");
+    eprintln!("  passrus show [-s|--reveal] [-a <attribute>]... [-t] <vault> <entry>");
+    eprintln!("                                      print an entry, secrets masked unless -s is given. -a prints just");
+    eprintln!("                                      the attribute (title, username, password, url, notes, revision, or");
+    eprintln!(
+        "                                      a kind field), -t the current one time password"
+    );
+    eprintln!("  passrus show <entry>                print an entry from the vault in PASSRUS_VAULT like pass show");
+    eprintln!("  passrus ls [-R] [-f] <vault> [container]");
+    eprintln!("                                      list a container's children and entries, -R recursively, -f as paths");
+    eprintln!(
+        "  passrus mkdir <vault> <container>   create a container and any missing ones above it"
+    );
+    eprintln!("  passrus set-policy <vault> <container> [<rule>...]");
+    eprintln!("                                      require new passwords below container to meet the rules (min-length=<n>,");
+    eprintln!("                                      lowercase, uppercase, digit, symbol, no-username, not-in-breach=<dir>,");
+    eprintln!("                                      enforce), none to remove");
+    eprintln!("  passrus match-url <vault> <url>     list the logins to offer on the page at url");
+    eprintln!("  passrus set-match <vault> <entry> [exact-host|base-domain|subdomains|regex:<pattern>|never]...");
+    eprintln!("                                      set the pages an entry is offered on, none for its base domain");
+    eprintln!(
+        "  passrus clip [-a <attribute>] [-t] [--once] [--primary] <vault> <entry> [timeout]"
+    );
+    eprintln!("                                      copy the password (or attribute, or otp) to the clipboard, cleared");
+    eprintln!("                                      after timeout seconds (10, 0 to keep it) or with --once after the");
+    eprintln!("                                      first paste. --primary uses the primary selection instead");
+    eprintln!("  passrus add <vault> <entry> <kind>  add an entry of kind (login, card, identity, note, recovery_codes)");
+    eprintln!("                                      from \"field: value\" lines on stdin, checked against the kind's");
+    eprintln!(
+        "                                      fields. a login takes username, email and password"
+    );
+    eprintln!("  passrus add-card <vault> <entry>    same as add <vault> <entry> card (cardholder, number, expiry MM/YY,");
+    eprintln!("                                      cvv, pin)");
+    eprintln!("  passrus add-identity <vault> <entry>");
+    eprintln!("                                      same as add <vault> <entry> identity (name, address, phone,");
+    eprintln!("                                      national_id, passport_number, protect: <fields to mask>)");
+    eprintln!("  passrus add-note <vault> <entry>    add a secure note, titled after the entry key, with stdin as its body");
+    eprintln!("  passrus alias <vault> <entry> <alias>");
+    eprintln!("                                      make entry reachable at a second path, sharing one stored record");
+    eprintln!("  passrus link <vault> <entry> recovery|two_factor|signs_in_with|related <target>");
+    eprintln!("                                      record how target relates to entry, shown by show on both sides");
+    eprintln!("  passrus unlink <vault> <entry> <target> [<relation>]");
+    eprintln!("                                      remove the entry's links to target");
+    eprintln!("  passrus search <vault> <field> <value>");
+    eprintln!("                                      list entries whose field (url, username, email, phone, ...) is value");
+    eprintln!("  passrus passphrase --wordlist <file> [--words <n>] [--separator <s>]");
+    eprintln!("                                      print a passphrase of random words (6 by default) from a wordlist");
+    eprintln!("  passrus recovery-codes <vault> <entry>");
+    eprintln!("                                      store recovery codes read from stdin as a recovery codes entry");
+    eprintln!("  passrus use-recovery-code <vault> <entry>");
+    eprintln!("                                      print the next unused recovery code and mark it used");
+    eprintln!("  passrus ci-export <vault> --container <path> --format dotenv|github-env|json");
+    eprintln!("                                      print the container's logins as CI variables (entry key -> password)");
+    eprintln!("  passrus ci-export <token file> --format dotenv|github-env|json");
+    eprintln!("                                      the same for a file written by ci-token, with PASSRUS_CI_TOKEN");
+    eprintln!("  passrus ci-token <vault> --container <path> <out>");
+    eprintln!("                                      write the container's logins to out under a new token for CI");
+    eprintln!("  passrus secrets-dir <vault> --container <path> <dir> [--watch <seconds>]");
+    eprintln!("                                      write the container's entries to dir as files, docker secrets style");
+    eprintln!("  passrus export-pass <vault> <dir> (--gpg <id> | --age <recipient>)...");
+    eprintln!("                                      write the vault as a pass (gpg) or passage (age) password store");
+    eprintln!("  passrus kv-push <vault> <container> <mount>[/<prefix>] --token-entry <entry> [--delete]");
+    eprintln!("                                      write the container's logins to the Vault KV v2 mount at VAULT_ADDR");
+    eprintln!("  passrus kv-pull <vault> <container> <mount>[/<prefix>] --token-entry <entry>");
+    eprintln!("                                      add or update logins in the container from the Vault KV v2 mount");
+    eprintln!("  passrus export-container <vault> <container> <out> [--move]");
+    eprintln!("                                      write a container subtree to out, encrypted with PASSRUS_EXPORT_PASSWORD");
+    eprintln!("  passrus import-container <vault> <file> <parent> [--as <name>]");
+    eprintln!("                                      add a subtree written by export-container below parent");
+    eprintln!("  passrus rotate-every <vault> <entry> <days>|month|quarter|year|off");
+    eprintln!("                                      remind to change the entry's password every days days, or on");
+    eprintln!("                                      the first of every month, quarter or year");
+    eprintln!(
+        "  passrus rotate <vault> <entry>      set the entry's password to the first line of stdin"
+    );
+    eprintln!("  passrus due <vault>                 list the entries due for a password change");
+    eprintln!("  passrus edit <vault> <entry>        change the fields given on stdin as \"field: value\" lines");
+    eprintln!("  passrus mv <vault> <entry> <new entry>");
+    eprintln!("                                      move or rename an entry, keeping its aliases and links");
+    eprintln!("  passrus shard <vault> <dir>         copy the vault into dir, one file per top-level container");
+    eprintln!("  passrus unshard <dir> <vault>       copy a sharded vault back into a single file");
+    eprintln!("  passrus refresh-crypto <vault> [--max-age <days>]");
+    eprintln!("                                      re-encrypt the vault and entry passwords with fresh salts and nonces");
+    eprintln!();
+    eprintln!("edit, rotate, rotate-every and set-match take --if-revision <n>, and fail if the entry was saved since");
+    eprintln!("revision n (see show).");
+    eprintln!("the password is read from PASSRUS_PASSWORD. fsck works without it, but only checks the layout.");
+    eprintln!(
+        "set PASSRUS_ALLOW_WEAK to add entries that break an enforced container password policy, or to init a vault"
+    );
+    eprintln!("with a weak master password.");
+    2
+}
+
+/// the arguments of a command taking exactly N of them, or None after printing the usage.
+fn exactly<const N: usize>(args: &[String]) -> Option<&[String; N]> {
+    let args = args.try_into().ok();
+    if args.is_none() {
+        usage();
+    }
+    args
+}
+
+/// exactly's arguments and the master password from PASSRUS_PASSWORD, or None once what is wrong has been printed.
+fn unlock<const N: usize>(args: &[String]) -> Option<(&[String; N], String)> {
+    Some((exactly(args)?, required_password()?))
+}
+
+/// the master password from PASSRUS_PASSWORD, printing an error if it is missing.
+fn required_password() -> Option<String> {
+    required_env("PASSRUS_PASSWORD")
+}
+
+fn required_env(name: &str) -> Option<String> {
+    let value = env::var(name).ok();
+    if value.is_none() {
+        eprintln!("{} must be set", name);
+    }
+    value
+}
+
+/// turn a command's result into an exit code, logging the error.
+fn report(res: Result<(), PassrusError>) -> i32 {
+    match res {
+        Ok(()) => 0,
+        Err(err) => {
+            error!(target:"cli", "{} ({})", err, err.code());
+            1
+        }
+    }
+}
+
+/// an entry's password in the clear, empty if it has none. fails if the stored password doesn't decrypt with pass,
+/// so ciphertext is never passed on as if it were the password.
+fn plaintext_password(entry: &Entry, pass: &str) -> Result<Vec<u8>, PassrusError> {
+    if entry.pass_vec.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut entry = entry.clone();
+    entry.decrypt_password(pass)?;
+    Ok(entry.pass_vec)
+}
+
+/// a copy of entry with its protected fields opened (see Entry::open_fields), for showing or exporting them.
+fn with_fields(entry: &Entry, pass: &str) -> Result<Entry, PassrusError> {
+    let mut entry = entry.clone();
+    entry.open_fields(pass)?;
+    Ok(entry)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// split `--if-revision <n>` off args: the revision of the entry the caller last read, so the change fails with a
+/// conflict if it was saved since. see passrus_core::revision. None if n isn't a number.
+fn if_revision(args: &[String]) -> Option<(Vec<String>, Option<u64>)> {
+    let mut args = args.to_vec();
+    let expected = match args.iter().position(|arg| arg == "--if-revision") {
+        Some(i) => {
+            let revision = args.get(i + 1)?.parse().ok()?;
+            args.drain(i..i + 2);
+            Some(revision)
+        }
+        None => None,
+    };
+    Some((args, expected))
+}
+
+/// options of the keepassxc-cli compatible commands.
+#[derive(Default)]
+struct KpOptions {
+    attributes: Vec<String>,
+    show_protected: bool,
+    totp: bool,
+    recursive: bool,
+    flatten: bool,
+    once: bool,
+    primary: bool,
+}
+
+/// split args into positionals and options, which may appear anywhere as with keepassxc-cli. allowed holds the
+/// short forms the command takes (the long form for options without one), anything else starting with '-' is
+/// rejected.
+fn kp_options<'a>(args: &'a [String], allowed: &[&str]) -> Option<(Vec<&'a String>, KpOptions)> {
+    let mut positional = Vec::new();
+    let mut options = KpOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let short = match arg.as_str() {
+            "-a" | "--attributes" | "--attribute" => "-a",
+            "-s" | "--show-protected" | "--reveal" => "-s",
+            "-t" | "--totp" => "-t",
+            "-R" | "--recursive" => "-R",
+            "-f" | "--flatten" => "-f",
+            flag if flag.starts_with('-') && flag != "-" => flag,
+            _ => {
+                positional.push(arg);
+                continue;
+            }
+        };
+        if !allowed.contains(&short) {
+            eprintln!("unknown option: {}", arg);
+            return None;
+        }
+        match short {
+            "-a" => options.attributes.push(args.next()?.clone()),
+            "-s" => options.show_protected = true,
+            "-t" => options.totp = true,
+            "-R" => options.recursive = true,
+            "-f" => options.flatten = true,
+            "--once" => options.once = true,
+            _ => options.primary = true,
+        }
+    }
+    Some((positional, options))
+}
+
+/// an entry attribute by keepassxc name (title, username, password, url, notes; case is ignored) or by the name of a
+/// field of the entry's kind. the title is the entry key, which is its url. None when the entry has no such field.
+fn attribute(entry: &Entry, name: &str, pass: &str) -> Result<Option<String>, PassrusError> {
+    let name = name.to_lowercase();
+    let entry = &with_fields(entry, pass)?;
+    Ok(match name.as_str() {
+        "title" | "url" => Some(entry.url.clone()),
+        "username" => Some(entry.username.clone()),
+        "email" => Some(entry.email.clone()),
+        "password" => Some(String::from_utf8_lossy(&plaintext_password(entry, pass)?).into_owned()),
+        "notes" => Some(entry.kind.field_values("body").join("\n")),
+        "revision" => Some(entry.revision.to_string()),
+        field if entry.kind.schema().iter().any(|spec| spec.name == field) => {
+            Some(entry.kind.field_values(field).join("\n"))
+        }
+        _ => None,
+    })
+}
+
+fn no_attribute(entry_ref: &EntryRef, name: &str) -> PassrusError {
+    PassrusError::InvalidEntry(format!("{} has no attribute {}", entry_ref, name))
+}
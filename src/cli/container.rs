@@ -0,0 +1,207 @@
+//! listing containers and moving them between vaults.
+use super::{kp_options, report, required_env, required_password, unlock, usage, KpOptions};
+use passrus_core::{
+    policy::PasswordPolicy, subtree, Container, ContainerPath, PassrusError, Vault,
+};
+use std::{fs, io::Write, os::unix::fs::OpenOptionsExt};
+
+/// `ls [-R] [-f] <vault> [container]`: list a container like keepassxc-cli ls, child containers with a trailing '/'
+/// first, then entry keys. -R descends into the children, indenting their contents, -f prints full paths instead.
+pub(super) fn ls_cmd(args: &[String]) -> i32 {
+    let (path, container, options) = match kp_options(args, &["-R", "-f"]) {
+        Some((positional, options)) if (1..=2).contains(&positional.len()) => {
+            (positional[0], positional.get(1).copied(), options)
+        }
+        _ => return usage(),
+    };
+    let pass = match required_password() {
+        Some(pass) => pass,
+        None => return 2,
+    };
+
+    report((|| {
+        let vault = Vault::open(path, &pass)?;
+        let container_path = match container {
+            Some(container) => ContainerPath::parse(container)?,
+            None => ContainerPath::root(),
+        };
+        list_container(vault.root.get_container(&container_path)?, "", 0, &options);
+        Ok(())
+    })())
+}
+
+/// print one ls level. prefix is the container's path below the listed one, used by -f.
+fn list_container(container: &Container, prefix: &str, depth: usize, options: &KpOptions) {
+    let name_of = |name: &str| {
+        if options.flatten {
+            format!("{}{}", prefix, name)
+        } else {
+            format!("{}{}", "  ".repeat(depth), name)
+        }
+    };
+    let mut children: Vec<(&String, &Container)> = container.children.iter().collect();
+    children.sort_by_key(|(name, _)| *name);
+    for (name, child) in children {
+        println!("{}/", name_of(name));
+        if options.recursive {
+            list_container(child, &format!("{}{}/", prefix, name), depth + 1, options);
+        }
+    }
+    let mut keys: Vec<&String> = container.entries.keys().collect();
+    keys.sort();
+    for key in keys {
+        println!("{}", name_of(key));
+    }
+}
+
+/// `mkdir <vault> <container>`: create a container, and the missing ones above it like mkdir -p. fails if it exists.
+pub(super) fn mkdir_cmd(args: &[String]) -> i32 {
+    let ([path, container], pass) = match unlock(args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    report((|| {
+        let container_path = ContainerPath::parse(container)?;
+        let mut vault = Vault::open(path, &pass)?;
+        if vault.root.get_container(&container_path).is_ok() {
+            return Err(PassrusError::InvalidPath(format!(
+                "{} already exists",
+                container_path
+            )));
+        }
+        let mut parent = &mut vault.root;
+        for name in container_path.segments() {
+            if !parent.children.contains_key(name) {
+                parent.add_child(Container::new(name))?;
+            }
+            parent = parent.get_container_mut(&ContainerPath::parse(name)?)?;
+        }
+        vault.save(&pass)?;
+        println!("created {}", container_path);
+        Ok(())
+    })())
+}
+
+/// `set-policy <vault> <container> [<rule>...]`: set the password policy checked when passwords below the container are
+/// added or changed (see PasswordPolicy::parse for the rules). without rules the container's own policy is removed.
+pub(super) fn set_policy_cmd(args: &[String]) -> i32 {
+    let (path, container, rules) = match args {
+        [path, container, rules @ ..] => (path, container, rules),
+        _ => return usage(),
+    };
+    let pass = match required_password() {
+        Some(pass) => pass,
+        None => return 2,
+    };
+
+    report((|| {
+        let policy = match rules {
+            [] => None,
+            rules => Some(PasswordPolicy::parse(rules)?),
+        };
+        let mut vault = Vault::open(path, &pass)?;
+        vault
+            .root
+            .set_policy(&ContainerPath::parse(container)?, policy)?;
+        vault.save(&pass)?;
+        Ok(())
+    })())
+}
+
+/// `export-container <vault> <container> <out> [--move]`: write the container and everything below it to out,
+/// encrypted with PASSRUS_EXPORT_PASSWORD. out must not exist yet and is created readable by the owner only.
+/// with --move the container is removed from the vault once out is written.
+pub(super) fn export_container_cmd(args: &[String]) -> i32 {
+    let (path, container, out, move_out) = match args {
+        [path, container, out] => (path, container, out, false),
+        [path, container, out, flag] if flag == "--move" => (path, container, out, true),
+        _ => return usage(),
+    };
+    let (pass, export_pass) = match (required_password(), required_env("PASSRUS_EXPORT_PASSWORD")) {
+        (Some(pass), Some(export_pass)) => (pass, export_pass),
+        _ => return 2,
+    };
+
+    report((|| {
+        let mut vault = Vault::open(path, &pass)?;
+        let container_path = ContainerPath::parse(container)?;
+        let parent_path = match container_path.parent() {
+            Some(parent) => parent,
+            None => {
+                return Err(PassrusError::InvalidPath(
+                    "export the whole vault by copying its file".to_owned(),
+                ))
+            }
+        };
+        let data = subtree::export(&vault.root, &container_path, &pass, &export_pass)?;
+        // an existing file is never replaced, it may be the only copy of an earlier export.
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(out)?;
+        if let Err(err) = file.write_all(&data) {
+            let _ = fs::remove_file(out);
+            return Err(err.into());
+        }
+        println!("exported {} to {}", container_path, out);
+
+        if move_out {
+            let name = container_path
+                .segments()
+                .last()
+                .cloned()
+                .unwrap_or_default();
+            vault
+                .root
+                .get_container_mut(&parent_path)?
+                .children
+                .remove(&name);
+            vault.save(&pass)?;
+            println!("removed {} from {}", container_path, path);
+        }
+        Ok(())
+    })())
+}
+
+/// `import-container <vault> <file> <parent> [--as <name>]`: add a subtree written by export-container, opened with
+/// PASSRUS_EXPORT_PASSWORD, as a child of parent. an existing child of the same name is never replaced.
+pub(super) fn import_container_cmd(args: &[String]) -> i32 {
+    let (path, file, parent, name) = match args {
+        [path, file, parent] => (path, file, parent, None),
+        [path, file, parent, flag, name] if flag == "--as" => (path, file, parent, Some(name)),
+        _ => return usage(),
+    };
+    let (pass, export_pass) = match (required_password(), required_env("PASSRUS_EXPORT_PASSWORD")) {
+        (Some(pass), Some(export_pass)) => (pass, export_pass),
+        _ => return 2,
+    };
+
+    report((|| {
+        let mut subtree = subtree::import(fs::read(file)?, &export_pass, &pass)?;
+        if let Some(name) = name {
+            subtree.name = name.clone();
+        }
+        let mut vault = Vault::open(path, &pass)?;
+        if subtree
+            .iter_entries()
+            .any(|(_, entry)| vault.root.find_by_id(&entry.id).is_some())
+        {
+            subtree::renew_ids(&mut subtree, &pass)?;
+        }
+        let parent_path = ContainerPath::parse(parent)?;
+        let target = parent_path.join(&subtree.name)?;
+        let parent = vault.root.get_container_mut(&parent_path)?;
+        if parent.children.contains_key(&subtree.name) {
+            return Err(PassrusError::InvalidPath(format!(
+                "{} already exists, import it under another name with --as",
+                target
+            )));
+        }
+        parent.add_child(subtree)?;
+        vault.save(&pass)?;
+        println!("imported {}", target);
+        Ok(())
+    })())
+}
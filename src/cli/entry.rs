@@ -0,0 +1,628 @@
+//! reading and changing entries.
+use super::{
+    attribute, if_revision, kp_options, no_attribute, onetime::next_otp,
+    password::check_new_password, plaintext_password, report, required_env, required_password,
+    unix_now, unlock, usage, with_fields,
+};
+use crate::clipboard;
+use crate::pass_store;
+use passrus_core::{
+    kind::{Alias, EntryKind, Note},
+    link::{Link, Relation},
+    matching::{self, MatchRule},
+    revision, Entry, EntryRef, PassrusError, Vault,
+};
+use std::{
+    env,
+    io::{self, BufRead, Write},
+    time::Duration,
+};
+
+/// `show [-s] [-a <attribute>]... [-t] <vault> <entry>`: print an entry's fields, secrets masked unless -s (or
+/// --reveal). as with keepassxc-cli show, -a prints only the named attributes' values, one per line, and -t the
+/// current one time password. `show <entry>`, without a vault, is pass_show instead.
+pub(super) fn show_cmd(args: &[String]) -> i32 {
+    if let [entry] = args {
+        if !entry.starts_with('-') {
+            return pass_show(entry);
+        }
+    }
+    let (path, entry, options) = match kp_options(args, &["-s", "-a", "-t"]) {
+        Some((positional, options)) if positional.len() == 2 => {
+            (positional[0], positional[1], options)
+        }
+        _ => return usage(),
+    };
+    let reveal = options.show_protected;
+    let pass = match required_password() {
+        Some(pass) => pass,
+        None => return 2,
+    };
+
+    report((|| {
+        let mut vault = Vault::open(path, &pass)?;
+        let entry_ref = EntryRef::parse(entry)?;
+        let entry = &with_fields(vault.root.resolve(&entry_ref)?, &pass)?;
+        if !options.attributes.is_empty() || options.totp {
+            for name in &options.attributes {
+                let value =
+                    attribute(entry, name, &pass)?.ok_or_else(|| no_attribute(&entry_ref, name))?;
+                println!("{}", value);
+            }
+            if options.totp {
+                println!("{}", next_otp(&mut vault, &entry_ref, &pass)?);
+            }
+            return Ok(());
+        }
+
+        let mut fields = vec![
+            ("kind", entry.kind.name().to_owned()),
+            ("url", entry.url.clone()),
+        ];
+        for (label, value) in [("username", &entry.username), ("email", &entry.email)] {
+            if !value.is_empty() {
+                fields.push((label, value.clone()));
+            }
+        }
+        if !entry.pass_vec.is_empty() {
+            let password = if reveal {
+                String::from_utf8_lossy(&plaintext_password(entry, &pass)?).into_owned()
+            } else {
+                "••••••••".to_owned()
+            };
+            fields.push(("password", password));
+        }
+        if !entry.matching_rules.is_empty() {
+            let rules: Vec<String> = entry.matching_rules.iter().map(|r| r.to_string()).collect();
+            fields.push(("matching", rules.join(", ")));
+        }
+        if entry.otp.is_some() {
+            fields.push(("otp", "yes, see passrus otp".to_owned()));
+        }
+        if let Some(rotation) = entry.rotation {
+            let now = unix_now();
+            let status = match rotation.overdue_days(now) {
+                Some(days) => format!("{} days overdue", days),
+                None => format!("due in {} days", (rotation.due_at() - now).div_ceil(86400)),
+            };
+            fields.push(("rotation", format!("{}, {}", rotation, status)));
+        }
+        fields.extend(entry.kind.display_fields(reveal));
+        fields.push(("revision", entry.revision.to_string()));
+        for link in &entry.links {
+            let target = match vault.root.path_of(&link.target)? {
+                Some(target) => target.to_string(),
+                None => format!("{} (missing)", link.target),
+            };
+            fields.push((link.relation.name(), target));
+        }
+        for (source, relation) in vault.root.links_to(&entry.id)? {
+            fields.push(("linked from", format!("{} ({})", source, relation)));
+        }
+        for (label, value) in fields {
+            println!("{:>15}: {}", label, value);
+        }
+        Ok(())
+    })())
+}
+
+/// `show <entry>` in the vault named by PASSRUS_VAULT: print the entry the way `pass show` does (see
+/// pass_store::render), so rofi-pass, browserpass and scripts written for pass can run against passrus.
+pub(super) fn pass_show(entry: &str) -> i32 {
+    let path = match required_env("PASSRUS_VAULT") {
+        Some(path) => path,
+        None => return 2,
+    };
+    let pass = match required_password() {
+        Some(pass) => pass,
+        None => return 2,
+    };
+
+    report((|| {
+        let vault = Vault::open(&path, &pass)?;
+        let entry = vault.root.resolve(&EntryRef::parse(entry)?)?;
+        let rendered = pass_store::render(
+            &with_fields(entry, &pass)?,
+            &plaintext_password(entry, &pass)?,
+        )?;
+        io::stdout().write_all(&rendered)?;
+        Ok(())
+    })())
+}
+
+/// seconds before clip clears the clipboard, keepassxc-cli's default.
+const CLIP_TIMEOUT: u64 = 10;
+
+/// `clip [-a <attribute>] [-t] <vault> <entry> [timeout]`: copy the entry's password, another attribute or the current
+/// one time password to the clipboard like keepassxc-cli clip. it is cleared after timeout seconds, never when 0.
+pub(super) fn clip_cmd(args: &[String]) -> i32 {
+    let (path, entry, timeout, options) =
+        match kp_options(args, &["-a", "-t", "--once", "--primary"]) {
+            Some((positional, options)) if options.attributes.len() <= 1 => {
+                match positional.as_slice() {
+                    [path, entry] => (*path, *entry, CLIP_TIMEOUT, options),
+                    [path, entry, timeout] => match timeout.parse() {
+                        Ok(timeout) => (*path, *entry, timeout, options),
+                        Err(_) => {
+                            eprintln!("timeout must be a number of seconds: {}", timeout);
+                            return 2;
+                        }
+                    },
+                    _ => return usage(),
+                }
+            }
+            _ => return usage(),
+        };
+    let pass = match required_password() {
+        Some(pass) => pass,
+        None => return 2,
+    };
+
+    report((|| {
+        let mut vault = Vault::open(path, &pass)?;
+        let entry_ref = EntryRef::parse(entry)?;
+        let value = if options.totp {
+            next_otp(&mut vault, &entry_ref, &pass)?
+        } else {
+            let name = options
+                .attributes
+                .first()
+                .map_or("password", String::as_str);
+            attribute(vault.root.resolve(&entry_ref)?, name, &pass)?
+                .ok_or_else(|| no_attribute(&entry_ref, name))?
+        };
+        let selection = if options.primary {
+            "primary selection"
+        } else {
+            "clipboard"
+        };
+        let copied = || match (timeout, options.once) {
+            (0, _) => eprintln!("copied {} to the {}", entry_ref, selection),
+            (_, false) => eprintln!(
+                "copied {} to the {}, clearing it in {} seconds",
+                entry_ref, selection, timeout
+            ),
+            (_, true) => eprintln!(
+                "copied {} to the {}, clearing it after the first paste or in {} seconds",
+                entry_ref, selection, timeout
+            ),
+        };
+        clipboard::copy(
+            value.as_bytes(),
+            options.primary,
+            Duration::from_secs(timeout),
+            options.once,
+            &copied,
+        )?;
+        if timeout > 0 {
+            eprintln!("{} cleared", selection);
+        }
+        Ok(())
+    })())
+}
+
+/// `add <vault> <entry> <kind>`, and the add-card/add-identity shorthands: read "field: value" lines from stdin,
+/// build an entry of kind from them (see EntryKind::from_fields, which checks them against the kind's schema) and add it.
+/// a login takes username, email and password instead, and its password is checked against the container's policy.
+pub(super) fn add_kind_cmd(args: &[String], kind: &str) -> i32 {
+    let ([path, entry], pass) = match unlock(args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    report((|| {
+        let input = io::read_to_string(io::stdin())?;
+        let fields = input
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                line.split_once(':').ok_or_else(|| {
+                    PassrusError::InvalidEntry(format!("expected \"field: value\", got {:?}", line))
+                })
+            })
+            .collect::<Result<Vec<(&str, &str)>, PassrusError>>()?;
+        let entry_ref = EntryRef::parse(entry)?;
+        let (new_entry, plaintext) = if kind == "login" {
+            login_from_fields(&fields, &entry_ref.key, &pass)?
+        } else {
+            let mut new_entry = Entry::new("", Vec::new(), "", &entry_ref.key);
+            new_entry.set_kind(EntryKind::from_fields(kind, &fields)?, &pass)?;
+            (new_entry, String::new())
+        };
+
+        let mut vault = Vault::open(path, &pass)?;
+        let container = vault.root.get_container_mut(&entry_ref.container)?;
+        if container.entries.contains_key(&entry_ref.key) {
+            return Err(PassrusError::InvalidEntry(format!(
+                "{} already exists",
+                entry_ref
+            )));
+        }
+        let name = new_entry.kind.name();
+        let allow_violations = env::var_os("PASSRUS_ALLOW_WEAK").is_some();
+        let warnings = vault.root.add_entry_checked(
+            &entry_ref.container,
+            new_entry,
+            &plaintext,
+            allow_violations,
+        )?;
+        vault.save(&pass)?;
+        for warning in warnings {
+            eprintln!("warning: password policy: {}", warning);
+        }
+        println!("added {} {}", name, entry_ref);
+        Ok(())
+    })())
+}
+
+/// a login keyed key from "field: value" pairs (username, email, password, each at most once, the password required),
+/// with the password encrypted under pass. values are kept as given apart from the space after the colon, like edit's. returns the entry and the plaintext password for the policy check.
+fn login_from_fields(
+    fields: &[(&str, &str)],
+    key: &str,
+    pass: &str,
+) -> Result<(Entry, String), PassrusError> {
+    let mut entry = Entry::new("", Vec::new(), "", key);
+    let mut plaintext = None;
+    for (i, (field, value)) in fields.iter().enumerate() {
+        let field = field.trim();
+        if fields[..i].iter().any(|(other, _)| other.trim() == field) {
+            return Err(PassrusError::InvalidEntry(format!(
+                "{} is given more than once",
+                field
+            )));
+        }
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "username" => entry.username = value.to_owned(),
+            "email" => entry.email = value.to_owned(),
+            "password" => plaintext = Some(value.to_owned()),
+            other => {
+                return Err(PassrusError::InvalidEntry(format!(
+                    "login entries have no field {:?}",
+                    other
+                )))
+            }
+        }
+    }
+    let plaintext = match plaintext {
+        Some(plaintext) if !plaintext.is_empty() => plaintext,
+        _ => {
+            return Err(PassrusError::InvalidEntry(
+                "login entries need a password".to_owned(),
+            ))
+        }
+    };
+    entry.set_password(plaintext.as_bytes(), pass, unix_now())?;
+    Ok((entry, plaintext))
+}
+
+/// `add-note <vault> <entry>`: store all of stdin as the body of a note titled after the entry key.
+pub(super) fn add_note_cmd(args: &[String]) -> i32 {
+    let ([path, entry], pass) = match unlock(args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    report((|| {
+        let body = io::read_to_string(io::stdin())?;
+        let mut vault = Vault::open(path, &pass)?;
+        let entry_ref = EntryRef::parse(entry)?;
+        let container = vault.root.get_container_mut(&entry_ref.container)?;
+        if container.entries.contains_key(&entry_ref.key) {
+            return Err(PassrusError::InvalidEntry(format!(
+                "{} already exists",
+                entry_ref
+            )));
+        }
+        let mut new_entry = Entry::new("", Vec::new(), "", &entry_ref.key);
+        new_entry.set_kind(
+            EntryKind::Note(Note {
+                title: entry_ref.key.clone(),
+                body,
+            }),
+            &pass,
+        )?;
+        vault
+            .root
+            .add_entry_checked(&entry_ref.container, new_entry, "", false)?;
+        vault.save(&pass)?;
+        println!("added note {}", entry_ref);
+        Ok(())
+    })())
+}
+
+/// `edit <vault> <entry>`: change only the fields given on stdin as "field: value" lines, leaving the others as they
+/// are. an empty value clears the field, a repeated field (an identity's address) takes every line given for it.
+/// a new password is checked like rotate's.
+pub(super) fn edit_cmd(args: &[String]) -> i32 {
+    let (args, expected) = match if_revision(args) {
+        Some(split) => split,
+        None => return usage(),
+    };
+    let ([path, entry], pass) = match unlock(&args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    report((|| {
+        let mut lines = Vec::new();
+        for line in io::stdin().lock().lines() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (field, value) = line.split_once(':').ok_or_else(|| {
+                PassrusError::InvalidEntry(format!("expected \"field: value\", got {:?}", line))
+            })?;
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            lines.push((field.trim().to_owned(), value.to_owned()));
+        }
+        if lines.is_empty() {
+            return Err(PassrusError::InvalidEntry(
+                "no fields to change on stdin".to_owned(),
+            ));
+        }
+        let patch: Vec<(&str, Option<&str>)> = lines
+            .iter()
+            .map(|(field, value)| {
+                (
+                    field.as_str(),
+                    Some(value.as_str()).filter(|v| !v.is_empty()),
+                )
+            })
+            .collect();
+
+        let mut vault = Vault::open(path, &pass)?;
+        let entry_ref = EntryRef::parse(entry)?;
+        let mut patched = vault.root.resolve(&entry_ref)?.clone();
+        revision::check(&entry_ref, expected, patched.revision)?;
+        patched.apply_patch(&patch, &pass, unix_now())?;
+        let violations = match patch.iter().find(|(field, _)| *field == "password") {
+            Some((_, Some(plaintext))) => {
+                check_new_password(&vault.root, &entry_ref, &patched, plaintext)?
+            }
+            _ => Vec::new(),
+        };
+
+        *vault.root.resolve_mut(&entry_ref)? = patched;
+        vault.save(&pass)?;
+        for warning in violations {
+            eprintln!("warning: password policy: {}", warning);
+        }
+        println!("updated {}", entry_ref);
+        Ok(())
+    })())
+}
+
+/// `mv <vault> <entry> <new entry>`: move or rename an entry, keeping its id so aliases and links to it still work.
+/// done in one transaction, so it is never saved removed but not yet re-added.
+pub(super) fn mv_cmd(args: &[String]) -> i32 {
+    let ([path, from, to], pass) = match unlock(args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    report((|| {
+        let from = EntryRef::parse(from)?;
+        let to = EntryRef::parse(to)?;
+        let mut vault = Vault::open(path, &pass)?;
+        vault.transaction(&pass, |root| {
+            let mut entry = root
+                .get_container_mut(&from.container)?
+                .entries
+                .remove(&from.key)
+                .ok_or_else(|| PassrusError::EntryNotFound(from.to_string()))?;
+            let target = root.get_container_mut(&to.container)?;
+            if target.entries.contains_key(&to.key) {
+                return Err(PassrusError::InvalidEntry(format!("{} already exists", to)));
+            }
+            entry.url = to.key.clone();
+            target.add_entry(entry);
+            Ok(())
+        })?;
+        println!("moved {} to {}", from, to);
+        Ok(())
+    })())
+}
+
+/// `alias <vault> <entry> <alias>`: make entry reachable at a second path as well. reads and changes through either
+/// path reach the one stored entry; aliasing an alias points at its target.
+pub(super) fn alias_cmd(args: &[String]) -> i32 {
+    let ([path, entry, alias], pass) = match unlock(args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    report((|| {
+        let mut vault = Vault::open(path, &pass)?;
+        let target = vault.root.resolve(&EntryRef::parse(entry)?)?.id.clone();
+        let alias_ref = EntryRef::parse(alias)?;
+        if vault
+            .root
+            .get_container(&alias_ref.container)?
+            .entries
+            .contains_key(&alias_ref.key)
+        {
+            return Err(PassrusError::InvalidEntry(format!(
+                "{} already exists",
+                alias_ref
+            )));
+        }
+        let mut alias_entry = Entry::new("", Vec::new(), "", &alias_ref.key);
+        alias_entry.kind = EntryKind::Alias(Alias { target });
+        vault
+            .root
+            .add_entry_checked(&alias_ref.container, alias_entry, "", false)?;
+        vault.save(&pass)?;
+        println!("{} is now also reachable as {}", entry, alias_ref);
+        Ok(())
+    })())
+}
+
+/// `link <vault> <entry> <relation> <target>`: record that target is related to entry, e.g. `link vault.bin
+/// work/github.com recovery personal/mail`. links point at the target's id, an alias links to the entry behind it.
+pub(super) fn link_cmd(args: &[String]) -> i32 {
+    let ([path, entry, relation, target], pass) = match unlock(args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    report((|| {
+        let relation: Relation = relation.parse()?;
+        let mut vault = Vault::open(path, &pass)?;
+        let target_id = vault.root.resolve(&EntryRef::parse(target)?)?.id.clone();
+        let entry_ref = EntryRef::parse(entry)?;
+        let entry = vault.root.resolve_mut(&entry_ref)?;
+        if entry.id == target_id {
+            return Err(PassrusError::InvalidEntry(format!(
+                "{} can't link to itself",
+                entry_ref
+            )));
+        }
+        let link = Link {
+            relation,
+            target: target_id,
+        };
+        if !entry.links.contains(&link) {
+            entry.links.push(link);
+            vault.save(&pass)?;
+        }
+        println!("{} -> {} ({})", entry_ref, target, relation);
+        Ok(())
+    })())
+}
+
+/// `unlink <vault> <entry> <target> [<relation>]`: remove the entry's links to target, or only the one of relation.
+pub(super) fn unlink_cmd(args: &[String]) -> i32 {
+    let (path, entry, target, relation) = match args {
+        [path, entry, target] => (path, entry, target, None),
+        [path, entry, target, relation] => (path, entry, target, Some(relation)),
+        _ => return usage(),
+    };
+    let pass = match required_password() {
+        Some(pass) => pass,
+        None => return 2,
+    };
+
+    report((|| {
+        let relation = relation.map(|r| r.parse::<Relation>()).transpose()?;
+        let mut vault = Vault::open(path, &pass)?;
+        let target_ref = EntryRef::parse(target)?;
+        let target_id = vault.root.resolve(&target_ref)?.id.clone();
+        let entry_ref = EntryRef::parse(entry)?;
+        let entry = vault.root.resolve_mut(&entry_ref)?;
+        let before = entry.links.len();
+        entry.links.retain(|link| {
+            link.target != target_id || relation.is_some_and(|r| r != link.relation)
+        });
+        if entry.links.len() == before {
+            return Err(PassrusError::InvalidEntry(format!(
+                "{} has no such link to {}",
+                entry_ref, target_ref
+            )));
+        }
+        vault.save(&pass)?;
+        Ok(())
+    })())
+}
+
+/// `search <vault> <field> <value>`: list the entries whose field equals value. field is url, username, email or
+/// a kind specific field such as an identity's phone. protected fields are sealed and never match.
+pub(super) fn search_cmd(args: &[String]) -> i32 {
+    let ([path, field, value], pass) = match unlock(args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    let vault = match Vault::open(path, &pass) {
+        Ok(vault) => vault,
+        Err(err) => return report(Err(err)),
+    };
+    let mut found = 0;
+    for (container, entry) in vault.root.iter_entries() {
+        let matches = match field.as_str() {
+            "url" => entry.url == *value,
+            "username" => entry.username == *value,
+            "email" => entry.email == *value,
+            other => entry.kind.field_values(other).contains(&value.as_str()),
+        };
+        if matches {
+            let entry_ref = match vault.root.entry_ref(&container, entry) {
+                Ok(entry_ref) => entry_ref,
+                Err(err) => return report(Err(err)),
+            };
+            found += 1;
+            println!("{} ({})", entry_ref, entry.kind.name());
+        }
+    }
+    if found == 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/// `match-url <vault> <url>`: list the entries to offer on the page at url, according to their matching rules.
+pub(super) fn match_url_cmd(args: &[String]) -> i32 {
+    let ([path, url], pass) = match unlock(args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    let vault = match Vault::open(path, &pass) {
+        Ok(vault) => vault,
+        Err(err) => return report(Err(err)),
+    };
+    let found: Result<Vec<String>, PassrusError> = vault
+        .root
+        .iter_entries()
+        .filter(|(_, entry)| entry.kind.is_login() && matching::matches(entry, url))
+        .map(|(container, entry)| Ok(vault.root.entry_ref(&container, entry)?.to_string()))
+        .collect();
+    let mut found = match found {
+        Ok(found) => found,
+        Err(err) => return report(Err(err)),
+    };
+    found.sort();
+    for entry in &found {
+        println!("{}", entry);
+    }
+    if found.is_empty() {
+        1
+    } else {
+        0
+    }
+}
+
+/// `set-match <vault> <entry> [<rule>...]`: replace the entry's matching rules (exact-host, base-domain, subdomains,
+/// regex:<pattern>, never). without rules the entry goes back to matching its base domain.
+pub(super) fn set_match_cmd(args: &[String]) -> i32 {
+    let (args, expected) = match if_revision(args) {
+        Some(split) => split,
+        None => return usage(),
+    };
+    let (path, entry, rules) = match args.as_slice() {
+        [path, entry, rules @ ..] => (path, entry, rules),
+        _ => return usage(),
+    };
+    let pass = match required_password() {
+        Some(pass) => pass,
+        None => return 2,
+    };
+
+    report((|| {
+        let rules = rules
+            .iter()
+            .map(|rule| MatchRule::parse(rule))
+            .collect::<Result<Vec<MatchRule>, PassrusError>>()?;
+        let mut vault = Vault::open(path, &pass)?;
+        let entry_ref = EntryRef::parse(entry)?;
+        let entry = vault.root.resolve_mut(&entry_ref)?;
+        revision::check(&entry_ref, expected, entry.revision)?;
+        entry.matching_rules = rules;
+        vault.save(&pass)?;
+        Ok(())
+    })())
+}
@@ -0,0 +1,328 @@
+//! getting secrets out of the vault: one-time shares, CI variables, secrets directories and pass stores.
+use super::{
+    plaintext_password, report, required_env, required_password, unlock, usage, with_fields,
+};
+use crate::ci::{self, CiFormat};
+use crate::pass_store;
+use crate::secrets_dir;
+use passrus_core::{
+    cryptman, passman, share, subtree, Container, ContainerPath, Entry, EntryRef, PassrusError,
+    Vault,
+};
+use rand::{rngs::OsRng, RngCore};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    io::{self, BufRead},
+    path::Path,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// `share <vault> <entry>`: print a one-time share blob and its passphrase for one entry.
+pub(super) fn share_cmd(args: &[String]) -> i32 {
+    let ([path, entry], pass) = match unlock(args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    report((|| {
+        let vault = Vault::open(path, &pass)?;
+        let mut entry = vault.root.resolve(&EntryRef::parse(entry)?)?.clone();
+        // shared in the clear, and no longer marked as under this vault's entry key.
+        if !entry.pass_vec.is_empty() {
+            entry.decrypt_password(&pass)?;
+        }
+        entry.open_fields(&pass)?;
+
+        let shared = share::share_entry(&entry)?;
+        println!("{}", shared.blob);
+        eprintln!(
+            "one-time passphrase (shown once, send it separately): {}",
+            shared.passphrase
+        );
+        Ok(())
+    })())
+}
+
+/// `import-shared <vault> <container>`: read a share blob and then its passphrase from stdin, add the entry to container and save.
+/// fails if the container already has an entry of that name. the sender's links point at ids of their vault and are dropped.
+pub(super) fn import_shared_cmd(args: &[String]) -> i32 {
+    let ([path, container], pass) = match unlock(args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    report((|| {
+        let mut lines = io::stdin().lock().lines();
+        let blob = lines.next().transpose()?.unwrap_or_default();
+        let passphrase = lines.next().transpose()?.unwrap_or_default();
+        let mut entry = share::import_shared(&blob, &passphrase)?;
+        // the sender's id may already be in this vault, e.g. when sharing between one's own vaults.
+        entry.id = passman::new_id();
+        entry.links.clear();
+        let plaintext = String::from_utf8_lossy(&entry.pass_vec).into_owned();
+
+        // store the password the same way as other entries: encrypted with a key from the vault password.
+        let mut salt = [0u8; 32];
+        OsRng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; 24];
+        OsRng.fill_bytes(&mut nonce);
+        let (key, salt) = cryptman::pass_2_key(&pass, salt)?;
+        entry.encrypt_password(key, nonce, salt)?;
+        entry.set_kind(entry.kind.clone(), &pass)?;
+
+        let mut vault = Vault::open(path, &pass)?;
+        let container_path = ContainerPath::parse(container)?;
+        if vault
+            .root
+            .get_container(&container_path)?
+            .entries
+            .contains_key(&entry.url)
+        {
+            return Err(PassrusError::InvalidEntry(format!(
+                "{} already exists",
+                EntryRef::new(container_path, &entry.url)
+            )));
+        }
+        let allow_violations = env::var_os("PASSRUS_ALLOW_WEAK").is_some();
+        let warnings =
+            vault
+                .root
+                .add_entry_checked(&container_path, entry, &plaintext, allow_violations)?;
+        for warning in warnings {
+            eprintln!("warning: password policy: {}", warning);
+        }
+        vault.save(&pass)?;
+        println!("imported shared entry into {}", container);
+        Ok(())
+    })())
+}
+
+/// `ci-export <vault> --container <path> --format <format>`: print the passwords of the logins directly in a container
+/// as CI variables, named after the entry keys. other kinds of entry are skipped. with PASSRUS_CI_TOKEN set instead of
+/// PASSRUS_PASSWORD, `ci-export <token file> --format <format>` prints those of a file written by ci-token.
+pub(super) fn ci_export(args: &[String]) -> i32 {
+    let (path, container, format) = match args {
+        [path, c_flag, container, f_flag, format]
+            if c_flag == "--container" && f_flag == "--format" =>
+        {
+            (path, Some(container), format)
+        }
+        [path, f_flag, format] if f_flag == "--format" => (path, None, format),
+        _ => return usage(),
+    };
+    let format: CiFormat = match format.parse() {
+        Ok(format) => format,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 2;
+        }
+    };
+
+    let res = match container {
+        Some(container) => {
+            let pass = match required_password() {
+                Some(pass) => pass,
+                None => return 2,
+            };
+            (|| {
+                let vault = Vault::open(path, &pass)?;
+                let container = vault
+                    .root
+                    .get_container(&ContainerPath::parse(container)?)?;
+                ci_variables(&vault.root, container, &pass)
+            })()
+        }
+        None => {
+            let token = match required_env("PASSRUS_CI_TOKEN") {
+                Some(token) => token,
+                None => return 2,
+            };
+            (|| {
+                let json = cryptman::decrypt_file_mem_gen_key(fs::read(path)?, "", &token)?;
+                let mut scoped = Container::new("");
+                scoped.from_json_arr(&json)?;
+                ci_variables(&scoped, &scoped, &token)
+            })()
+        }
+    };
+    report(res.map(|vars| print!("{}", ci::render(&vars, format))))
+}
+
+/// the CI variables for the logins directly in container, which is below root, with passwords encrypted under pass.
+fn ci_variables(
+    root: &Container,
+    container: &Container,
+    pass: &str,
+) -> Result<BTreeMap<String, String>, PassrusError> {
+    let mut secrets = Vec::new();
+    for (key, entry) in ci_logins(root, container)? {
+        let value = String::from_utf8(plaintext_password(entry, pass)?).map_err(|_| {
+            PassrusError::CorruptFile {
+                reason: format!("password of {} is not valid utf-8", key),
+            }
+        })?;
+        secrets.push((key.clone(), value));
+    }
+    ci::variables(secrets)
+}
+
+/// the logins directly in container by key, aliases followed. other kinds of entry have no password to export and are
+/// skipped with a note on stderr.
+fn ci_logins<'a>(
+    root: &'a Container,
+    container: &'a Container,
+) -> Result<Vec<(&'a String, &'a Entry)>, PassrusError> {
+    let mut logins = Vec::new();
+    for (key, entry) in &container.entries {
+        let entry = root.follow(entry)?;
+        if entry.kind.is_login() {
+            logins.push((key, entry));
+        } else {
+            eprintln!(
+                "skipped {}: {} entries have no password",
+                key,
+                entry.kind.name()
+            );
+        }
+    }
+    Ok(logins)
+}
+
+/// `ci-token <vault> --container <path> <out>`: write the passwords of the logins directly in a container to out, under
+/// their entry keys and encrypted under a new random token which is printed once. nothing else of the entries is copied. a pipeline given the token and out can run ci-export on just those entries,
+/// without the master password. run it again after changing them, the previous out is overwritten (see
+/// passrus_core::shred) so its blocks don't stay readable with the old token; deleting out and the token revokes access.
+pub(super) fn ci_token(args: &[String]) -> i32 {
+    let (path, container, out) = match args {
+        [path, c_flag, container, out] if c_flag == "--container" => (path, container, out),
+        _ => return usage(),
+    };
+    let pass = match required_password() {
+        Some(pass) => pass,
+        None => return 2,
+    };
+
+    report((|| {
+        let vault = Vault::open(path, &pass)?;
+        let container_path = ContainerPath::parse(container)?;
+        let container = vault.root.get_container(&container_path)?;
+        let mut scoped = Container::new("ci");
+        for (key, entry) in ci_logins(&vault.root, container)? {
+            // the id and entry_key flag come along, the password is encrypted under a key derived from them.
+            let mut copy = Entry::new("", entry.pass_vec.clone(), "", key);
+            copy.id = entry.id.clone();
+            copy.entry_key = entry.entry_key;
+            scoped.add_entry(copy);
+        }
+
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token = hex::encode(bytes);
+        let data = subtree::export(&scoped, &ContainerPath::root(), &pass, &token)?;
+        secrets_dir::write_atomically(Path::new(out), &data)?;
+        eprintln!(
+            "wrote {} entries of {} to {}. the token, shown once, goes in PASSRUS_CI_TOKEN:",
+            scoped.entries.len(),
+            container_path,
+            out
+        );
+        println!("{}", token);
+        Ok(())
+    })())
+}
+
+/// `secrets-dir <vault> --container <path> <dir> [--watch <seconds>]`: materialise a container into dir.
+/// with --watch the vault's modification time is polled and dir is refreshed whenever it changes.
+pub(super) fn secrets_dir_cmd(args: &[String]) -> i32 {
+    let (path, container, dir, watch) = match args {
+        [path, c_flag, container, dir] if c_flag == "--container" => (path, container, dir, None),
+        [path, c_flag, container, dir, w_flag, secs]
+            if c_flag == "--container" && w_flag == "--watch" =>
+        {
+            match secs.parse::<u64>() {
+                Ok(secs) if secs > 0 => (path, container, dir, Some(Duration::from_secs(secs))),
+                _ => return usage(),
+            }
+        }
+        _ => return usage(),
+    };
+    let pass = match required_password() {
+        Some(pass) => pass,
+        None => return 2,
+    };
+
+    let refresh = || -> Result<(), PassrusError> {
+        let vault = Vault::open(path, &pass)?;
+        let container = vault
+            .root
+            .get_container(&ContainerPath::parse(container)?)?;
+        let n = secrets_dir::materialize(container, Path::new(dir), &|entry| {
+            plaintext_password(vault.root.follow(entry)?, &pass)
+        })?;
+        println!("wrote {} secrets to {}", n, dir);
+        Ok(())
+    };
+
+    let interval = match watch {
+        Some(interval) => interval,
+        None => return report(refresh()),
+    };
+
+    let mut last_modified: Option<SystemTime> = None;
+    loop {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        if modified != last_modified {
+            // keep serving the previous contents if a refresh fails, e.g. while the vault is being rewritten.
+            if report(refresh()) == 0 {
+                last_modified = modified;
+            }
+        }
+        thread::sleep(interval);
+    }
+}
+
+/// `export-pass <vault> <dir> (--gpg <id> | --age <recipient>)...`: write the whole vault as a password store in dir,
+/// readable by pass (gpg) or passage (age). see pass_store::export.
+pub(super) fn export_pass_cmd(args: &[String]) -> i32 {
+    let (path, dir, flags) = match args {
+        [path, dir, flags @ ..] if !flags.is_empty() && flags.len().is_multiple_of(2) => {
+            (path, dir, flags)
+        }
+        _ => return usage(),
+    };
+    let mut gpg_ids = Vec::new();
+    let mut age_recipients = Vec::new();
+    for pair in flags.chunks(2) {
+        match pair[0].as_str() {
+            "--gpg" => gpg_ids.push(pair[1].clone()),
+            "--age" => age_recipients.push(pair[1].clone()),
+            _ => return usage(),
+        }
+    }
+    let encryption = match (gpg_ids.is_empty(), age_recipients.is_empty()) {
+        (false, true) => pass_store::Encryption::Gpg(gpg_ids),
+        (true, false) => pass_store::Encryption::Age(age_recipients),
+        _ => {
+            eprintln!("give gpg ids or age recipients, not both");
+            return 2;
+        }
+    };
+    let pass = match required_password() {
+        Some(pass) => pass,
+        None => return 2,
+    };
+
+    report((|| {
+        let vault = Vault::open(path, &pass)?;
+        let n = pass_store::export(&vault.root, Path::new(dir), &encryption, &|entry| {
+            Ok((
+                with_fields(entry, &pass)?,
+                plaintext_password(entry, &pass)?,
+            ))
+        })?;
+        println!("exported {} entries to {}", n, dir);
+        Ok(())
+    })())
+}
@@ -0,0 +1,179 @@
+//! mirroring containers to a HashiCorp Vault KV v2 mount, see vault_kv.
+use super::{plaintext_password, report, required_env, required_password, unix_now, usage};
+use crate::vault_kv::{self, Kv, Secret};
+use passrus_core::{Container, ContainerPath, Entry, EntryRef, PassrusError, Vault};
+use std::env;
+
+/// `kv-push <vault> <container> <mount>[/<prefix>] --token-entry <entry> [--delete]`: write the logins below the
+/// container to the KV v2 mount on the server at VAULT_ADDR, below prefix, authenticated with the password of the
+/// token entry. with --delete, secrets below prefix that aren't in the container are deleted (their older versions
+/// stay in Vault).
+pub(super) fn kv_push_cmd(args: &[String]) -> i32 {
+    let (path, container, target, token_entry, delete) = match args {
+        [path, container, target, flag, token_entry] if flag == "--token-entry" => {
+            (path, container, target, token_entry, false)
+        }
+        [path, container, target, flag, token_entry, delete]
+            if flag == "--token-entry" && delete == "--delete" =>
+        {
+            (path, container, target, token_entry, true)
+        }
+        _ => return usage(),
+    };
+    let (pass, addr) = match (required_password(), required_env("VAULT_ADDR")) {
+        (Some(pass), Some(addr)) => (pass, addr),
+        _ => return 2,
+    };
+
+    report((|| {
+        let vault = Vault::open(path, &pass)?;
+        let (kv, prefix) = connect(&vault, &addr, target, token_entry, &pass)?;
+        let container = vault
+            .root
+            .get_container(&ContainerPath::parse(container)?)?;
+        let secrets = vault_kv::secrets(&vault.root, container, &|entry| {
+            plaintext_password(entry, &pass)
+        })?;
+        let pushed = vault_kv::push(&kv, prefix, &secrets, delete)?;
+        println!(
+            "pushed {} secrets to {} ({} unchanged, {} deleted)",
+            pushed.written, target, pushed.unchanged, pushed.deleted
+        );
+        Ok(())
+    })())
+}
+
+/// `kv-pull <vault> <container> <mount>[/<prefix>] --token-entry <entry>`: add the secrets below prefix on the KV v2
+/// mount at VAULT_ADDR to the container as logins, creating the containers below it they are in, and update the
+/// logins already there. entries the mount doesn't have are kept. new passwords are checked against the container
+/// policies like added ones.
+pub(super) fn kv_pull_cmd(args: &[String]) -> i32 {
+    let (path, container, target, token_entry) = match args {
+        [path, container, target, flag, token_entry] if flag == "--token-entry" => {
+            (path, container, target, token_entry)
+        }
+        _ => return usage(),
+    };
+    let (pass, addr) = match (required_password(), required_env("VAULT_ADDR")) {
+        (Some(pass), Some(addr)) => (pass, addr),
+        _ => return 2,
+    };
+
+    report((|| {
+        let mut vault = Vault::open(path, &pass)?;
+        let (kv, prefix) = connect(&vault, &addr, target, token_entry, &pass)?;
+        let secrets = vault_kv::pull(&kv, prefix)?;
+        let base = ContainerPath::parse(container)?;
+        vault.root.get_container(&base)?;
+        let allow_violations = env::var_os("PASSRUS_ALLOW_WEAK").is_some();
+        let (changed, warnings) = vault.transaction(&pass, |root| {
+            let mut changed = 0;
+            let mut warnings = Vec::new();
+            for (secret_path, secret) in &secrets {
+                let (container_path, key) = vault_kv::entry_path(&base, secret_path)?;
+                let entry_ref = EntryRef::new(container_path, &key);
+                let (entry, new_password) = match pulled_entry(root, &entry_ref, secret, &pass)? {
+                    Some(pulled) => pulled,
+                    None => continue,
+                };
+                make_containers(root, &entry_ref.container)?;
+                let container = root.get_container_mut(&entry_ref.container)?;
+                container.entries.remove(&key);
+                changed += 1;
+                // only a new password goes through the policy checks, like rotate's.
+                if !new_password {
+                    container.add_entry(entry);
+                    continue;
+                }
+                let plaintext = &secret["password"];
+                for warning in root.add_entry_checked(
+                    &entry_ref.container,
+                    entry,
+                    plaintext,
+                    allow_violations,
+                )? {
+                    warnings.push(format!("{}: {}", entry_ref, warning));
+                }
+            }
+            Ok((changed, warnings))
+        })?;
+        for warning in warnings {
+            eprintln!("warning: password policy: {}", warning);
+        }
+        println!(
+            "pulled {} secrets from {} ({} unchanged)",
+            changed,
+            target,
+            secrets.len() - changed
+        );
+        Ok(())
+    })())
+}
+
+/// the client for target (<mount>[/<prefix>]) and the prefix, with the token from the password of token_entry.
+fn connect<'a>(
+    vault: &Vault,
+    addr: &str,
+    target: &'a str,
+    token_entry: &str,
+    pass: &str,
+) -> Result<(Kv, &'a str), PassrusError> {
+    let token = plaintext_password(vault.root.resolve(&EntryRef::parse(token_entry)?)?, pass)?;
+    let token = String::from_utf8(token).map_err(|_| PassrusError::CorruptFile {
+        reason: format!("password of {} is not valid utf-8", token_entry),
+    })?;
+    let (mount, prefix) = target.split_once('/').unwrap_or((target, ""));
+    Ok((Kv::new(addr, mount, token), prefix.trim_matches('/')))
+}
+
+/// the login at entry_ref as it should be after pulling secret and whether its password is new, None if it already
+/// is. a new entry if there is none.
+fn pulled_entry(
+    root: &Container,
+    entry_ref: &EntryRef,
+    secret: &Secret,
+    pass: &str,
+) -> Result<Option<(Entry, bool)>, PassrusError> {
+    let field = |name: &str| secret.get(name).map_or("", String::as_str);
+    let existing = root
+        .get_container(&entry_ref.container)
+        .ok()
+        .and_then(|container| container.entries.get(&entry_ref.key));
+    let (mut entry, new_password) = match existing {
+        Some(entry) if !entry.kind.is_login() => {
+            return Err(PassrusError::InvalidEntry(format!(
+                "{} is a {} entry, not a login",
+                entry_ref,
+                entry.kind.name()
+            )))
+        }
+        Some(entry) => {
+            let new_password = plaintext_password(entry, pass)? != field("password").as_bytes();
+            if !new_password && entry.username == field("username") && entry.email == field("email")
+            {
+                return Ok(None);
+            }
+            (entry.clone(), new_password)
+        }
+        None => (Entry::new("", Vec::new(), "", &entry_ref.key), true),
+    };
+    entry.username = field("username").to_owned();
+    entry.email = field("email").to_owned();
+    // an unchanged password keeps its rotation schedule.
+    if new_password {
+        entry.set_password(field("password").as_bytes(), pass, unix_now())?;
+    }
+    Ok(Some((entry, new_password)))
+}
+
+/// create the containers along path that don't exist yet.
+fn make_containers(root: &mut Container, path: &ContainerPath) -> Result<(), PassrusError> {
+    let mut parent = root;
+    for name in path.segments() {
+        if !parent.children.contains_key(name) {
+            parent.add_child(Container::new(name))?;
+        }
+        parent = parent.get_container_mut(&ContainerPath::parse(name)?)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,196 @@
+//! one time passwords and recovery codes.
+use super::{report, required_password, unix_now, unlock, usage, with_fields};
+use passrus_core::{
+    kind::{EntryKind, RecoveryCodes, RECOVERY_CODES_LOW},
+    otp, ContainerPath, Entry, EntryRef, PassrusError, Vault,
+};
+use std::io::{self, BufRead};
+
+/// `import-otp <vault> <container>`: read otpauth URIs from stdin, one per line, and attach each seed to the matching
+/// entry in container (see OtpSeed::matches). seeds without a match get a new entry keyed by their issuer.
+pub(super) fn import_otp_cmd(args: &[String]) -> i32 {
+    let ([path, container], pass) = match unlock(args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    report((|| {
+        let mut seeds = Vec::new();
+        for line in io::stdin().lock().lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                seeds.extend(otp::parse(&line)?);
+            }
+        }
+
+        let mut vault = Vault::open(path, &pass)?;
+        let container = vault
+            .root
+            .get_container_mut(&ContainerPath::parse(container)?)?;
+        for seed in seeds {
+            if let Some(entry) = container.entries.values_mut().find(|e| seed.matches(e)) {
+                println!("attached {} to {}", seed.issuer, entry.url);
+                entry.otp = Some(seed);
+                continue;
+            }
+            let key = if seed.issuer.is_empty() {
+                seed.account.clone()
+            } else {
+                seed.issuer.clone()
+            };
+            if key.is_empty() || container.entries.contains_key(&key) {
+                eprintln!(
+                    "skipped a seed for {:?}: no matching entry and no free key to add one under",
+                    seed.account
+                );
+                continue;
+            }
+            let mut entry = Entry::new(&seed.account, Vec::new(), "", &key);
+            entry.otp = Some(seed);
+            container.add_entry(entry);
+            println!("added {}", key);
+        }
+        vault.save(&pass)?;
+        Ok(())
+    })())
+}
+
+/// `otp <vault> <entry> [--resync <code> [<code>]]`: print the entry's current code. HOTP counters advance with every
+/// code, so the vault is saved afterwards. with --resync the counter is moved past the given consecutive codes instead.
+pub(super) fn otp_cmd(args: &[String]) -> i32 {
+    let (path, entry, resync) = match args {
+        [path, entry] => (path, entry, None),
+        [path, entry, flag, codes @ ..] if flag == "--resync" && (1..=2).contains(&codes.len()) => {
+            (path, entry, Some(codes))
+        }
+        _ => return usage(),
+    };
+    let pass = match required_password() {
+        Some(pass) => pass,
+        None => return 2,
+    };
+
+    report((|| {
+        let mut vault = Vault::open(path, &pass)?;
+        let entry_ref = EntryRef::parse(entry)?;
+        if let Some(codes) = resync {
+            let codes: Vec<&str> = codes.iter().map(String::as_str).collect();
+            otp_seed(&mut vault, &entry_ref)?.resync(&codes, otp::RESYNC_WINDOW)?;
+            vault.save(&pass)?;
+            println!("resynced {}", entry_ref);
+            return Ok(());
+        }
+        println!("{}", next_otp(&mut vault, &entry_ref, &pass)?);
+        Ok(())
+    })())
+}
+
+fn otp_seed<'a>(
+    vault: &'a mut Vault,
+    entry_ref: &EntryRef,
+) -> Result<&'a mut otp::OtpSeed, PassrusError> {
+    vault
+        .root
+        .resolve_mut(entry_ref)?
+        .otp
+        .as_mut()
+        .ok_or_else(|| PassrusError::InvalidOtp(format!("{} has no otp seed", entry_ref)))
+}
+
+/// the entry's current one time password. HOTP counters advance with every code, so the vault is saved afterwards.
+pub(super) fn next_otp(
+    vault: &mut Vault,
+    entry_ref: &EntryRef,
+    pass: &str,
+) -> Result<String, PassrusError> {
+    let now = unix_now();
+    let seed = otp_seed(vault, entry_ref)?;
+    let counter_based = matches!(seed.kind, otp::OtpKind::Hotp { .. });
+    let code = seed.generate(now)?;
+    if counter_based {
+        vault.save(pass)?;
+    }
+    Ok(code)
+}
+
+/// `recovery-codes <vault> <entry>`: read codes from stdin, separated by whitespace, and store them at entry.
+/// an existing recovery codes entry has its codes replaced, e.g. after regenerating them on the site.
+pub(super) fn recovery_codes_cmd(args: &[String]) -> i32 {
+    let ([path, entry], pass) = match unlock(args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    report((|| {
+        let input = io::read_to_string(io::stdin())?;
+        let codes = RecoveryCodes::new(input.split_whitespace().map(str::to_owned));
+        let count = codes.codes.len();
+
+        let mut vault = Vault::open(path, &pass)?;
+        let entry_ref = EntryRef::parse(entry)?;
+        let container = vault.root.get_container_mut(&entry_ref.container)?;
+        match container.entries.get_mut(&entry_ref.key) {
+            Some(existing) if matches!(existing.kind, EntryKind::RecoveryCodes(_)) => {
+                existing.set_kind(EntryKind::RecoveryCodes(codes), &pass)?;
+            }
+            Some(existing) => {
+                return Err(PassrusError::WrongEntryKind {
+                    entry: entry_ref.to_string(),
+                    expected: "recovery_codes",
+                    actual: existing.kind.name(),
+                });
+            }
+            None => {
+                let mut new_entry = Entry::new("", Vec::new(), "", &entry_ref.key);
+                new_entry.set_kind(EntryKind::RecoveryCodes(codes), &pass)?;
+                container.add_entry(new_entry);
+            }
+        }
+        vault.save(&pass)?;
+        println!("stored {} recovery codes at {}", count, entry_ref);
+        Ok(())
+    })())
+}
+
+/// `use-recovery-code <vault> <entry>`: print the next unused code, mark it used and save. warns when few are left.
+pub(super) fn use_recovery_code_cmd(args: &[String]) -> i32 {
+    let ([path, entry], pass) = match unlock(args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    report((|| {
+        let mut vault = Vault::open(path, &pass)?;
+        let entry_ref = EntryRef::parse(entry)?;
+        let entry = vault.root.resolve_mut(&entry_ref)?;
+        let mut opened = with_fields(entry, &pass)?;
+        let codes = match &mut opened.kind {
+            EntryKind::RecoveryCodes(codes) => codes,
+            other => {
+                return Err(PassrusError::WrongEntryKind {
+                    entry: entry_ref.to_string(),
+                    expected: "recovery_codes",
+                    actual: other.name(),
+                })
+            }
+        };
+
+        let now = unix_now();
+        let code = codes
+            .use_next(now)
+            .ok_or_else(|| PassrusError::RecoveryCodesExhausted(entry_ref.to_string()))?;
+        let remaining = codes.remaining();
+        // sealed again with the code marked used.
+        entry.set_kind(opened.kind, &pass)?;
+        vault.save(&pass)?;
+
+        println!("{}", code);
+        if remaining <= RECOVERY_CODES_LOW {
+            eprintln!(
+                "warning: {} recovery codes left for {}, generate new ones soon",
+                remaining, entry_ref
+            );
+        }
+        Ok(())
+    })())
+}
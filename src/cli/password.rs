@@ -0,0 +1,229 @@
+//! password quality and rotation.
+use super::{if_revision, plaintext_password, report, unix_now, unlock, usage};
+use passrus_core::{
+    passphrase, policy, revision,
+    rotation::{self, Rotation},
+    Container, Entry, EntryRef, PassrusError, Vault,
+};
+use std::{
+    env, fs,
+    io::{self, BufRead},
+};
+
+/// `audit <vault>`: print every entry whose password breaks its container's policy or is guessable from the entry.
+/// exits 1 if anything was found.
+pub(super) fn audit(args: &[String]) -> i32 {
+    let ([path], pass) = match unlock(args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    let vault = match Vault::open(path, &pass) {
+        Ok(vault) => vault,
+        Err(err) => return report(Err(err)),
+    };
+    let mut flagged = 0;
+    for (container, entry) in vault.root.iter_entries() {
+        if !entry.kind.is_login() {
+            continue;
+        }
+        let plaintext = match plaintext_password(entry, &pass) {
+            Ok(plaintext) => String::from_utf8_lossy(&plaintext).into_owned(),
+            Err(err) => return report(Err(err)),
+        };
+        let entry_ref = match vault.root.entry_ref(&container, entry) {
+            Ok(entry_ref) => entry_ref,
+            Err(err) => return report(Err(err)),
+        };
+        let mut findings = match vault.root.policy_for(&entry_ref.container) {
+            Some(policy) => policy.check(&plaintext, &entry.username),
+            None => Vec::new(),
+        };
+        for warning in policy::guessable(entry, &plaintext) {
+            if !findings.contains(&warning) {
+                findings.push(warning);
+            }
+        }
+        if !findings.is_empty() {
+            flagged += 1;
+            println!("{}: {}", entry_ref, policy::describe(&findings));
+        }
+    }
+    if flagged > 0 {
+        println!("{} entries flagged", flagged);
+        1
+    } else {
+        println!("no weak passwords found");
+        0
+    }
+}
+
+/// `passphrase --wordlist <file> [--words <n>] [--separator <s>]`: print a passphrase of n random words from the
+/// wordlist, see passrus_core::passphrase. its strength goes to stderr.
+pub(super) fn passphrase_cmd(args: &[String]) -> i32 {
+    if !args.len().is_multiple_of(2) {
+        return usage();
+    }
+    let mut wordlist = None;
+    let mut words = passphrase::DEFAULT_WORDS;
+    let mut separator = "-".to_owned();
+    for pair in args.chunks(2) {
+        match pair[0].as_str() {
+            "--wordlist" => wordlist = Some(&pair[1]),
+            "--words" => match pair[1].parse() {
+                Ok(n) if n > 0 => words = n,
+                _ => {
+                    eprintln!("--words takes a positive number");
+                    return 2;
+                }
+            },
+            "--separator" => separator = pair[1].clone(),
+            _ => return usage(),
+        }
+    }
+    let wordlist = match wordlist {
+        Some(wordlist) => wordlist,
+        None => {
+            eprintln!("--wordlist is required, e.g. the EFF large wordlist");
+            return 2;
+        }
+    };
+
+    report((|| {
+        let list = passphrase::Wordlist::parse(&fs::read_to_string(wordlist)?)?;
+        println!("{}", list.generate(words, &separator));
+        eprintln!(
+            "{} words from a list of {}, {:.0} bits",
+            words,
+            list.len(),
+            list.bits_per_word() * words as f64
+        );
+        Ok(())
+    })())
+}
+
+/// `rotate-every <vault> <entry> <days>|month|quarter|year|off`: remind to change the entry's password every days
+/// days counting from now, or on the first day of every month, quarter or year, or stop reminding.
+pub(super) fn rotate_every_cmd(args: &[String]) -> i32 {
+    let (args, expected) = match if_revision(args) {
+        Some(split) => split,
+        None => return usage(),
+    };
+    let ([path, entry, schedule], pass) = match unlock(&args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    report((|| {
+        let rotation = match schedule.as_str() {
+            "off" => None,
+            schedule => Some(Rotation::parse(schedule, unix_now())?),
+        };
+        let mut vault = Vault::open(path, &pass)?;
+        let entry_ref = EntryRef::parse(entry)?;
+        let entry = vault.root.resolve_mut(&entry_ref)?;
+        revision::check(&entry_ref, expected, entry.revision)?;
+        entry.rotation = rotation;
+        vault.save(&pass)?;
+        Ok(())
+    })())
+}
+
+/// `rotate <vault> <entry>`: read the entry's new password from the first line of stdin, check it against the
+/// container's policy like a new entry, and store it. a rotation reminder starts over.
+pub(super) fn rotate_cmd(args: &[String]) -> i32 {
+    let (args, expected) = match if_revision(args) {
+        Some(split) => split,
+        None => return usage(),
+    };
+    let ([path, entry], pass) = match unlock(&args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    report((|| {
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line)?;
+        let plaintext = line.trim_end_matches(['\r', '\n']);
+        if plaintext.is_empty() {
+            return Err(PassrusError::InvalidEntry(
+                "no new password on stdin".to_owned(),
+            ));
+        }
+
+        let mut vault = Vault::open(path, &pass)?;
+        let entry_ref = EntryRef::parse(entry)?;
+        let entry = vault.root.resolve(&entry_ref)?;
+        revision::check(&entry_ref, expected, entry.revision)?;
+        if plaintext_password(entry, &pass)? == plaintext.as_bytes() {
+            return Err(PassrusError::InvalidEntry(
+                "the new password is the current one".to_owned(),
+            ));
+        }
+        let violations = check_new_password(&vault.root, &entry_ref, entry, plaintext)?;
+
+        vault.root.resolve_mut(&entry_ref)?.set_password(
+            plaintext.as_bytes(),
+            &pass,
+            unix_now(),
+        )?;
+        vault.save(&pass)?;
+        for warning in violations {
+            eprintln!("warning: password policy: {}", warning);
+        }
+        println!("changed the password of {}", entry_ref);
+        Ok(())
+    })())
+}
+
+/// `due <vault>`: list the entries due for a password change, most overdue first. exits 1 if there are any.
+pub(super) fn due_cmd(args: &[String]) -> i32 {
+    let ([path], pass) = match unlock(args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    let vault = match Vault::open(path, &pass) {
+        Ok(vault) => vault,
+        Err(err) => return report(Err(err)),
+    };
+    let due = match rotation::due(&vault.root, unix_now()) {
+        Ok(due) => due,
+        Err(err) => return report(Err(err)),
+    };
+    for (entry_ref, overdue) in &due {
+        match overdue {
+            0 => println!("{}: due today", entry_ref),
+            days => println!("{}: {} days overdue", entry_ref, days),
+        }
+    }
+    if due.is_empty() {
+        println!("no passwords due for rotation");
+        0
+    } else {
+        1
+    }
+}
+
+/// check a new password for entry (at entry_ref) against its container's policy like a new entry's. fails on violations
+/// of an enforced policy unless PASSRUS_ALLOW_WEAK is set, otherwise returns them with the guessability warnings.
+pub(super) fn check_new_password(
+    root: &Container,
+    entry_ref: &EntryRef,
+    entry: &Entry,
+    plaintext: &str,
+) -> Result<Vec<policy::Violation>, PassrusError> {
+    let (mut violations, enforce) = match root.policy_for(&entry_ref.container) {
+        Some(policy) => (policy.check(plaintext, &entry.username), policy.enforce),
+        None => (Vec::new(), false),
+    };
+    if enforce && !violations.is_empty() && env::var_os("PASSRUS_ALLOW_WEAK").is_none() {
+        return Err(PassrusError::PolicyViolation(violations));
+    }
+    for warning in policy::guessable(entry, plaintext) {
+        if !violations.contains(&warning) {
+            violations.push(warning);
+        }
+    }
+    Ok(violations)
+}
@@ -0,0 +1,286 @@
+//! creating, checking and converting vault files.
+use super::{report, required_password, unix_now, unlock, usage};
+use crate::doctor::{self, Severity};
+use crate::paper;
+use passrus_core::{format, paper as frames, policy, Layout, PassrusError, Vault};
+use std::{
+    env, fs,
+    io::{self, BufRead, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+};
+use tracing::error;
+
+/// `init [vault]`: create a new, empty vault file. the path is asked for if not given, the master password is taken
+/// from PASSRUS_PASSWORD or else asked for twice on the terminal. it is rated with policy::estimate_bits and refused
+/// below policy::MIN_MASTER_BITS unless PASSRUS_ALLOW_WEAK is set. that catches common passwords and their variations
+/// but otherwise only counts characters, so it is reported as an upper bound. nothing else is set up: there is no config file, backup directory or auto-lock to configure,
+/// and format v0 has no room for Argon2 parameters, so they aren't calibrated.
+pub(super) fn init_cmd(args: &[String]) -> i32 {
+    let path = match args {
+        [path] => Some(path.clone()),
+        [] => None,
+        _ => return usage(),
+    };
+
+    report((|| {
+        let path = match path {
+            Some(path) => path,
+            None => {
+                eprint!("vault file [vault.bin]: ");
+                let mut line = String::new();
+                io::stdin().lock().read_line(&mut line)?;
+                match line.trim() {
+                    "" => "vault.bin".to_owned(),
+                    path => path.to_owned(),
+                }
+            }
+        };
+        if Path::new(&path).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", path),
+            )
+            .into());
+        }
+        let pass = match env::var("PASSRUS_PASSWORD") {
+            Ok(pass) => pass,
+            Err(_) => {
+                let pass = rpassword::prompt_password("master password: ")?;
+                if rpassword::prompt_password("again: ")? != pass {
+                    return Err(PassrusError::InvalidEntry(
+                        "the passwords don't match".to_owned(),
+                    ));
+                }
+                pass
+            }
+        };
+
+        let bits = policy::estimate_bits(&pass) as u32;
+        let rating = match bits {
+            bits if bits < policy::MIN_MASTER_BITS => "weak",
+            bits if bits < policy::GOOD_MASTER_BITS => "fair",
+            _ => "good",
+        };
+        eprintln!(
+            "master password strength: at most {} bits ({}), counting characters and common passwords only; less if it is made of words or names",
+            bits, rating
+        );
+        if bits < policy::MIN_MASTER_BITS && env::var_os("PASSRUS_ALLOW_WEAK").is_none() {
+            return Err(PassrusError::WeakPassword {
+                bits,
+                min: policy::MIN_MASTER_BITS,
+            });
+        }
+
+        // created up front so it is never readable by others, the save keeps its permissions.
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)?;
+        let mut vault = Vault::new(&path, "");
+        if let Err(err) = vault.save(&pass) {
+            let _ = fs::remove_file(&path);
+            return Err(err);
+        }
+        println!("created {}", path);
+        if env::var_os("PASSRUS_PASSWORD").is_none() {
+            eprintln!("other commands read the master password from PASSRUS_PASSWORD");
+        }
+        Ok(())
+    })())
+}
+
+/// `fsck --spec <file>`: validate a file against passrus_core::format.
+/// without PASSRUS_PASSWORD only the layout is checked.
+pub(super) fn fsck(args: &[String]) -> i32 {
+    let path = match args {
+        [flag, path] if flag == "--spec" => path,
+        _ => return usage(),
+    };
+
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(err) => {
+            error!(target:"fsck", "could not read {}: {}", path, err);
+            return 1;
+        }
+    };
+
+    let pass = env::var("PASSRUS_PASSWORD").ok();
+    match format::validate(&data, pass.as_deref()) {
+        Ok(()) => {
+            let depth = if pass.is_some() {
+                "layout and content"
+            } else {
+                "layout only"
+            };
+            println!("{}: ok ({})", path, depth);
+            0
+        }
+        Err(err) => {
+            println!("{}: {} ({})", path, err, err.code());
+            1
+        }
+    }
+}
+
+/// signature shared by vaultfile::encrypt_file and vaultfile::decrypt_file.
+type FileOp = fn(&str, &str, &str, &mut dyn FnMut(u64, u64)) -> Result<(), PassrusError>;
+
+/// `encrypt-file`/`decrypt-file <src> <dst>`: run op with the password from PASSRUS_PASSWORD, drawing progress on stderr.
+pub(super) fn crypt_file(args: &[String], op: FileOp) -> i32 {
+    let ([src, dst], pass) = match unlock(args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    let mut progress = |done: u64, total: u64| {
+        let percent = (done * 100).checked_div(total).unwrap_or(100);
+        eprint!("\r{}: {}%", src, percent);
+    };
+    let res = op(src, dst, &pass, &mut progress);
+    eprintln!();
+    report(res)
+}
+
+/// `doctor <vault>...`: print the findings of doctor::check, exiting 1 if any is a warning.
+/// contents are only checked when PASSRUS_PASSWORD is set.
+pub(super) fn doctor_cmd(args: &[String]) -> i32 {
+    if args.is_empty() {
+        return usage();
+    }
+    let vaults: Vec<PathBuf> = args.iter().map(PathBuf::from).collect();
+    let pass = env::var("PASSRUS_PASSWORD").ok();
+
+    let findings = doctor::check(&vaults, pass.as_deref());
+    for finding in &findings {
+        println!("{}", finding);
+    }
+    let warnings = findings
+        .iter()
+        .filter(|f| f.severity == Severity::Warn)
+        .count();
+    if warnings > 0 {
+        println!("{} problems found", warnings);
+        1
+    } else {
+        println!("no problems found");
+        0
+    }
+}
+
+/// `paper-export <vault> <out.svg>`: render the vault file as it is on disk, still encrypted, as QR codes.
+/// the password isn't needed, but the file's layout is checked so a damaged vault isn't printed.
+pub(super) fn paper_export(args: &[String]) -> i32 {
+    let (path, out) = match args {
+        [path, out] => (path, out),
+        _ => return usage(),
+    };
+
+    report((|| {
+        let data = fs::read(path)?;
+        format::validate(&data, None)?;
+        let frames = frames::split(&data);
+        let title = format!(
+            "passrus paper backup of {}, {} codes. needs the master password to open.",
+            Path::new(path)
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy(),
+            frames.len()
+        );
+        let svg = paper::render_svg(&frames, &title)
+            .map_err(|err| io::Error::other(format!("could not encode QR code: {}", err)))?;
+        fs::write(out, svg)?;
+        println!("wrote {} codes to {}", frames.len(), out);
+        Ok(())
+    })())
+}
+
+/// `paper-import <vault>`: read scanned frames from stdin and write the reassembled vault file. vault must not exist yet.
+pub(super) fn paper_import(args: &[String]) -> i32 {
+    let path = match args {
+        [path] => path,
+        _ => return usage(),
+    };
+
+    report((|| {
+        let lines = io::stdin()
+            .lock()
+            .lines()
+            .collect::<io::Result<Vec<String>>>()?;
+        let data = frames::join(lines.iter().map(String::as_str))?;
+        format::validate(&data, env::var("PASSRUS_PASSWORD").ok().as_deref())?;
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)?
+            .write_all(&data)?;
+        println!("restored {} bytes to {}", data.len(), path);
+        Ok(())
+    })())
+}
+
+/// `shard <vault> <dir>` and `unshard <dir> <file>`: write the vault at path to new_path in layout, see
+/// passrus_core::shard. the original is left in place.
+pub(super) fn reshape_cmd(args: &[String], layout: Layout) -> i32 {
+    let ([path, new_path], pass) = match unlock(args) {
+        Some(unlocked) => unlocked,
+        None => return 2,
+    };
+
+    report((|| {
+        let mut vault = Vault::open(path, &pass)?;
+        vault.save_as(new_path, layout, &pass)?;
+        println!(
+            "wrote {} to {}, remove the original once you've checked the copy",
+            path, new_path
+        );
+        Ok(())
+    })())
+}
+
+/// `refresh-crypto <vault> [--max-age <days>]`: re-encrypt the vault and every entry password with fresh salts and
+/// nonces, see Vault::refresh_crypto. with --max-age nothing happens unless the last refresh is older, for running it
+/// from cron or a systemd timer.
+pub(super) fn refresh_crypto_cmd(args: &[String]) -> i32 {
+    let (path, max_age) = match args {
+        [path] => (path, None),
+        [path, flag, days] if flag == "--max-age" => {
+            match days
+                .parse::<u64>()
+                .ok()
+                .and_then(|days| days.checked_mul(24 * 60 * 60))
+            {
+                Some(max_age) => (path, Some(max_age)),
+                None => return usage(),
+            }
+        }
+        _ => return usage(),
+    };
+    let pass = match required_password() {
+        Some(pass) => pass,
+        None => return 2,
+    };
+
+    report((|| {
+        let mut vault = Vault::open(path, &pass)?;
+        let now = unix_now();
+        let age = vault.root.refreshed_at.map(|at| now.saturating_sub(at));
+        if let (Some(max_age), Some(age)) = (max_age, age) {
+            if age < max_age {
+                println!(
+                    "{} was re-encrypted {} days ago, not due yet",
+                    path,
+                    age / 86400
+                );
+                return Ok(());
+            }
+        }
+        vault.refresh_crypto(&pass, now)?;
+        println!("re-encrypted {}", path);
+        Ok(())
+    })())
+}
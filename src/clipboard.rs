@@ -0,0 +1,106 @@
+use std::{
+    env, io,
+    io::Write,
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod wayland;
+
+/// the clipboard tools tried in order, as (program, args). the first one that starts is used. with primary the text
+/// goes to the primary selection (middle click paste), with once it is gone after the first paste where the tool
+/// supports that.
+fn tools(primary: bool, once: bool) -> Vec<(&'static str, Vec<&'static str>)> {
+    if cfg!(target_os = "macos") {
+        return if primary {
+            Vec::new()
+        } else {
+            vec![("pbcopy", Vec::new())]
+        };
+    }
+    let mut tools = Vec::new();
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut args = Vec::new();
+        if primary {
+            args.push("--primary");
+        }
+        if once {
+            args.push("--paste-once");
+        }
+        tools.push(("wl-copy", args));
+    }
+    let mut args = vec!["-selection", if primary { "primary" } else { "clipboard" }];
+    if once {
+        args.extend(["-loops", "1"]);
+    }
+    tools.push(("xclip", args));
+    if !once {
+        tools.push((
+            "xsel",
+            vec![if primary { "--primary" } else { "--clipboard" }, "--input"],
+        ));
+    }
+    tools
+}
+
+/// put text on the clipboard (or the primary selection) and take it off again after timeout, never with a zero
+/// timeout. with once it is also taken off after the first paste; a clipboard manager reading every new selection
+/// counts as that paste. copied is called once the text is on the clipboard, copy then blocks until it is off again.
+///
+/// on wayland compositors with the wlr data control protocol passrus serves the clipboard itself, see wayland.
+/// elsewhere, or to keep the text there after passrus exits, it pipes the text to pbcopy, wl-copy, xclip or xsel,
+/// which keep serving it, and clears by copying nothing.
+pub fn copy(
+    text: &[u8],
+    primary: bool,
+    timeout: Duration,
+    once: bool,
+    copied: &dyn Fn(),
+) -> io::Result<()> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if !timeout.is_zero() {
+        if let Some(served) = wayland::serve(text, primary, timeout, once, copied) {
+            return served;
+        }
+    }
+    pipe(text, primary, once)?;
+    copied();
+    if timeout.is_zero() {
+        return Ok(());
+    }
+    thread::sleep(timeout);
+    pipe(b"", primary, false)
+}
+
+fn pipe(text: &[u8], primary: bool, once: bool) -> io::Result<()> {
+    for (program, args) in tools(primary, once) {
+        let mut child = match Command::new(program)
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text)?;
+        }
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "{} exited with {}",
+                program, status
+            )));
+        }
+        return Ok(());
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "no clipboard tool found, install wl-copy, xclip or xsel",
+    ))
+}
@@ -0,0 +1,229 @@
+//! serving the clipboard natively on wayland compositors with the wlr data control protocol (sway, hyprland, river,
+//! kde and other wlroots based ones), instead of through wl-copy. passrus offers the secret itself for as long as it
+//! should be available, and withdraws it afterwards: a real clear, not a copy of nothing.
+use std::{
+    fs::File,
+    io::{self, Write},
+    os::fd::AsRawFd,
+    time::{Duration, Instant},
+};
+use tracing::info;
+use wayland_client::{
+    event_created_child,
+    globals::{registry_queue_init, GlobalListContents},
+    protocol::{wl_registry, wl_seat::WlSeat},
+    Connection, Dispatch, QueueHandle,
+};
+use wayland_protocols_wlr::data_control::v1::client::{
+    zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+    zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+    zwlr_data_control_offer_v1::ZwlrDataControlOfferV1,
+    zwlr_data_control_source_v1::{self, ZwlrDataControlSourceV1},
+};
+
+/// the mime types the secret is offered as, plain text in the names toolkits ask for.
+const MIME_TYPES: [&str; 4] = [
+    "text/plain;charset=utf-8",
+    "text/plain",
+    "UTF8_STRING",
+    "STRING",
+];
+
+struct State {
+    text: Vec<u8>,
+    once: bool,
+    /// someone else took over the selection, or the paste allowed by once happened.
+    done: bool,
+    /// the selection is no longer ours to clear.
+    replaced: bool,
+}
+
+/// offer text as the clipboard (or primary selection) until timeout passes, it has been pasted once with once, or
+/// something else is copied, then withdraw it. copied is called once the text is offered. None if there is no wayland
+/// session or the compositor lacks the protocol (primary needs version 2), so the caller can fall back to the
+/// clipboard tools.
+pub fn serve(
+    text: &[u8],
+    primary: bool,
+    timeout: Duration,
+    once: bool,
+    copied: &dyn Fn(),
+) -> Option<io::Result<()>> {
+    let conn = Connection::connect_to_env().ok()?;
+    let (globals, mut queue) = registry_queue_init::<State>(&conn).ok()?;
+    let qh = queue.handle();
+    let min_version = if primary { 2 } else { 1 };
+    let manager: ZwlrDataControlManagerV1 = globals.bind(&qh, min_version..=2, ()).ok()?;
+    let seat: WlSeat = globals.bind(&qh, 1..=1, ()).ok()?;
+    info!(target:"clipboard", "serving the {} through wlr data control", if primary { "primary selection" } else { "clipboard" });
+
+    let device = manager.get_data_device(&seat, &qh, ());
+    let source = manager.create_data_source(&qh, ());
+    for mime_type in MIME_TYPES {
+        source.offer(mime_type.to_owned());
+    }
+    let set = |source: Option<&ZwlrDataControlSourceV1>| {
+        if primary {
+            device.set_primary_selection(source);
+        } else {
+            device.set_selection(source);
+        }
+    };
+    set(Some(&source));
+
+    let mut state = State {
+        text: text.to_vec(),
+        once,
+        done: false,
+        replaced: false,
+    };
+    let served = (|| {
+        conn.flush().map_err(io::Error::other)?;
+        copied();
+        let deadline = Instant::now() + timeout;
+        while !state.done {
+            conn.flush().map_err(io::Error::other)?;
+            queue
+                .dispatch_pending(&mut state)
+                .map_err(io::Error::other)?;
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if state.done || remaining.is_zero() {
+                break;
+            }
+            let guard = match queue.prepare_read() {
+                Some(guard) => guard,
+                None => continue,
+            };
+            let mut poll_fd = libc::pollfd {
+                fd: guard.connection_fd().as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let millis = remaining.as_millis().min(i32::MAX as u128) as i32;
+            // SAFETY: poll reads and writes the single pollfd passed by pointer.
+            let ready = unsafe { libc::poll(&mut poll_fd, 1, millis) };
+            if ready < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            } else if ready > 0 {
+                guard.read().map_err(io::Error::other)?;
+            }
+        }
+        Ok(())
+    })();
+
+    if !state.replaced {
+        set(None);
+    }
+    source.destroy();
+    let withdrawn = queue
+        .roundtrip(&mut state)
+        .map(|_| ())
+        .map_err(io::Error::other);
+    Some(served.and(withdrawn))
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for State {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlSeat, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &WlSeat,
+        _: <WlSeat as wayland_client::Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrDataControlManagerV1, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &ZwlrDataControlManagerV1,
+        _: <ZwlrDataControlManagerV1 as wayland_client::Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrDataControlDeviceV1,
+        event: zwlr_data_control_device_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            // offers of what others copy, including the initial one, aren't read.
+            zwlr_data_control_device_v1::Event::Selection { id: Some(offer) }
+            | zwlr_data_control_device_v1::Event::PrimarySelection { id: Some(offer) } => {
+                offer.destroy()
+            }
+            zwlr_data_control_device_v1::Event::Finished => {
+                state.done = true;
+                state.replaced = true;
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(State, ZwlrDataControlDeviceV1, [
+        zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrDataControlOfferV1, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &ZwlrDataControlOfferV1,
+        _: <ZwlrDataControlOfferV1 as wayland_client::Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrDataControlSourceV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrDataControlSourceV1,
+        event: zwlr_data_control_source_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_data_control_source_v1::Event::Send { fd, .. } => {
+                let written = File::from(fd).write_all(&state.text);
+                info!(target:"clipboard", "selection pasted");
+                if written.is_ok() && state.once {
+                    state.done = true;
+                }
+            }
+            zwlr_data_control_source_v1::Event::Cancelled => {
+                info!(target:"clipboard", "selection replaced");
+                state.done = true;
+                state.replaced = true;
+            }
+            _ => {}
+        }
+    }
+}
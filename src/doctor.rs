@@ -0,0 +1,359 @@
+use passrus_core::{cryptman, format, schema, Container};
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// files bigger than this aren't searched for plaintext exports.
+const EXPORT_SCAN_LIMIT: u64 = 16 * 1024 * 1024;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Ok,
+    Info,
+    Warn,
+}
+
+/// one result of a check, with what to do about it when it's a warning.
+pub struct Finding {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.severity {
+            Severity::Ok => "ok",
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+        };
+        write!(f, "{:>4}: {}", label, self.message)
+    }
+}
+
+fn finding(severity: Severity, message: String) -> Finding {
+    Finding { severity, message }
+}
+
+/// check the given vault files and their surroundings. with pass the contents are decrypted too.
+pub fn check(vaults: &[PathBuf], pass: Option<&str>) -> Vec<Finding> {
+    let mut findings = vec![check_process(), check_kdf()];
+    // nonce and salt -> the first file seen with them and its ciphertext.
+    let mut trailers: HashMap<Vec<u8>, (PathBuf, Vec<u8>)> = HashMap::new();
+
+    for path in vaults {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(err) => {
+                findings.push(finding(
+                    Severity::Warn,
+                    format!("{}: could not read: {}", path.display(), err),
+                ));
+                continue;
+            }
+        };
+        findings.extend(check_permissions(path));
+        findings.extend(check_contents(path, &data, pass));
+
+        if let Ok(parsed) = format::parse(&data) {
+            let key = [parsed.nonce.as_slice(), parsed.salt.as_slice()].concat();
+            match trailers.get(&key) {
+                // identical copies are harmless, different ciphertexts under one key and nonce leak their xor.
+                Some((other, ciphertext)) if *ciphertext != parsed.ciphertext => {
+                    findings.push(finding(
+                        Severity::Warn,
+                        format!(
+                            "{} and {} were encrypted with the same nonce and salt. re-save one of them",
+                            path.display(),
+                            other.display()
+                        ),
+                    ))
+                }
+                Some(_) => {}
+                None => {
+                    trailers.insert(key, (path.clone(), parsed.ciphertext.to_vec()));
+                }
+            }
+        }
+    }
+
+    let mut dirs: Vec<&Path> = vaults.iter().map(|path| parent_dir(path)).collect();
+    dirs.dedup();
+    for dir in dirs {
+        findings.extend(check_exports(dir, vaults));
+    }
+    findings
+}
+
+/// RLIMIT_CORE as set by harden::apply.
+fn check_process() -> Finding {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: getrlimit writes into the struct passed by pointer.
+    let ok = unsafe { libc::getrlimit(libc::RLIMIT_CORE, &mut limit) } == 0;
+    if ok && limit.rlim_cur == 0 {
+        finding(
+            Severity::Ok,
+            "core dumps are disabled for passrus".to_owned(),
+        )
+    } else {
+        finding(
+            Severity::Warn,
+            "core dumps are enabled, a crash could write decrypted secrets to disk".to_owned(),
+        )
+    }
+}
+
+/// the parameters are fixed by format v0 (see passrus_core::format) rather than stored per file.
+fn check_kdf() -> Finding {
+    finding(
+        Severity::Info,
+        "key derivation is Argon2id m=19456 KiB t=2 p=1, the OWASP minimum. \
+         format v0 can't record stronger parameters, so a long master password matters"
+            .to_owned(),
+    )
+}
+
+fn check_permissions(path: &Path) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    if let Ok(meta) = fs::metadata(path) {
+        let mode = meta.permissions().mode() & 0o777;
+        if mode & 0o077 != 0 {
+            findings.push(finding(
+                Severity::Warn,
+                format!(
+                    "{} is accessible by other users (mode {:o}). run chmod 600 {}",
+                    path.display(),
+                    mode,
+                    path.display()
+                ),
+            ));
+        } else {
+            findings.push(finding(
+                Severity::Ok,
+                format!("{} is only accessible by its owner", path.display()),
+            ));
+        }
+    }
+    let dir = parent_dir(path);
+    if let Ok(meta) = fs::metadata(dir) {
+        let mode = meta.permissions().mode();
+        // a sticky, world writable dir like /tmp still lets others create files next to the vault.
+        if mode & 0o022 != 0 {
+            findings.push(finding(
+                Severity::Warn,
+                format!(
+                    "{} is writable by other users, who could replace the vault. move it to a private directory",
+                    dir.display()
+                ),
+            ));
+        }
+    }
+    findings
+}
+
+fn check_contents(path: &Path, data: &[u8], pass: Option<&str>) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let parsed = match format::parse(data) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            findings.push(finding(
+                Severity::Warn,
+                format!("{}: {} ({})", path.display(), err, err.code()),
+            ));
+            return findings;
+        }
+    };
+    if parsed.salt.iter().all(|b| *b == 0) || parsed.nonce.iter().all(|b| *b == 0) {
+        findings.push(finding(
+            Severity::Warn,
+            format!(
+                "{} has an all zero salt or nonce, it was not written with random values. re-save it",
+                path.display()
+            ),
+        ));
+    }
+
+    let pass = match pass {
+        Some(pass) => pass,
+        None => {
+            findings.push(finding(
+                Severity::Info,
+                format!(
+                    "{}: layout is valid format v0. set PASSRUS_PASSWORD to check the contents",
+                    path.display()
+                ),
+            ));
+            return findings;
+        }
+    };
+    let json = match cryptman::decrypt_file_mem_gen_key(data.to_vec(), "", pass) {
+        Ok(json) => json,
+        Err(err) => {
+            findings.push(finding(
+                Severity::Warn,
+                format!("{}: {} ({})", path.display(), err, err.code()),
+            ));
+            return findings;
+        }
+    };
+    let mut root = Container::new("");
+    if root.from_json_arr(&json).is_ok() {
+        findings.extend(check_entry_nonces(path, &parsed, &root));
+    }
+    match serde_json::from_slice::<serde_json::Value>(&json) {
+        Ok(value) => {
+            let version = schema::version_of(&value);
+            let (severity, note) = if version < schema::CURRENT_SCHEMA_VERSION {
+                (
+                    Severity::Info,
+                    ", it is migrated the next time it is saved. run passrus refresh-crypto to do it now",
+                )
+            } else {
+                (Severity::Ok, "")
+            };
+            findings.push(finding(
+                severity,
+                format!(
+                    "{}: format v0, schema version {} (current {}){}",
+                    path.display(),
+                    version,
+                    schema::CURRENT_SCHEMA_VERSION,
+                    note
+                ),
+            ));
+            let refreshed = match value
+                .get("refreshed_at")
+                .and_then(serde_json::Value::as_u64)
+            {
+                Some(at) => format!(
+                    "entry passwords were last re-encrypted {} days ago",
+                    unix_now().saturating_sub(at) / 86400
+                ),
+                None => "entry passwords have never been re-encrypted. run passrus refresh-crypto"
+                    .to_owned(),
+            };
+            findings.push(finding(
+                Severity::Info,
+                format!("{}: {}", path.display(), refreshed),
+            ));
+        }
+        Err(err) => findings.push(finding(
+            Severity::Warn,
+            format!(
+                "{}: decrypted contents are not valid JSON: {}",
+                path.display(),
+                err
+            ),
+        )),
+    }
+    findings
+}
+
+/// entry passwords encrypted under the same nonce and salt as another entry, or as the vault itself. vaults written by
+/// older tools or hand rolled importers sometimes reused one nonce for every entry, which leaks the xor of the
+/// passwords. also entry passwords still encrypted with the vault password's key instead of one of their own.
+/// refresh-crypto re-encrypts each under its own key and nonce.
+fn check_entry_nonces(
+    path: &Path,
+    vault: &format::EncryptedFile,
+    root: &Container,
+) -> Vec<Finding> {
+    let mut seen: HashMap<Vec<u8>, usize> = HashMap::new();
+    seen.insert([vault.nonce.as_slice(), vault.salt.as_slice()].concat(), 1);
+    let mut vault_keyed = 0;
+    for (_, entry) in root.iter_entries() {
+        // entries with a key of their own can't share one with anything, whatever their nonce.
+        if entry.entry_key {
+            continue;
+        }
+        if let Ok(parsed) = format::parse(&entry.pass_vec) {
+            vault_keyed += 1;
+            *seen
+                .entry([parsed.nonce.as_slice(), parsed.salt.as_slice()].concat())
+                .or_default() += 1;
+        }
+    }
+    let mut findings = Vec::new();
+    let shared: usize = seen.values().filter(|count| **count > 1).sum();
+    if shared > 0 {
+        findings.push(finding(
+            Severity::Warn,
+            format!(
+                "{}: {} entry passwords (or the vault) share a nonce and key, which leaks their xor. run passrus refresh-crypto {}",
+                path.display(),
+                shared,
+                path.display()
+            ),
+        ));
+    }
+    if vault_keyed > 0 {
+        findings.push(finding(
+            Severity::Info,
+            format!(
+                "{}: {} entry passwords are encrypted with the vault password's key rather than their own. run passrus refresh-crypto {}",
+                path.display(),
+                vault_keyed,
+                path.display()
+            ),
+        ));
+    }
+    findings
+}
+
+/// look for unencrypted container or entry JSON in dir, next to the vaults.
+fn check_exports(dir: &Path, vaults: &[PathBuf]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return findings,
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let small_file = entry
+            .metadata()
+            .map(|m| m.is_file() && m.len() <= EXPORT_SCAN_LIMIT)
+            .unwrap_or(false);
+        if !small_file || vaults.iter().any(|vault| same_file(&path, vault)) {
+            continue;
+        }
+        let looks_exported = fs::read(&path)
+            .map(|data| data.windows(10).any(|w| w == b"\"pass_vec\""))
+            .unwrap_or(false);
+        if looks_exported {
+            findings.push(finding(
+                Severity::Warn,
+                format!(
+                    "{} looks like an unencrypted vault export. delete it if it isn't needed",
+                    path.display()
+                ),
+            ));
+        }
+    }
+    findings
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
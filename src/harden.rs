@@ -0,0 +1,27 @@
+use tracing::warn;
+
+/// keep the master password and decrypted vault out of core dumps and away from same-user debuggers:
+/// RLIMIT_CORE is set to 0, and on linux PR_SET_DUMPABLE to 0, which also stops other processes of the same user
+/// from ptracing us or reading /proc/<pid>/mem. called first thing in main, failures are logged and otherwise ignored.
+pub fn apply() {
+    let limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: setrlimit only reads the struct passed by pointer.
+    if unsafe { libc::setrlimit(libc::RLIMIT_CORE, &limit) } != 0 {
+        warn!(target:"harden", "could not disable core dumps: {}", std::io::Error::last_os_error());
+    }
+    set_not_dumpable();
+}
+
+#[cfg(target_os = "linux")]
+fn set_not_dumpable() {
+    // SAFETY: PR_SET_DUMPABLE takes a plain integer argument.
+    if unsafe { libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0) } != 0 {
+        warn!(target:"harden", "could not mark the process non dumpable: {}", std::io::Error::last_os_error());
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_not_dumpable() {}
@@ -1,12 +1,36 @@
-use log:: warn;
+use passrus_core::{cryptman, passman, EntryRef};
 use rand::{rngs::OsRng, RngCore};
 use std::process::exit;
-mod cryptman;
-mod passman;
-
-
+use tracing::{error, info};
+use tracing_subscriber::EnvFilter;
+mod ci;
+mod cli;
+mod clipboard;
+mod doctor;
+mod harden;
+mod paper;
+mod pass_store;
+mod secrets_dir;
+#[cfg(feature = "vault-kv")]
+mod vault_kv;
 
 fn main() {
+    // log filter comes from PASSRUS_LOG (e.g. PASSRUS_LOG=passrus_core=debug), defaulting to info. logs go to stderr so
+    // they never end up in output meant for other programs, such as ci-export's.
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_env("PASSRUS_LOG").unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+    harden::apply();
+
+    // with arguments, run a subcommand. without, run the demo below.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        exit(cli::run(&args));
+    }
+
     // obligatory garbage password
     let pass = "password";
 
@@ -15,8 +39,7 @@ fn main() {
         Ok(res) => res,
 
         Err(error) => {
-            println! {"rip: {error:?}"}
-            warn!(target:"main","error generating key and salt: {error:?}");
+            error!(target:"main","error generating key and salt: {error:?}");
             exit(0);
         }
     };
@@ -75,7 +98,9 @@ fn main() {
         .encrypt_password(key, nonce, salt);
 
     // adding a new container as a child, then adding entries to it
-    sub_container.add_child(passman::Container::new("sub_sub_container"));
+    sub_container
+        .add_child(passman::Container::new("sub_sub_container"))
+        .unwrap();
 
     sub_container
         .children
@@ -91,18 +116,15 @@ fn main() {
     let mut nonce = [0u8; 24];
     OsRng.fill_bytes(&mut nonce);
 
-    // encrypting a password of an entry already in a nested container
+    // encrypting a password of an entry already in a nested container, addressed by path
+    let entry_ref = EntryRef::parse("sub_sub_container/test-site3.com").unwrap();
     let _ = sub_container
-        .children
-        .get_mut("sub_sub_container")
-        .unwrap()
-        .entries
-        .get_mut("test-site3.com")
+        .resolve_mut(&entry_ref)
         .unwrap()
         .encrypt_password(key, nonce, salt);
 
     //adding a container as a child after instantiating it and adding entries to it.
-    parent_container.add_child(sub_container);
+    parent_container.add_child(sub_container).unwrap();
 
     let binding = parent_container.to_json_string();
     let json_arr = binding.as_bytes();
@@ -115,11 +137,11 @@ fn main() {
     let enc_res =
         match cryptman::encrypt_file_mem_with_salt(json_arr.to_vec(), "", &key, &nonce, &salt) {
             Ok(res) => {
-                println!("encrypted with key,salt&nonce successfully");
+                info!(target:"main","encrypted with key,salt&nonce successfully");
                 res
             }
             Err(error) => {
-                println!("rip: {error:?}");
+                error!(target:"main","error encrypting data: {error:?}");
                 exit(0);
             }
         };
@@ -127,12 +149,12 @@ fn main() {
     // decrypt the content, reading it from file.
     let dec_res = match cryptman::decrypt_file_mem_gen_key(enc_res, "", pass) {
         Ok(res) => {
-            println!("grabbed salt&nonce from file, decrypted successfully");
+            info!(target:"main","grabbed salt&nonce from file, decrypted successfully");
             res
         }
 
         Err(error) => {
-            warn!(target:"main","error decrypting data: {error:?}");
+            error!(target:"main","error decrypting data: {error:?}");
             exit(0);
         }
     };
@@ -149,7 +171,7 @@ fn main() {
         let vec = &vec.clone();
 
         let lossy_encrypted = String::from_utf8_lossy(vec.as_slice());
-        entry.pass_vec = cryptman::decrypt_file_mem_gen_key(entry.pass_vec, "", pass).unwrap();
+        entry.decrypt_password(pass).unwrap();
 
         let password = String::from_utf8_lossy(entry.pass_vec.as_slice());
         println!(
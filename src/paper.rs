@@ -0,0 +1,76 @@
+use qrcode::{types::QrError, Color, EcLevel, QrCode};
+use std::fmt::Write;
+
+/// width of the sheet in mm (A4).
+const PAGE_WIDTH: u32 = 210;
+/// codes per row.
+const COLUMNS: u32 = 2;
+/// size of each code's cell in mm, including its label.
+const CELL: u32 = 95;
+const MARGIN: u32 = 10;
+/// light modules around each code, as the QR spec asks for.
+const QUIET_ZONE: usize = 4;
+
+/// lay out one QR code per frame on a printable A4 wide SVG sheet, each labelled with its position.
+/// frames are encoded at medium error correction so a creased or smudged printout still scans.
+pub fn render_svg(frames: &[String], title: &str) -> Result<String, QrError> {
+    let rows = (frames.len() as u32).div_ceil(COLUMNS);
+    let height = 2 * MARGIN + 10 + rows * CELL;
+    let size = (CELL - 10) as f64;
+
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        r##"<svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="{w}mm" height="{h}mm" viewBox="0 0 {w} {h}"><rect width="100%" height="100%" fill="#fff"/>"##,
+        w = PAGE_WIDTH,
+        h = height
+    );
+    let _ = write!(
+        out,
+        r#"<text x="{}" y="{}" font-family="monospace" font-size="5">{}</text>"#,
+        MARGIN,
+        MARGIN + 5,
+        escape(title)
+    );
+
+    for (i, frame) in frames.iter().enumerate() {
+        let code = QrCode::with_error_correction_level(frame.as_bytes(), EcLevel::M)?;
+        let width = code.width();
+        let x = MARGIN + (i as u32 % COLUMNS) * CELL;
+        let y = MARGIN + 10 + (i as u32 / COLUMNS) * CELL;
+        let scale = size / (width + 2 * QUIET_ZONE) as f64;
+
+        let _ = write!(
+            out,
+            r#"<path transform="translate({} {}) scale({:.4})" shape-rendering="crispEdges" d=""#,
+            x, y, scale
+        );
+        for (n, color) in code.to_colors().iter().enumerate() {
+            if *color == Color::Dark {
+                let _ = write!(
+                    out,
+                    "M{} {}h1v1h-1z",
+                    n % width + QUIET_ZONE,
+                    n / width + QUIET_ZONE
+                );
+            }
+        }
+        out.push_str(r#""/>"#);
+        let _ = write!(
+            out,
+            r#"<text x="{}" y="{}" font-family="monospace" font-size="4">{} / {}</text>"#,
+            x + 2,
+            y as f64 + size + 5.0,
+            i + 1,
+            frames.len()
+        );
+    }
+    out.push_str("</svg>\n");
+    Ok(out)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,242 @@
+use crate::secrets_dir;
+use passrus_core::{shred, Container, Entry, PassrusError};
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+use tracing::info;
+
+/// how the files of the store are encrypted, with the recipients to encrypt to.
+/// gpg gives the layout of pass (.gpg files, .gpg-id), age that of passage (.age files, .age-recipients).
+pub enum Encryption {
+    Gpg(Vec<String>),
+    Age(Vec<String>),
+}
+
+impl Encryption {
+    fn extension(&self) -> &'static str {
+        match self {
+            Encryption::Gpg(_) => "gpg",
+            Encryption::Age(_) => "age",
+        }
+    }
+
+    /// the file at the top of the store listing the recipients, one per line.
+    fn recipients_file(&self) -> (&'static str, &[String]) {
+        match self {
+            Encryption::Gpg(ids) => (".gpg-id", ids),
+            Encryption::Age(recipients) => (".age-recipients", recipients),
+        }
+    }
+
+    /// encrypt plaintext into out by piping it through gpg or age, so it never reaches the disk unencrypted.
+    fn encrypt(&self, plaintext: &[u8], out: &Path) -> io::Result<()> {
+        let mut command = match self {
+            Encryption::Gpg(ids) => {
+                let mut command = Command::new("gpg");
+                command.args(["--batch", "--yes", "--quiet", "--encrypt"]);
+                for id in ids {
+                    command.args(["--recipient", id]);
+                }
+                command
+            }
+            Encryption::Age(recipients) => {
+                let mut command = Command::new("age");
+                command.arg("--encrypt");
+                for recipient in recipients {
+                    command.args(["--recipient", recipient]);
+                }
+                command
+            }
+        };
+        let mut child = command
+            .arg("--output")
+            .arg(out)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(plaintext)?;
+        }
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "encrypting {} failed: {}",
+                out.display(),
+                status
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// the plaintext of entry in pass' convention: the password on the first line, then "key: value" lines (login, email,
+/// url, the fields of the entry's kind) which browserpass and passff understand, an otpauth:// line for pass-otp,
+/// and multi line values such as a note's body as free text at the end. a password with a newline in it is refused,
+/// pass would read only its first line.
+pub fn render(entry: &Entry, password: &[u8]) -> Result<Vec<u8>, PassrusError> {
+    if password.contains(&b'\n') {
+        return Err(PassrusError::InvalidEntry(
+            "the password contains a newline, pass would read only the line before it".to_owned(),
+        ));
+    }
+    let mut out = password.to_vec();
+    out.push(b'\n');
+    let mut lines = Vec::new();
+    let mut free_text = Vec::new();
+    if entry.kind.is_login() {
+        for (label, value) in [
+            ("login", &entry.username),
+            ("email", &entry.email),
+            ("url", &entry.url),
+        ] {
+            if !value.is_empty() {
+                lines.push(format!("{}: {}", label, value));
+            }
+        }
+    }
+    for (label, value) in entry.kind.display_fields(true) {
+        if value.contains('\n') {
+            free_text.push(value);
+        } else if !value.is_empty() {
+            lines.push(format!("{}: {}", label, value));
+        }
+    }
+    if let Some(seed) = &entry.otp {
+        lines.push(seed.to_uri());
+    }
+    for line in lines.into_iter().chain(free_text) {
+        out.extend_from_slice(line.as_bytes());
+        out.push(b'\n');
+    }
+    Ok(out)
+}
+
+/// turns an entry into a copy with its protected fields opened, and its plaintext password.
+pub type OpenEntry<'a> = dyn Fn(&Entry) -> Result<(Entry, Vec<u8>), PassrusError> + 'a;
+
+/// write every entry below root into dir as <container path>/<entry key>.gpg (or .age), the layout of a
+/// ~/.password-store, and the recipients file (mode 0600) at its top. dir must not exist yet or be empty, so an
+/// existing store is never mixed with the export. two keys of a container that map to the same file name (see
+/// secrets_dir::file_name) are refused before anything is written, and if writing fails partway what was written is
+/// shredded (see passrus_core::shred). open opens each entry, see OpenEntry. returns the number of entries.
+pub fn export(
+    root: &Container,
+    dir: &Path,
+    encryption: &Encryption,
+    open: &OpenEntry<'_>,
+) -> Result<usize, PassrusError> {
+    if dir.exists() && fs::read_dir(dir)?.next().is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} is not empty", dir.display()),
+        )
+        .into());
+    }
+    let mut files = BTreeMap::new();
+    plan(root, "", dir, encryption.extension(), &mut files)?;
+
+    let existed = dir.exists();
+    create_private_dir(dir)?;
+    let written = write_store(root, dir, encryption, open, &files);
+    if written.is_err() {
+        // dir was empty, everything in it is part of this export.
+        if let Err(err) = discard(dir, existed) {
+            info!(target:"pass_store", "could not remove the partial export: {}", err);
+        }
+    }
+    written.map(|()| files.len())
+}
+
+/// write the recipients file and every planned file of export into dir.
+fn write_store(
+    root: &Container,
+    dir: &Path,
+    encryption: &Encryption,
+    open: &OpenEntry<'_>,
+    files: &BTreeMap<PathBuf, (String, &Entry)>,
+) -> Result<(), PassrusError> {
+    let (recipients_file, recipients) = encryption.recipients_file();
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(dir.join(recipients_file))?
+        .write_all((recipients.join("\n") + "\n").as_bytes())?;
+
+    for (path, (name, entry)) in files {
+        let (entry, password) = open(root.follow(entry)?)?;
+        let rendered = render(&entry, &password).map_err(|err| match err {
+            PassrusError::InvalidEntry(reason) => {
+                PassrusError::InvalidEntry(format!("{}: {}", name, reason))
+            }
+            err => err,
+        })?;
+        if let Some(parent) = path.parent() {
+            create_private_dir(parent)?;
+        }
+        info!(target:"pass_store", "writing {}", path.display());
+        encryption.encrypt(&rendered, path)?;
+    }
+    Ok(())
+}
+
+/// shred every file below dir and remove the directories below it, and dir itself unless keep is set.
+fn discard(dir: &Path, keep: bool) -> Result<(), PassrusError> {
+    for item in fs::read_dir(dir)? {
+        let path = item?.path();
+        if path.is_dir() && !path.is_symlink() {
+            discard(&path, false)?;
+        } else {
+            shred::remove_file(&path)?;
+        }
+    }
+    if !keep {
+        fs::remove_dir(dir)?;
+    }
+    Ok(())
+}
+
+/// map the file each entry below container is exported to to the entry and its path, failing on a collision.
+fn plan<'a>(
+    container: &'a Container,
+    container_path: &str,
+    dir: &Path,
+    extension: &str,
+    files: &mut BTreeMap<PathBuf, (String, &'a Entry)>,
+) -> Result<(), PassrusError> {
+    for (key, entry) in &container.entries {
+        let name = match container_path {
+            "" => key.clone(),
+            _ => format!("{}/{}", container_path, key),
+        };
+        let path = dir.join(format!("{}.{}", secrets_dir::file_name(key), extension));
+        if let Some((other, _)) = files.insert(path.clone(), (name.clone(), entry)) {
+            return Err(PassrusError::InvalidEntry(format!(
+                "{} and {} would both be exported to {}, rename one of them",
+                other,
+                name,
+                path.display()
+            )));
+        }
+    }
+    for (child_name, child) in &container.children {
+        let child_path = match container_path {
+            "" => child_name.clone(),
+            _ => format!("{}/{}", container_path, child_name),
+        };
+        // names read from a vault file aren't validated, file_name keeps one like ".." inside dir.
+        let child_dir = dir.join(secrets_dir::file_name(child_name));
+        plan(child, &child_path, &child_dir, extension, files)?;
+    }
+    Ok(())
+}
+
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+}
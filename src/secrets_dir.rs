@@ -0,0 +1,132 @@
+use passrus_core::{shred, Container, Entry, PassrusError};
+use rand::{rngs::OsRng, RngCore};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::Path,
+};
+use tracing::info;
+
+/// file name for an entry key in a secrets directory: '/' and leading dots are replaced so keys can't escape the directory.
+pub fn file_name(key: &str) -> String {
+    let name = key.replace('/', "_");
+    match name.strip_prefix('.') {
+        Some(rest) => format!("_{}", rest),
+        None if name.is_empty() => "_".to_owned(),
+        None => name,
+    }
+}
+
+/// the file in a secrets directory listing the secrets the last materialize wrote there, as a JSON array of names.
+/// it marks the directory as passrus', and only the names in it are ever removed.
+pub const MANIFEST: &str = ".passrus-secrets";
+
+/// write one file per entry of container into dir (docker secrets layout: file name is the entry key, content the password).
+/// dir is created with mode 0700 and files with 0600, each written to a temp file and renamed into place so readers
+/// never see a half written secret. the previous value of a replaced secret, and secrets written by the previous run
+/// that are gone from the container, are overwritten (see passrus_core::shred); nothing else in dir is touched. a dir that isn't empty and has
+/// no MANIFEST was not written by materialize and is refused, as are two keys that map to the same file name.
+/// password returns the plaintext password of an entry.
+pub fn materialize(
+    container: &Container,
+    dir: &Path,
+    password: &dyn Fn(&Entry) -> Result<Vec<u8>, PassrusError>,
+) -> Result<usize, PassrusError> {
+    let previous = previous_secrets(dir)?;
+    let mut names = HashMap::new();
+    for key in container.entries.keys() {
+        if let Some(other) = names.insert(file_name(key), key) {
+            return Err(PassrusError::InvalidEntry(format!(
+                "{:?} and {:?} would both be written to {}, rename one of them",
+                other,
+                key,
+                file_name(key)
+            )));
+        }
+    }
+    fs::create_dir_all(dir)?;
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+
+    let mut written = BTreeSet::new();
+    for (name, key) in &names {
+        let secret = password(&container.entries[*key])?;
+        write_atomically(&dir.join(name), &secret)?;
+        written.insert(name.clone());
+    }
+    let manifest = serde_json::to_vec(&written).map_err(io::Error::other)?;
+    write_atomically(&dir.join(MANIFEST), &manifest)?;
+
+    for stale in previous.difference(&written) {
+        info!(target:"secrets_dir", "removing stale secret {}", stale);
+        shred::remove_file(dir.join(stale))?;
+    }
+    Ok(written.len())
+}
+
+/// the names listed in dir's MANIFEST. empty if dir doesn't exist or is empty, an error if it holds anything else.
+fn previous_secrets(dir: &Path) -> Result<BTreeSet<String>, PassrusError> {
+    match fs::read(dir.join(MANIFEST)) {
+        Ok(data) => {
+            let names: BTreeSet<String> =
+                serde_json::from_slice(&data).map_err(|err| PassrusError::CorruptFile {
+                    reason: format!("{}: {}", dir.join(MANIFEST).display(), err),
+                })?;
+            // a name that isn't one file_name could produce must not lead a removal outside dir.
+            match names.iter().find(|name| file_name(name) != **name) {
+                Some(name) => Err(PassrusError::CorruptFile {
+                    reason: format!("{} lists {:?}", dir.join(MANIFEST).display(), name),
+                }),
+                None => Ok(names),
+            }
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            let empty = match fs::read_dir(dir) {
+                Ok(mut entries) => entries.next().is_none(),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => true,
+                Err(err) => return Err(err.into()),
+            };
+            if !empty {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!(
+                        "{} is not empty and has no {}, use an empty directory dedicated to these secrets",
+                        dir.display(),
+                        MANIFEST
+                    ),
+                )
+                .into());
+            }
+            Ok(BTreeSet::new())
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// write data to path through a new temp file next to it (random name, created exclusively), which then replaces path
+/// (see passrus_core::shred::replace). the temp file is shredded if anything fails.
+pub fn write_atomically(path: &Path, data: &[u8]) -> Result<(), PassrusError> {
+    let mut suffix = [0u8; 8];
+    OsRng.fill_bytes(&mut suffix);
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(".{}.{}.tmp", name, hex::encode(suffix)));
+    let res = write_secret(&tmp, data)
+        .map_err(PassrusError::from)
+        .and_then(|()| shred::replace(&tmp, path));
+    if let Err(err) = res {
+        let _ = shred::remove_file(&tmp);
+        return Err(err);
+    }
+    Ok(())
+}
+
+fn write_secret(path: &Path, secret: &[u8]) -> io::Result<()> {
+    let mut f = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?;
+    f.write_all(secret)?;
+    f.sync_all()
+}
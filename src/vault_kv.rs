@@ -0,0 +1,297 @@
+//! mirroring a container subtree to a HashiCorp Vault KV v2 mount and back, see kv-push and kv-pull.
+//!
+//! every login below the container becomes one secret, at its path below the container: work/db/main in container
+//! work is the secret db/main under the prefix. a secret holds the fields password, username and email, the latter two
+//! only when set. the KV v2 HTTP API is spoken directly with the token of a Vault entry; TLS comes from ureq.
+use passrus_core::{Container, ContainerPath, Entry, PassrusError};
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, io};
+use tracing::info;
+
+/// the fields of one secret.
+pub type Secret = BTreeMap<String, String>;
+
+/// a KV v2 mount on a Vault server.
+pub struct Kv {
+    agent: ureq::Agent,
+    /// <addr>/v1/<mount>
+    base: String,
+    token: String,
+}
+
+/// what push did.
+#[derive(Default)]
+pub struct Pushed {
+    pub written: usize,
+    pub unchanged: usize,
+    pub deleted: usize,
+}
+
+impl Kv {
+    /// the mount at addr (VAULT_ADDR, e.g. https://vault.example.com:8200), used with token.
+    pub fn new(addr: &str, mount: &str, token: String) -> Self {
+        Kv {
+            agent: ureq::Agent::new(),
+            base: format!("{}/v1/{}", addr.trim_end_matches('/'), encode_path(mount)),
+            token,
+        }
+    }
+
+    /// the names directly below path, directories ending in '/'. empty if there is nothing there.
+    fn list(&self, path: &str) -> Result<Vec<String>, PassrusError> {
+        let url = format!("{}/metadata/{}?list=true", self.base, encode_path(path));
+        let body = match self.call("GET", &url, None)? {
+            Some(body) => body,
+            None => return Ok(Vec::new()),
+        };
+        let keys = body["data"]["keys"]
+            .as_array()
+            .ok_or_else(|| unexpected(&url))?;
+        Ok(keys
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_owned)
+            .collect())
+    }
+
+    /// the paths of every secret below prefix, relative to it.
+    pub fn list_all(&self, prefix: &str) -> Result<Vec<String>, PassrusError> {
+        let mut found = Vec::new();
+        let mut pending = vec![String::new()];
+        while let Some(dir) = pending.pop() {
+            for name in self.list(&join(prefix, &dir))? {
+                match name.strip_suffix('/') {
+                    Some(sub) => pending.push(join(&dir, sub)),
+                    None => found.push(join(&dir, &name)),
+                }
+            }
+        }
+        found.sort();
+        Ok(found)
+    }
+
+    /// the current version of the secret at path, None if there is none or it was deleted.
+    pub fn read(&self, path: &str) -> Result<Option<Secret>, PassrusError> {
+        let url = format!("{}/data/{}", self.base, encode_path(path));
+        let body = match self.call("GET", &url, None)? {
+            Some(body) => body,
+            None => return Ok(None),
+        };
+        let data = body["data"]["data"]
+            .as_object()
+            .ok_or_else(|| unexpected(&url))?;
+        Ok(Some(
+            data.iter()
+                .map(|(field, value)| {
+                    let value = match value {
+                        Value::String(value) => value.clone(),
+                        value => value.to_string(),
+                    };
+                    (field.clone(), value)
+                })
+                .collect(),
+        ))
+    }
+
+    /// write secret as a new version of path.
+    pub fn write(&self, path: &str, secret: &Secret) -> Result<(), PassrusError> {
+        let url = format!("{}/data/{}", self.base, encode_path(path));
+        self.call("POST", &url, Some(json!({ "data": secret })))?;
+        Ok(())
+    }
+
+    /// delete the current version of path. older versions stay, so it can be undeleted in Vault.
+    pub fn delete(&self, path: &str) -> Result<(), PassrusError> {
+        let url = format!("{}/data/{}", self.base, encode_path(path));
+        self.call("DELETE", &url, None)?;
+        Ok(())
+    }
+
+    /// send a request, returning its JSON body if there is one. None on 404, which Vault answers for missing paths.
+    fn call(
+        &self,
+        method: &str,
+        url: &str,
+        body: Option<Value>,
+    ) -> Result<Option<Value>, PassrusError> {
+        let request = self
+            .agent
+            .request(method, url)
+            .set("X-Vault-Token", &self.token);
+        let response = match body {
+            Some(body) => request.send_json(body),
+            None => request.call(),
+        };
+        let response = match response {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => return Ok(None),
+            Err(ureq::Error::Status(status, response)) => {
+                let body: Value = response.into_json().unwrap_or(Value::Null);
+                let errors: Vec<&str> = body["errors"]
+                    .as_array()
+                    .map(|errors| errors.iter().filter_map(Value::as_str).collect())
+                    .unwrap_or_default();
+                return Err(io::Error::other(format!(
+                    "{} {}: {} {}",
+                    method,
+                    url,
+                    status,
+                    errors.join(", ")
+                ))
+                .into());
+            }
+            Err(err) => return Err(io::Error::other(err.to_string()).into()),
+        };
+        if response.status() == 204 {
+            return Ok(None);
+        }
+        Ok(Some(response.into_json()?))
+    }
+}
+
+/// the logins below container by path relative to it, aliases followed, as secrets. password returns an entry's
+/// plaintext password. other kinds of entry are skipped with a note on stderr.
+pub fn secrets(
+    root: &Container,
+    container: &Container,
+    password: &dyn Fn(&Entry) -> Result<Vec<u8>, PassrusError>,
+) -> Result<BTreeMap<String, Secret>, PassrusError> {
+    let mut secrets = BTreeMap::new();
+    collect(root, container, "", password, &mut secrets)?;
+    Ok(secrets)
+}
+
+fn collect(
+    root: &Container,
+    container: &Container,
+    dir: &str,
+    password: &dyn Fn(&Entry) -> Result<Vec<u8>, PassrusError>,
+    secrets: &mut BTreeMap<String, Secret>,
+) -> Result<(), PassrusError> {
+    for (key, entry) in &container.entries {
+        let path = join(dir, key);
+        // a '/' would nest the secret a level deeper, and "." or ".." would be resolved away by the server.
+        if key.contains('/') || key == "." || key == ".." {
+            return Err(PassrusError::InvalidEntry(format!(
+                "{} can't be a Vault path, rename it",
+                path
+            )));
+        }
+        let entry = root.follow(entry)?;
+        if !entry.kind.is_login() {
+            eprintln!(
+                "skipped {}: {} entries have no password",
+                path,
+                entry.kind.name()
+            );
+            continue;
+        }
+        let plaintext =
+            String::from_utf8(password(entry)?).map_err(|_| PassrusError::CorruptFile {
+                reason: format!("password of {} is not valid utf-8", path),
+            })?;
+        let mut secret = Secret::new();
+        secret.insert("password".to_owned(), plaintext);
+        for (field, value) in [("username", &entry.username), ("email", &entry.email)] {
+            if !value.is_empty() {
+                secret.insert(field.to_owned(), value.clone());
+            }
+        }
+        secrets.insert(path, secret);
+    }
+    for (name, child) in &container.children {
+        collect(root, child, &join(dir, name), password, secrets)?;
+    }
+    Ok(())
+}
+
+/// write secrets below prefix, skipping those already there as they are. with delete, the secrets below prefix that
+/// aren't in secrets are deleted.
+pub fn push(
+    kv: &Kv,
+    prefix: &str,
+    secrets: &BTreeMap<String, Secret>,
+    delete: bool,
+) -> Result<Pushed, PassrusError> {
+    let mut pushed = Pushed::default();
+    let remote = kv.list_all(prefix)?;
+    for (path, secret) in secrets {
+        let full = join(prefix, path);
+        if kv.read(&full)?.as_ref() == Some(secret) {
+            pushed.unchanged += 1;
+            continue;
+        }
+        info!(target:"vault_kv", "writing {}", full);
+        kv.write(&full, secret)?;
+        pushed.written += 1;
+    }
+    if delete {
+        for path in remote.iter().filter(|path| !secrets.contains_key(*path)) {
+            let full = join(prefix, path);
+            // listing still shows deleted secrets, only delete what is current.
+            if kv.read(&full)?.is_some() {
+                info!(target:"vault_kv", "deleting {}", full);
+                kv.delete(&full)?;
+                pushed.deleted += 1;
+            }
+        }
+    }
+    Ok(pushed)
+}
+
+/// the secrets below prefix by path relative to it. those without a password are skipped with a note on stderr.
+pub fn pull(kv: &Kv, prefix: &str) -> Result<BTreeMap<String, Secret>, PassrusError> {
+    let mut secrets = BTreeMap::new();
+    for path in kv.list_all(prefix)? {
+        match kv.read(&join(prefix, &path))? {
+            Some(secret) if secret.contains_key("password") => {
+                secrets.insert(path, secret);
+            }
+            Some(_) => eprintln!("skipped {}: no password field", path),
+            None => {}
+        }
+    }
+    Ok(secrets)
+}
+
+/// where a secret pulled into container goes: the container path of its directory below container, and its key.
+pub fn entry_path(
+    container: &ContainerPath,
+    path: &str,
+) -> Result<(ContainerPath, String), PassrusError> {
+    let (dir, key) = match path.rsplit_once('/') {
+        Some((dir, key)) => (dir, key),
+        None => ("", path),
+    };
+    let mut container = container.clone();
+    for name in dir.split('/').filter(|name| !name.is_empty()) {
+        container = container.join(name)?;
+    }
+    Ok((container, key.to_owned()))
+}
+
+fn join(dir: &str, name: &str) -> String {
+    match (dir, name) {
+        ("", name) => name.to_owned(),
+        (dir, "") => dir.to_owned(),
+        (dir, name) => format!("{}/{}", dir, name),
+    }
+}
+
+/// percent-encode everything in path but unreserved characters and the '/' between segments.
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            byte => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn unexpected(url: &str) -> PassrusError {
+    io::Error::other(format!("unexpected response from {}", url)).into()
+}
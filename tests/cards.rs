@@ -0,0 +1,78 @@
+mod common;
+
+use common::Scratch;
+
+const CARD: &str = "cardholder: A Person\nnumber: 4111 1111 1111 1111\nexpiry: 09/29\ncvv: 123\n";
+
+#[test]
+fn show_masks_and_reveals_sealed_fields() {
+    let s = Scratch::new();
+    s.ok(&["add-card", &s.vault, "visa"], CARD);
+
+    let shown = s.ok(&["show", &s.vault, "visa"], "");
+    assert!(shown.contains("number: •••• 1111"), "{}", shown);
+    assert!(shown.contains("cvv: •••"), "{}", shown);
+    let revealed = s.ok(&["show", "-s", &s.vault, "visa"], "");
+    assert!(
+        revealed.contains("number: 4111111111111111"),
+        "{}",
+        revealed
+    );
+    assert_eq!(
+        s.ok(&["show", "-a", "number", "-a", "cvv", &s.vault, "visa"], ""),
+        "4111111111111111\n123\n"
+    );
+    // sealed fields can't be searched.
+    assert_eq!(
+        s.run(&["search", &s.vault, "number", "4111111111111111"], "")
+            .status
+            .code(),
+        Some(1)
+    );
+}
+
+#[test]
+fn edit_keeps_sealed_fields() {
+    let s = Scratch::new();
+    s.ok(&["add-card", &s.vault, "visa"], CARD);
+    s.ok(&["edit", &s.vault, "visa"], "expiry: 10/30\n");
+    assert_eq!(
+        s.ok(
+            &["show", "-a", "number", "-a", "expiry", &s.vault, "visa"],
+            ""
+        ),
+        "4111111111111111\n10/30\n"
+    );
+}
+
+#[test]
+fn invalid_cards_are_rejected() {
+    let s = Scratch::new();
+    let out = s.run(
+        &["add-card", &s.vault, "visa"],
+        "number: 4111 1111 1111 1112\nexpiry: 09/29\n",
+    );
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(s.ok(&["ls", &s.vault], ""), "");
+}
+
+#[test]
+fn shared_cards_arrive_sealed_and_readable() {
+    let s = Scratch::new();
+    s.ok(&["add-card", &s.vault, "visa"], CARD);
+    s.ok(&["mkdir", &s.vault, "shared"], "");
+    let out = s.run(&["share", &s.vault, "visa"], "");
+    assert!(out.status.success());
+    let blob = String::from_utf8(out.stdout).unwrap();
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    let passphrase = stderr.rsplit(": ").next().unwrap().trim();
+
+    s.ok(
+        &["import-shared", &s.vault, "shared"],
+        &format!("{}{}\n", blob, passphrase),
+    );
+    assert_eq!(
+        s.ok(&["show", "-a", "cvv", &s.vault, "shared/visa"], ""),
+        "123\n"
+    );
+}
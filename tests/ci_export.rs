@@ -0,0 +1,142 @@
+mod common;
+
+use common::Scratch;
+use std::{fs, io::Read, os::unix::fs::PermissionsExt};
+
+fn export(s: &Scratch, format: &str) -> std::process::Output {
+    s.run(
+        &[
+            "ci-export",
+            &s.vault,
+            "--container",
+            "ci",
+            "--format",
+            format,
+        ],
+        "",
+    )
+}
+
+#[test]
+fn dotenv_names_variables_and_escapes_values() {
+    let s = Scratch::new();
+    s.login("ci/api.example.com", r#"a"b$c\d-Long-enough-1"#);
+    s.login("ci/return", "a\rb-Long-enough-9");
+    s.login("ci/1password", "plain-Long-enough-2");
+    let out = export(&s, "dotenv");
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "API_EXAMPLE_COM=\"a\\\"b\\$c\\\\d-Long-enough-1\"\nRETURN=\"a\\rb-Long-enough-9\"\n_1PASSWORD=\"plain-Long-enough-2\"\n"
+    );
+}
+
+#[test]
+fn github_env_delimiters_never_occur_in_the_value() {
+    let s = Scratch::new();
+    s.login("ci/token", "PASSRUS_EOF_-Long-enough-3");
+    let out = String::from_utf8(export(&s, "github-env").stdout).unwrap();
+    let mut lines = out.lines();
+    let (name, delimiter) = lines.next().unwrap().split_once("<<").unwrap();
+    assert_eq!(name, "TOKEN");
+    assert!(delimiter.starts_with("PASSRUS_EOF_") && delimiter.len() > "PASSRUS_EOF_".len());
+    assert_eq!(lines.next(), Some("PASSRUS_EOF_-Long-enough-3"));
+    assert_eq!(lines.next(), Some(delimiter));
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+fn colliding_names_are_refused() {
+    let s = Scratch::new();
+    s.login("ci/api.example.com", "first-Long-enough-4");
+    s.login("ci/api-example-com", "second-Long-enough-5");
+    let out = export(&s, "json");
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty());
+}
+
+#[test]
+fn other_kinds_are_skipped() {
+    let s = Scratch::new();
+    s.login("ci/db", "db-Long-enough-6");
+    s.ok(&["add-note", &s.vault, "ci/runbook"], "restart it");
+    let out = export(&s, "json");
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "{\n  \"DB\": \"db-Long-enough-6\"\n}\n"
+    );
+    assert!(String::from_utf8_lossy(&out.stderr).contains("skipped runbook"));
+}
+
+#[test]
+fn a_scoped_token_exports_without_the_master_password() {
+    let s = Scratch::new();
+    s.login("ci/db", "db-Long-enough-7");
+    s.login("personal/bank", "bank-Long-enough-8");
+    let file = s.path("ci.bin");
+    let token = s.ok(&["ci-token", &s.vault, "--container", "ci", &file], "");
+    let token = token.trim();
+    assert_eq!(token.len(), 64);
+
+    let args = ["ci-export", file.as_str(), "--format", "dotenv"];
+    let vars = [("PASSRUS_CI_TOKEN", token), ("PASSRUS_PASSWORD", "")];
+    let out = s.run_env(&args, "", &vars);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(out.stdout, b"DB=\"db-Long-enough-7\"\n");
+    // only the password was copied.
+    let shown = s.run_env(&["show", &file, "db"], "", &[("PASSRUS_PASSWORD", token)]);
+    assert!(shown.status.success());
+    assert!(!String::from_utf8_lossy(&shown.stdout).contains("alice"));
+
+    let wrong = [("PASSRUS_CI_TOKEN", "0".repeat(64))];
+    let wrong: Vec<(&str, &str)> = wrong.iter().map(|(k, v)| (*k, v.as_str())).collect();
+    assert_eq!(s.run_env(&args, "", &wrong).status.code(), Some(1));
+    // the token file holds nothing from outside the container, even encrypted.
+    let out = s.run_env(
+        &[
+            "ci-export",
+            &file,
+            "--container",
+            "personal",
+            "--format",
+            "json",
+        ],
+        "",
+        &[("PASSRUS_PASSWORD", token)],
+    );
+    assert_eq!(out.status.code(), Some(1));
+}
+
+#[test]
+fn a_new_token_overwrites_the_previous_file() {
+    let s = Scratch::new();
+    s.login("ci/db", "db-Long-enough-9");
+    let file = s.path("ci.bin");
+    let old_token = s.ok(&["ci-token", &s.vault, "--container", "ci", &file], "");
+    // what a copy of the blocks made before the rerun would hold.
+    let mut old = fs::File::open(&file).unwrap();
+
+    let new_token = s.ok(&["ci-token", &s.vault, "--container", "ci", &file], "");
+    let mut old_contents = Vec::new();
+    old.read_to_end(&mut old_contents).unwrap();
+    assert!(!old_contents.is_empty() && old_contents.iter().all(|b| *b == 0));
+
+    let args = ["ci-export", file.as_str(), "--format", "dotenv"];
+    let with = |token: &str| s.run_env(&args, "", &[("PASSRUS_CI_TOKEN", token.trim())]);
+    assert_eq!(with(&old_token).status.code(), Some(1));
+    assert!(with(&new_token).status.success());
+    let mode = fs::metadata(&file).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    let names: Vec<_> = fs::read_dir(&s.dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert!(names
+        .iter()
+        .all(|name| !name.to_string_lossy().ends_with(".tmp")));
+}
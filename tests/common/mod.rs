@@ -0,0 +1,95 @@
+//! drives the passrus binary against a fresh vault in a scratch directory.
+#![allow(dead_code)]
+use std::{
+    env, fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+pub const PASSWORD: &str = "correct-Horse-battery-staple-42";
+
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// a scratch directory holding vault.bin, removed on drop.
+pub struct Scratch {
+    pub dir: PathBuf,
+    pub vault: String,
+}
+
+impl Scratch {
+    /// a new directory with an empty vault in it, created by `init`.
+    pub fn new() -> Self {
+        let dir = env::temp_dir().join(format!(
+            "passrus-test-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let vault = dir.join("vault.bin").to_string_lossy().into_owned();
+        let scratch = Scratch { dir, vault };
+        scratch.ok(&["init", &scratch.vault], "");
+        scratch
+    }
+
+    pub fn path(&self, name: &str) -> String {
+        self.dir.join(name).to_string_lossy().into_owned()
+    }
+
+    /// run passrus with args, PASSRUS_PASSWORD set and stdin fed from input.
+    pub fn run(&self, args: &[&str], input: &str) -> Output {
+        self.run_env(args, input, &[])
+    }
+
+    pub fn run_env(&self, args: &[&str], input: &str, vars: &[(&str, &str)]) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_testtest"))
+            .args(args)
+            .env("PASSRUS_PASSWORD", PASSWORD)
+            .env("PASSRUS_LOG", "error")
+            .env_remove("PASSRUS_ALLOW_WEAK")
+            .envs(vars.iter().copied())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    }
+
+    /// run and check it succeeded, returning stdout.
+    pub fn ok(&self, args: &[&str], input: &str) -> String {
+        let out = self.run(args, input);
+        assert!(
+            out.status.success(),
+            "{:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&out.stderr)
+        );
+        String::from_utf8(out.stdout).unwrap()
+    }
+
+    /// add a login with the given password at entry, creating its container first.
+    pub fn login(&self, entry: &str, password: &str) {
+        if let Some((container, _)) = entry.rsplit_once('/') {
+            let _ = self.run(&["mkdir", &self.vault, container], "");
+        }
+        self.ok(
+            &["add", &self.vault, entry, "login"],
+            &format!("username: alice\npassword: {}\n", password),
+        );
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::Scratch;
+use std::{fs, os::unix::fs::PermissionsExt};
+
+#[test]
+fn mkdir_creates_missing_parents_and_refuses_existing() {
+    let s = Scratch::new();
+    s.ok(&["mkdir", &s.vault, "work/clients"], "");
+    assert_eq!(s.ok(&["ls", "-R", &s.vault], ""), "work/\n  clients/\n");
+    assert_eq!(
+        s.run(&["mkdir", &s.vault, "work"], "").status.code(),
+        Some(1)
+    );
+    assert_eq!(
+        s.run(&["mkdir", &s.vault, "a//b"], "").status.code(),
+        Some(1)
+    );
+}
+
+#[test]
+fn add_login_stores_an_encrypted_password() {
+    let s = Scratch::new();
+    s.login("work/github.com", "s3cret-Pass-word");
+    let shown = s.ok(
+        &[
+            "show",
+            "-a",
+            "username",
+            "-a",
+            "password",
+            &s.vault,
+            "work/github.com",
+        ],
+        "",
+    );
+    assert_eq!(shown, "alice\ns3cret-Pass-word\n");
+    assert!(!String::from_utf8_lossy(&fs::read(&s.vault).unwrap()).contains("s3cret"));
+}
+
+#[test]
+fn add_login_rejects_unknown_fields_and_a_missing_password() {
+    let s = Scratch::new();
+    for input in [
+        "username: alice\n",
+        "username: alice\npassword: x\ncolour: red\n",
+    ] {
+        let out = s.run(&["add", &s.vault, "github.com", "login"], input);
+        assert_eq!(out.status.code(), Some(1), "{:?}", input);
+    }
+    assert_eq!(s.ok(&["ls", &s.vault], ""), "");
+}
+
+#[test]
+fn add_login_keeps_the_password_as_given() {
+    let s = Scratch::new();
+    s.ok(
+        &["add", &s.vault, "github.com", "login"],
+        "username: alice\npassword:  padded-Pass-w0rd \n",
+    );
+    let password = s.ok(&["show", "-a", "password", &s.vault, "github.com"], "");
+    assert_eq!(password, " padded-Pass-w0rd \n");
+}
+
+#[test]
+fn add_refuses_extra_arguments() {
+    let s = Scratch::new();
+    let out = s.run(
+        &["add", &s.vault, "github.com", "login", "extra"],
+        "username: alice\npassword: s3cret-Pass-word\n",
+    );
+    assert_eq!(out.status.code(), Some(2));
+    assert_eq!(s.ok(&["ls", &s.vault], ""), "");
+}
+
+#[test]
+fn export_container_never_replaces_a_file() {
+    let s = Scratch::new();
+    s.login("clients/acme/vpn", "s3cret-Pass-word");
+    let export = |out: &str| {
+        s.run_env(
+            &["export-container", &s.vault, "clients/acme", out, "--move"],
+            "",
+            &[("PASSRUS_EXPORT_PASSWORD", "export-Pass-word-1")],
+        )
+    };
+
+    let fresh = s.path("fresh.bin");
+    assert!(export(&fresh).status.success());
+    let mode = fs::metadata(&fresh).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let exported = fs::read(&fresh).unwrap();
+    s.login("clients/acme/vpn", "s3cret-Pass-word");
+    assert_eq!(export(&fresh).status.code(), Some(1));
+    // the earlier export is untouched and --move left the container in place.
+    assert_eq!(fs::read(&fresh).unwrap(), exported);
+    assert_eq!(s.ok(&["ls", &s.vault, "clients"], ""), "acme/\n");
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use common::Scratch;
+use std::{fs, os::unix::fs::PermissionsExt};
+
+#[test]
+fn creates_a_private_vault() {
+    let s = Scratch::new();
+    let vault = s.path("new.bin");
+    let out = s.run(&["init", &vault], "");
+    assert!(out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("at most"), "{}", stderr);
+    let mode = fs::metadata(&vault).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    s.ok(&["mkdir", &vault, "work"], "");
+}
+
+#[test]
+fn refuses_a_weak_password() {
+    let s = Scratch::new();
+    let vault = s.path("new.bin");
+    let out = s.run_env(&["init", &vault], "", &[("PASSRUS_PASSWORD", "hunter2")]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("weak_password"));
+    assert!(fs::metadata(&vault).is_err());
+
+    let out = s.run_env(
+        &["init", &vault],
+        "",
+        &[("PASSRUS_PASSWORD", "hunter2"), ("PASSRUS_ALLOW_WEAK", "1")],
+    );
+    assert!(out.status.success());
+}
+
+#[test]
+fn refuses_a_common_password_with_decorations() {
+    let s = Scratch::new();
+    let vault = s.path("new.bin");
+    for pass in ["password123", "P@ssw0rd2024!"] {
+        let out = s.run_env(&["init", &vault], "", &[("PASSRUS_PASSWORD", pass)]);
+        assert!(!out.status.success(), "{}", pass);
+        assert!(fs::metadata(&vault).is_err());
+    }
+}
+
+#[test]
+fn refuses_an_existing_file() {
+    let s = Scratch::new();
+    let out = s.run(&["init", &s.vault], "");
+    assert!(!out.status.success());
+    s.ok(&["mkdir", &s.vault, "work"], "");
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use common::{Scratch, PASSWORD};
+use passrus_core::{subtree, Container, ContainerPath, Entry};
+use std::{fs, os::unix::fs::PermissionsExt, process::Command};
+
+fn gpg(home: &str, args: &[&str]) -> Option<Vec<u8>> {
+    let out = Command::new("gpg")
+        .env("GNUPGHOME", home)
+        .args(["--batch", "--quiet"])
+        .args(args)
+        .output()
+        .ok()?;
+    out.status.success().then_some(out.stdout)
+}
+
+#[test]
+fn exports_entries_readable_by_pass() {
+    let s = Scratch::new();
+    let home = s.path("gnupg");
+    fs::create_dir(&home).unwrap();
+    fs::set_permissions(&home, fs::Permissions::from_mode(0o700)).unwrap();
+    let key = [
+        "--passphrase",
+        "",
+        "--quick-gen-key",
+        "store@example.com",
+        "default",
+        "default",
+        "never",
+    ];
+    if gpg(&home, &key).is_none() {
+        eprintln!("skipped: gpg is not available");
+        return;
+    }
+    s.login("web/github.com", "gh-Long-enough-1");
+    let store = s.path("store");
+    let out = s.run_env(
+        &[
+            "export-pass",
+            &s.vault,
+            &store,
+            "--gpg",
+            "store@example.com",
+        ],
+        "",
+        &[("GNUPGHOME", &home)],
+    );
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let file = format!("{store}/web/github.com.gpg");
+    let plaintext = gpg(&home, &["--decrypt", &file]).unwrap();
+    let plaintext = String::from_utf8(plaintext).unwrap();
+    let _ = Command::new("gpgconf")
+        .env("GNUPGHOME", &home)
+        .args(["--kill", "gpg-agent"])
+        .status();
+    assert_eq!(
+        plaintext,
+        "gh-Long-enough-1\nlogin: alice\nurl: github.com\n"
+    );
+    let recipients = format!("{store}/.gpg-id");
+    assert_eq!(
+        fs::read_to_string(&recipients).unwrap(),
+        "store@example.com\n"
+    );
+    let mode = fs::metadata(&recipients).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+}
+
+#[test]
+fn keys_with_the_same_file_name_are_refused() {
+    let s = Scratch::new();
+    s.login("web/.env", "env-Long-enough-1");
+    s.login("web/_env", "env-Long-enough-2");
+    let store = s.path("store");
+    let out = s.run(&["export-pass", &s.vault, &store, "--gpg", "nobody"], "");
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("would both be exported"));
+    assert!(fs::metadata(&store).is_err());
+}
+
+#[test]
+fn passwords_with_a_newline_are_refused() {
+    let s = Scratch::new();
+    // the cli reads passwords a line at a time, so bring one with a newline in through an exported subtree.
+    let mut root = Container::new("");
+    let mut imported = Container::new("imported");
+    let mut entry = Entry::new("alice", Vec::new(), "", "multi");
+    entry.set_password(b"first\nsecond", PASSWORD, 0).unwrap();
+    imported.add_entry(entry);
+    root.add_child(imported).unwrap();
+    let data = subtree::export(
+        &root,
+        &ContainerPath::parse("imported").unwrap(),
+        PASSWORD,
+        "transfer",
+    )
+    .unwrap();
+    let file = s.path("imported.bin");
+    fs::write(&file, data).unwrap();
+    let out = s.run_env(
+        &["import-container", &s.vault, &file, ""],
+        "",
+        &[("PASSRUS_EXPORT_PASSWORD", "transfer")],
+    );
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let store = s.path("store");
+    let out = s.run(&["export-pass", &s.vault, &store, "--gpg", "nobody"], "");
+    assert!(!out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("imported/multi") && stderr.contains("newline"));
+    // the recipients file was written before the entry failed, the partial store is removed again.
+    assert!(fs::metadata(&store).is_err());
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::Scratch;
+
+fn add(s: &Scratch, entry: &str, password: &str, vars: &[(&str, &str)]) -> std::process::Output {
+    s.run_env(
+        &["add", &s.vault, entry, "login"],
+        &format!("username: alice\npassword: {}\n", password),
+        vars,
+    )
+}
+
+#[test]
+fn an_enforced_policy_refuses_weak_passwords_on_add() {
+    let s = Scratch::new();
+    s.ok(&["mkdir", &s.vault, "work"], "");
+    s.ok(
+        &["set-policy", &s.vault, "work", "min-length=20", "enforce"],
+        "",
+    );
+
+    let out = add(&s, "work/short", "only-Fifteen-15", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("shorter than 20 characters"));
+    assert_eq!(s.ok(&["ls", &s.vault, "work"], ""), "");
+
+    let out = add(
+        &s,
+        "work/short",
+        "only-Fifteen-15",
+        &[("PASSRUS_ALLOW_WEAK", "1")],
+    );
+    assert!(out.status.success());
+    assert_eq!(s.ok(&["ls", &s.vault, "work"], ""), "short\n");
+}
+
+#[test]
+fn a_policy_that_is_not_enforced_warns_and_can_be_removed() {
+    let s = Scratch::new();
+    s.ok(&["mkdir", &s.vault, "work"], "");
+    s.ok(&["set-policy", &s.vault, "work", "symbol"], "");
+    let out = add(&s, "work/a", "NoSymbolsHere42", &[]);
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("no symbol"));
+
+    s.ok(&["set-policy", &s.vault, "work"], "");
+    let out = add(&s, "work/b", "NoSymbolsHere42", &[]);
+    assert!(out.status.success());
+    assert!(!String::from_utf8_lossy(&out.stderr).contains("no symbol"));
+}
+
+#[test]
+fn set_policy_rejects_unknown_rules_and_containers() {
+    let s = Scratch::new();
+    s.ok(&["mkdir", &s.vault, "work"], "");
+    assert_eq!(
+        s.run(&["set-policy", &s.vault, "work", "min-length=x"], "")
+            .status
+            .code(),
+        Some(1)
+    );
+    assert_eq!(
+        s.run(&["set-policy", &s.vault, "home", "digit"], "")
+            .status
+            .code(),
+        Some(1)
+    );
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::Scratch;
+
+#[test]
+fn codes_are_handed_out_in_order_and_once() {
+    let s = Scratch::new();
+    s.ok(
+        &["recovery-codes", &s.vault, "github-recovery"],
+        "aaaa-1111 bbbb-2222\ncccc-3333\n",
+    );
+    let show = |reveal: bool| {
+        let mut args = vec!["show", s.vault.as_str(), "github-recovery"];
+        if reveal {
+            args.insert(1, "-s");
+        }
+        s.ok(&args, "")
+    };
+    assert!(show(false).contains("remaining: 3 of 3"));
+    assert!(!show(false).contains("aaaa-1111"));
+
+    let used = s.run(&["use-recovery-code", &s.vault, "github-recovery"], "");
+    assert_eq!(String::from_utf8(used.stdout).unwrap(), "aaaa-1111\n");
+    assert!(String::from_utf8(used.stderr)
+        .unwrap()
+        .contains("2 recovery codes left"));
+    assert_eq!(
+        s.ok(&["use-recovery-code", &s.vault, "github-recovery"], ""),
+        "bbbb-2222\n"
+    );
+    let revealed = show(true);
+    assert!(revealed.contains("remaining: 1 of 3"), "{}", revealed);
+    assert!(revealed.contains("code: cccc-3333") && !revealed.contains("aaaa-1111"));
+
+    s.ok(&["use-recovery-code", &s.vault, "github-recovery"], "");
+    let out = s.run(&["use-recovery-code", &s.vault, "github-recovery"], "");
+    assert_eq!(out.status.code(), Some(1));
+}
+
+#[test]
+fn new_codes_replace_the_old_ones() {
+    let s = Scratch::new();
+    s.ok(&["recovery-codes", &s.vault, "site"], "old-1 old-2\n");
+    s.ok(&["use-recovery-code", &s.vault, "site"], "");
+    s.ok(&["recovery-codes", &s.vault, "site"], "new-1\n");
+    assert_eq!(
+        s.ok(&["use-recovery-code", &s.vault, "site"], ""),
+        "new-1\n"
+    );
+}
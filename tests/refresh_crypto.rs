@@ -0,0 +1,17 @@
+mod common;
+
+use common::Scratch;
+
+#[test]
+fn max_age_is_in_days_and_too_large_is_bad_usage() {
+    let s = Scratch::new();
+    s.ok(&["refresh-crypto", &s.vault], "");
+    let skipped = s.ok(&["refresh-crypto", &s.vault, "--max-age", "30"], "");
+    assert!(skipped.contains("not due yet"), "{}", skipped);
+
+    // the first is the smallest number of days whose seconds don't fit in a u64.
+    for days in ["213503982334602", "-1", "soon"] {
+        let out = s.run(&["refresh-crypto", &s.vault, "--max-age", days], "");
+        assert_eq!(out.status.code(), Some(2), "{}", days);
+    }
+}
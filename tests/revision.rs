@@ -0,0 +1,93 @@
+mod common;
+
+use common::Scratch;
+
+fn revision(s: &Scratch, entry: &str) -> String {
+    s.ok(&["show", "-a", "revision", &s.vault, entry], "")
+        .trim()
+        .to_owned()
+}
+
+fn assert_conflict(out: std::process::Output) {
+    assert!(!out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("changed meanwhile"), "{}", stderr);
+}
+
+#[test]
+fn edits_bump_only_the_changed_entry() {
+    let s = Scratch::new();
+    s.login("web/github.com", "gh-Long-enough-1");
+    s.login("web/gitlab.com", "gl-Long-enough-2");
+    assert_eq!(revision(&s, "web/github.com"), "0");
+
+    s.ok(&["edit", &s.vault, "web/github.com"], "username: bob\n");
+    assert_eq!(revision(&s, "web/github.com"), "1");
+    assert_eq!(revision(&s, "web/gitlab.com"), "0");
+
+    // a save that changes nothing leaves the revisions alone.
+    s.ok(&["set-match", &s.vault, "web/gitlab.com"], "");
+    assert_eq!(revision(&s, "web/gitlab.com"), "0");
+}
+
+#[test]
+fn a_stale_revision_is_a_conflict() {
+    let s = Scratch::new();
+    s.login("web/github.com", "gh-Long-enough-1");
+    let read = revision(&s, "web/github.com");
+    s.ok(
+        &["edit", &s.vault, "web/github.com", "--if-revision", &read],
+        "username: bob\n",
+    );
+
+    // someone else saved since read, so every guarded command refuses.
+    let stale = ["--if-revision", read.as_str()];
+    let edit = s.run(
+        &[&["edit", &s.vault, "web/github.com"][..], &stale].concat(),
+        "username: carol\n",
+    );
+    assert_conflict(edit);
+    let rotate = s.run(
+        &[&["rotate", &s.vault, "web/github.com"][..], &stale].concat(),
+        "gh-Long-enough-3\n",
+    );
+    assert_conflict(rotate);
+    let rotate_every = s.run(
+        &[
+            &["rotate-every", &s.vault, "web/github.com", "90"][..],
+            &stale,
+        ]
+        .concat(),
+        "",
+    );
+    assert_conflict(rotate_every);
+    let set_match = s.run(
+        &[
+            &["set-match", &s.vault, "web/github.com", "never"][..],
+            &stale,
+        ]
+        .concat(),
+        "",
+    );
+    assert_conflict(set_match);
+
+    assert_eq!(
+        s.ok(&["show", "-a", "username", &s.vault, "web/github.com"], "")
+            .trim(),
+        "bob"
+    );
+    assert_eq!(revision(&s, "web/github.com"), "1");
+}
+
+#[test]
+fn the_current_revision_is_accepted() {
+    let s = Scratch::new();
+    s.login("web/github.com", "gh-Long-enough-1");
+    s.ok(&["edit", &s.vault, "web/github.com"], "username: bob\n");
+    let read = revision(&s, "web/github.com");
+    s.ok(
+        &["rotate", &s.vault, "web/github.com", "--if-revision", &read],
+        "gh-Long-enough-3\n",
+    );
+    assert_eq!(revision(&s, "web/github.com"), "2");
+}
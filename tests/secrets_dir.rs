@@ -0,0 +1,83 @@
+mod common;
+
+use common::Scratch;
+use std::{fs, os::unix::fs::PermissionsExt};
+
+fn materialize(s: &Scratch, dir: &str) -> std::process::Output {
+    s.run(&["secrets-dir", &s.vault, "--container", "srv", dir], "")
+}
+
+#[test]
+fn writes_one_private_file_per_entry() {
+    let s = Scratch::new();
+    s.login("srv/db", "db-Long-enough-1");
+    s.login("srv/.env", "env-Long-enough-2");
+    let dir = s.path("secrets");
+    assert!(materialize(&s, &dir).status.success());
+
+    assert_eq!(
+        fs::read_to_string(format!("{dir}/db")).unwrap(),
+        "db-Long-enough-1"
+    );
+    assert_eq!(
+        fs::read_to_string(format!("{dir}/_env")).unwrap(),
+        "env-Long-enough-2"
+    );
+    let mode = fs::metadata(format!("{dir}/db"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o600);
+    let mode = fs::metadata(&dir).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o700);
+}
+
+#[test]
+fn refuses_a_directory_it_did_not_write() {
+    let s = Scratch::new();
+    s.login("srv/db", "db-Long-enough-1");
+    let dir = s.path("secrets");
+    fs::create_dir(&dir).unwrap();
+    fs::write(format!("{dir}/notes.txt"), "keep me").unwrap();
+
+    assert!(!materialize(&s, &dir).status.success());
+    assert_eq!(
+        fs::read_to_string(format!("{dir}/notes.txt")).unwrap(),
+        "keep me"
+    );
+    assert!(fs::metadata(format!("{dir}/db")).is_err());
+}
+
+#[test]
+fn removes_only_its_own_stale_secrets() {
+    let s = Scratch::new();
+    s.login("srv/db", "db-Long-enough-1");
+    s.login("srv/api", "api-Long-enough-2");
+    let dir = s.path("secrets");
+    assert!(materialize(&s, &dir).status.success());
+    fs::write(format!("{dir}/added-by-hand"), "keep me").unwrap();
+
+    s.ok(&["mv", &s.vault, "srv/api", "srv/api2"], "");
+    assert!(materialize(&s, &dir).status.success());
+    assert!(fs::metadata(format!("{dir}/api")).is_err());
+    assert_eq!(
+        fs::read_to_string(format!("{dir}/api2")).unwrap(),
+        "api-Long-enough-2"
+    );
+    assert_eq!(
+        fs::read_to_string(format!("{dir}/added-by-hand")).unwrap(),
+        "keep me"
+    );
+}
+
+#[test]
+fn keys_with_the_same_file_name_are_refused() {
+    let s = Scratch::new();
+    s.login("srv/.env", "env-Long-enough-1");
+    s.login("srv/_env", "env-Long-enough-2");
+    let dir = s.path("secrets");
+    let out = materialize(&s, &dir);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("_env"));
+    assert!(fs::metadata(&dir).is_err());
+}
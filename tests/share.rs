@@ -0,0 +1,71 @@
+mod common;
+
+use common::Scratch;
+
+/// share entry from s, returning the blob and passphrase lines import-shared reads.
+fn share(s: &Scratch, entry: &str) -> String {
+    let out = s.run(&["share", &s.vault, entry], "");
+    assert!(out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    let passphrase = stderr.trim().rsplit(' ').next().unwrap();
+    format!("{}{}\n", String::from_utf8_lossy(&out.stdout), passphrase)
+}
+
+#[test]
+fn import_drops_the_senders_links() {
+    let sender = Scratch::new();
+    sender.login("personal/mail.example.com", "mail-Long-enough-1");
+    sender.login("work/github.com", "gh-Long-enough-2");
+    sender.ok(
+        &[
+            "link",
+            &sender.vault,
+            "work/github.com",
+            "recovery",
+            "personal/mail.example.com",
+        ],
+        "",
+    );
+    let input = share(&sender, "work/github.com");
+
+    let receiver = Scratch::new();
+    receiver.ok(&["mkdir", &receiver.vault, "inbox"], "");
+    receiver.ok(&["import-shared", &receiver.vault, "inbox"], &input);
+    let shown = receiver.ok(&["show", &receiver.vault, "inbox/github.com"], "");
+    assert!(!shown.contains("recovery"), "{}", shown);
+    let password = receiver.ok(
+        &[
+            "show",
+            "-a",
+            "password",
+            &receiver.vault,
+            "inbox/github.com",
+        ],
+        "",
+    );
+    assert_eq!(password.trim(), "gh-Long-enough-2");
+}
+
+#[test]
+fn import_refuses_to_replace_an_entry() {
+    let sender = Scratch::new();
+    sender.login("work/github.com", "new-Long-enough-1");
+    let input = share(&sender, "work/github.com");
+
+    let receiver = Scratch::new();
+    receiver.login("inbox/github.com", "old-Long-enough-2");
+    let out = receiver.run(&["import-shared", &receiver.vault, "inbox"], &input);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("already exists"));
+    let password = receiver.ok(
+        &[
+            "show",
+            "-a",
+            "password",
+            &receiver.vault,
+            "inbox/github.com",
+        ],
+        "",
+    );
+    assert_eq!(password.trim(), "old-Long-enough-2");
+}
@@ -0,0 +1,267 @@
+#![cfg(feature = "vault-kv")]
+mod common;
+
+use common::Scratch;
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+const TOKEN: &str = "hvs.test-token";
+
+/// secrets by path below the mount, and whether their current version is deleted.
+type Store = Arc<Mutex<BTreeMap<String, (Value, bool)>>>;
+
+/// a KV v2 mount called secret on a local HTTP server, just enough of it for kv-push and kv-pull. returns its address.
+fn serve(store: Store) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut token = String::new();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                let (name, value) = line.split_once(':').unwrap();
+                match name.to_ascii_lowercase().as_str() {
+                    "x-vault-token" => token = value.trim().to_owned(),
+                    "content-length" => length = value.trim().parse().unwrap(),
+                    _ => {}
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let mut parts = request_line.split_whitespace();
+            let method = parts.next().unwrap().to_owned();
+            let target = parts.next().unwrap().to_owned();
+            let (status, reply) = if token == TOKEN {
+                handle(&store, &method, &target, &body)
+            } else {
+                (403, Some(json!({ "errors": ["permission denied"] })))
+            };
+            let reply = reply.map(|reply| reply.to_string()).unwrap_or_default();
+            write!(
+                stream,
+                "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                reply.len(),
+                reply
+            )
+            .unwrap();
+        }
+    });
+    addr
+}
+
+fn handle(store: &Store, method: &str, target: &str, body: &[u8]) -> (u16, Option<Value>) {
+    let mut store = store.lock().unwrap();
+    let not_found = (404, Some(json!({ "errors": [] })));
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let path = decode(path);
+    if let Some(dir) = path.strip_prefix("/v1/secret/metadata/") {
+        assert_eq!(query, "list=true");
+        let dir = match dir {
+            "" => String::new(),
+            dir => format!("{}/", dir.trim_end_matches('/')),
+        };
+        let mut keys: Vec<String> = store
+            .keys()
+            .filter_map(|key| key.strip_prefix(&dir))
+            .map(|rest| match rest.split_once('/') {
+                Some((sub, _)) => format!("{}/", sub),
+                None => rest.to_owned(),
+            })
+            .collect();
+        keys.dedup();
+        return match keys.is_empty() {
+            true => not_found,
+            false => (200, Some(json!({ "data": { "keys": keys } }))),
+        };
+    }
+    let path = path.strip_prefix("/v1/secret/data/").unwrap().to_owned();
+    match method {
+        "GET" => match store.get(&path) {
+            Some((data, false)) => (
+                200,
+                Some(json!({ "data": { "data": data, "metadata": {} } })),
+            ),
+            _ => not_found,
+        },
+        "POST" | "PUT" => {
+            let body: Value = serde_json::from_slice(body).unwrap();
+            store.insert(path, (body["data"].clone(), false));
+            (200, Some(json!({ "data": { "version": 1 } })))
+        }
+        "DELETE" => {
+            if let Some(secret) = store.get_mut(&path) {
+                secret.1 = true;
+            }
+            (204, None)
+        }
+        _ => (405, None),
+    }
+}
+
+fn decode(path: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut rest = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&tail[..2]).unwrap(), 16).unwrap());
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).unwrap()
+}
+
+fn kv(s: &Scratch, addr: &str, args: &[&str]) -> std::process::Output {
+    s.run_env(args, "", &[("VAULT_ADDR", addr)])
+}
+
+fn stdout(out: &std::process::Output) -> String {
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8_lossy(&out.stdout).into_owned()
+}
+
+/// a scratch vault holding the Vault token as ops/vault.
+fn scratch() -> Scratch {
+    let s = Scratch::new();
+    s.login("ops/vault", TOKEN);
+    s
+}
+
+#[test]
+fn push_writes_the_logins_below_the_prefix() {
+    let store = Store::default();
+    let addr = serve(store.clone());
+    let s = scratch();
+    s.login("srv/db", "db-Long-enough-1");
+    s.login("srv/payments/stripe", "stripe-Long-enough-2");
+    s.ok(&["add-note", &s.vault, "srv/runbook"], "restart it");
+
+    let push = [
+        "kv-push",
+        &s.vault,
+        "srv",
+        "secret/app",
+        "--token-entry",
+        "ops/vault",
+    ];
+    let out = kv(&s, &addr, &push);
+    assert!(stdout(&out).contains("pushed 2 secrets to secret/app (0 unchanged, 0 deleted)"));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("skipped runbook"));
+    assert_eq!(
+        store.lock().unwrap()["app/db"].0,
+        json!({ "password": "db-Long-enough-1", "username": "alice" })
+    );
+    assert_eq!(
+        store.lock().unwrap()["app/payments/stripe"].0["password"],
+        "stripe-Long-enough-2"
+    );
+
+    // nothing changed, nothing is written again. a secret that isn't in the container stays without --delete.
+    store
+        .lock()
+        .unwrap()
+        .insert("app/old".to_owned(), (json!({ "password": "x" }), false));
+    let out = kv(&s, &addr, &push);
+    assert!(stdout(&out).contains("pushed 0 secrets to secret/app (2 unchanged, 0 deleted)"));
+    assert!(!store.lock().unwrap()["app/old"].1);
+
+    let out = kv(&s, &addr, &[&push[..], &["--delete"]].concat());
+    assert!(stdout(&out).contains("(2 unchanged, 1 deleted)"));
+    assert!(store.lock().unwrap()["app/old"].1);
+}
+
+#[test]
+fn pull_adds_and_updates_logins() {
+    let store = Store::default();
+    store.lock().unwrap().extend([
+        (
+            "app/db".to_owned(),
+            (
+                json!({ "password": "db-Long-enough-3", "username": "bob" }),
+                false,
+            ),
+        ),
+        (
+            "app/cache/redis".to_owned(),
+            (json!({ "password": "redis-Long-enough-4" }), false),
+        ),
+        ("app/config".to_owned(), (json!({ "region": "eu" }), false)),
+    ]);
+    let addr = serve(store.clone());
+    let s = scratch();
+    s.login("srv/db", "db-Long-enough-1");
+    s.login("srv/local", "local-Long-enough-2");
+
+    let pull = [
+        "kv-pull",
+        &s.vault,
+        "srv",
+        "secret/app",
+        "--token-entry",
+        "ops/vault",
+    ];
+    let out = kv(&s, &addr, &pull);
+    assert!(stdout(&out).contains("pulled 2 secrets from secret/app (0 unchanged)"));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("skipped config: no password field"));
+    let show = |entry: &str, field: &str| {
+        s.ok(&["show", "-a", field, &s.vault, entry], "")
+            .trim()
+            .to_owned()
+    };
+    assert_eq!(show("srv/db", "password"), "db-Long-enough-3");
+    assert_eq!(show("srv/db", "username"), "bob");
+    assert_eq!(show("srv/cache/redis", "password"), "redis-Long-enough-4");
+    assert_eq!(show("srv/local", "password"), "local-Long-enough-2");
+
+    let out = kv(&s, &addr, &pull);
+    assert!(stdout(&out).contains("pulled 0 secrets from secret/app (2 unchanged)"));
+}
+
+#[test]
+fn a_rejected_token_changes_nothing() {
+    let store = Store::default();
+    let addr = serve(store.clone());
+    let s = Scratch::new();
+    s.login("ops/vault", "not-the-Token-1");
+    s.login("srv/db", "db-Long-enough-1");
+
+    let out = kv(
+        &s,
+        &addr,
+        &[
+            "kv-push",
+            &s.vault,
+            "srv",
+            "secret",
+            "--token-entry",
+            "ops/vault",
+        ],
+    );
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("permission denied"));
+    assert!(store.lock().unwrap().is_empty());
+}